@@ -21,6 +21,8 @@ pub trait Field:
     + Debug
     + Default
     + PartialEq
+    + Send
+    + Sync
     + From<u32>
     + Neg<Output = Self>
     + Add<Output = Self>
@@ -47,12 +49,20 @@ pub trait Field:
     /// size required to store the data
     const SIZE: usize;
 
+    /// number of bits of the field order, which bounds the soundness of a challenge drawn from
+    /// the field; for a packed field, of the field of a lane
+    const FIELD_SIZE: usize;
+
     /// Inverse of 2
     const INV_2: Self;
 
     /// type of the base field, can be itself
     type BaseField: Field + FieldSerde;
 
+    /// type of the verifier challenges, an extension of the base field large enough for
+    /// soundness; can be itself
+    type ChallengeField: Field<BaseField = Self::BaseField> + FieldSerde + From<Self>;
+
     // ====================================
     // constants
     // ====================================
@@ -141,6 +151,14 @@ pub trait FieldSerde {
     /// deserialize bytes into field
     fn deserialize_from(buffer: &[u8]) -> Self;
 
+    /// deserialize bytes into field, or `None` if they do not encode an element
+    fn try_deserialize_from(buffer: &[u8]) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(Self::deserialize_from(buffer))
+    }
+
     /// deserialize bytes into field following ecc format
     fn deserialize_from_ecc_format(_bytes: &[u8; 32]) -> Self
     where
@@ -15,12 +15,18 @@ impl Field for Fr {
     /// size required to store the data
     const SIZE: usize = size_of::<Fr>();
 
+    /// number of bits of the field order
+    const FIELD_SIZE: usize = 254;
+
     /// Inverse of 2
     const INV_2: Self = Fr::TWO_INV;
 
     /// type of the base field, can be itself
     type BaseField = Self;
 
+    /// type of the challenge field
+    type ChallengeField = Self;
+
     // ====================================
     // constants
     // ====================================
@@ -126,6 +132,10 @@ impl FieldSerde for Fr {
         Fr::from_bytes(buffer[..Fr::SIZE].try_into().unwrap_or(&[0; Fr::SIZE])).unwrap()
     }
 
+    fn try_deserialize_from(buffer: &[u8]) -> Option<Self> {
+        Fr::from_bytes(buffer.get(..Fr::SIZE)?.try_into().ok()?).into()
+    }
+
     fn deserialize_from_ecc_format(bytes: &[u8; 32]) -> Self {
         Fr::deserialize_from(bytes) // same as deserialize_from
     }
@@ -13,7 +13,7 @@ pub mod m31_neon;
 pub use m31_neon::PackedM31;
 use rand::RngCore;
 
-use crate::{Field, FieldSerde};
+use crate::{Field, FieldSerde, M31Ext3};
 use std::{
     iter::{Product, Sum},
     mem::size_of,
@@ -56,6 +56,13 @@ impl FieldSerde for M31 {
         M31 { v: v as u32 }
     }
 
+    /// Rejects the values that do not fit in 31 bits; the modulus is accepted as another
+    /// encoding of zero, as produced by `from_uniform_bytes`.
+    fn try_deserialize_from(buffer: &[u8]) -> Option<Self> {
+        let v = u32::from_ne_bytes(buffer.get(..M31::SIZE)?.try_into().ok()?);
+        (v <= M31_MOD as u32).then_some(M31 { v })
+    }
+
     #[inline(always)]
     fn deserialize_from_ecc_format(bytes: &[u8; 32]) -> Self {
         for (i, v) in bytes.iter().enumerate().skip(4).take(28) {
@@ -84,10 +91,15 @@ impl Field for M31 {
 
     const SIZE: usize = size_of::<u32>();
 
+    const FIELD_SIZE: usize = 31;
+
     const INV_2: M31 = M31 { v: 1 << 30 };
 
     type BaseField = M31;
 
+    /// 31-bit challenges are not enough for soundness
+    type ChallengeField = M31Ext3;
+
     #[inline(always)]
     fn zero() -> Self {
         M31 { v: 0 }
@@ -41,12 +41,31 @@ pub struct PackedM31 {
 
 impl PackedM31 {
     pub const SIZE: usize = size_of::<PackedDataType>();
+    pub const PACK_SIZE: usize = M31_PACK_SIZE;
+
     #[inline(always)]
     pub fn pack_full(x: M31) -> PackedM31 {
         PackedM31 {
             v: unsafe { _mm256_set1_epi32(x.v as i32) },
         }
     }
+
+    #[inline(always)]
+    pub fn pack(xs: &[M31; M31_PACK_SIZE]) -> PackedM31 {
+        let data = xs.map(|x| x.v);
+        PackedM31 {
+            v: unsafe { _mm256_loadu_si256(data.as_ptr() as *const PackedDataType) },
+        }
+    }
+
+    #[inline(always)]
+    pub fn unpack(&self) -> [M31; M31_PACK_SIZE] {
+        let mut data = [0u32; M31_PACK_SIZE];
+        unsafe {
+            _mm256_storeu_si256(data.as_mut_ptr() as *mut PackedDataType, self.v);
+        }
+        data.map(|v| M31 { v })
+    }
 }
 
 impl FieldSerde for PackedM31 {
@@ -61,7 +80,7 @@ impl FieldSerde for PackedM31 {
     /// deserialize bytes into field
     fn deserialize_from(buffer: &[u8]) -> Self {
         let mut data = [0; 32];
-        data.copy_from_slice(buffer);
+        data.copy_from_slice(&buffer[..32]);
         unsafe {
             PackedM31 {
                 v: transmute::<[u8; 32], PackedDataType>(data),
@@ -75,10 +94,15 @@ impl Field for PackedM31 {
 
     const SIZE: usize = size_of::<PackedDataType>();
 
+    const FIELD_SIZE: usize = 31;
+
     const INV_2: Self = Self { v: PACKED_INV_2 };
 
     type BaseField = M31;
 
+    /// challenges are broadcast to all lanes
+    type ChallengeField = Self;
+
     #[inline(always)]
     fn zero() -> Self {
         PackedM31 {
@@ -134,23 +158,45 @@ impl Field for PackedM31 {
         }
     }
 
-    fn exp(&self, _exponent: usize) -> Self {
-        todo!()
+    fn exp(&self, exponent: usize) -> Self {
+        let mut e = exponent;
+        let mut res = Self::one();
+        let mut t = *self;
+        while e != 0 {
+            if e & 1 == 1 {
+                res *= t;
+            }
+            t = t * t;
+            e >>= 1;
+        }
+        res
     }
 
+    /// lane-wise inverse; None if any lane is zero, in either of its representations 0 and
+    /// `M31_MOD`
     #[inline(always)]
     fn inv(&self) -> Option<Self> {
-        unimplemented!()
+        let has_zero_lane = unsafe {
+            let zero_cmp = _mm256_or_si256(
+                _mm256_cmpeq_epi32(self.v, PACKED_0),
+                _mm256_cmpeq_epi32(self.v, PACKED_MOD),
+            );
+            _mm256_movemask_epi8(zero_cmp) != 0
+        };
+        if has_zero_lane {
+            return None;
+        }
+        Some(self.exp(M31_MOD as usize - 2))
     }
 
     #[inline(always)]
-    fn add_base_elem(&self, _rhs: &Self::BaseField) -> Self {
-        unimplemented!()
+    fn add_base_elem(&self, rhs: &Self::BaseField) -> Self {
+        *self + PackedM31::pack_full(*rhs)
     }
 
     #[inline(always)]
-    fn add_assign_base_elem(&mut self, _rhs: &Self::BaseField) {
-        unimplemented!()
+    fn add_assign_base_elem(&mut self, rhs: &Self::BaseField) {
+        *self = self.add_base_elem(rhs);
     }
 
     #[inline(always)]
@@ -163,11 +209,14 @@ impl Field for PackedM31 {
         *self = *self * rhs;
     }
 
+    /// the first lane
     fn as_u32_unchecked(&self) -> u32 {
-        unimplemented!("self is a vector, cannot convert to u32")
+        self.unpack()[0].v
     }
-    fn from_uniform_bytes(_bytes: &[u8; 32]) -> Self {
-        unimplemented!(" cannot convert 32 bytes into a vectorized M31")
+
+    /// the same element in all lanes, so that challenges are shared by all instances
+    fn from_uniform_bytes(bytes: &[u8; 32]) -> Self {
+        PackedM31::pack_full(M31::from_uniform_bytes(bytes))
     }
 }
 
@@ -6,7 +6,7 @@ use std::{
     ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
-use crate::{Field, M31, M31_MOD};
+use crate::{Field, FieldSerde, M31, M31_MOD};
 
 type PackedDataType = uint32x4_t;
 pub(super) const M31_PACK_SIZE: usize = 4;
@@ -30,12 +30,53 @@ pub struct PackedM31 {
 }
 
 impl PackedM31 {
+    pub const SIZE: usize = size_of::<PackedDataType>();
+    pub const PACK_SIZE: usize = M31_PACK_SIZE;
+
     #[inline(always)]
     pub fn pack_full(x: M31) -> PackedM31 {
         PackedM31 {
             v: unsafe { vdupq_n_u32(x.v) },
         }
     }
+
+    #[inline(always)]
+    pub fn pack(xs: &[M31; M31_PACK_SIZE]) -> PackedM31 {
+        let data = xs.map(|x| x.v);
+        PackedM31 {
+            v: unsafe { vld1q_u32(data.as_ptr()) },
+        }
+    }
+
+    #[inline(always)]
+    pub fn unpack(&self) -> [M31; M31_PACK_SIZE] {
+        let mut data = [0u32; M31_PACK_SIZE];
+        unsafe {
+            vst1q_u32(data.as_mut_ptr(), self.v);
+        }
+        data.map(|v| M31 { v })
+    }
+}
+
+impl FieldSerde for PackedM31 {
+    /// serialize self into bytes
+    fn serialize_into(&self, buffer: &mut [u8]) {
+        unsafe {
+            let data = transmute::<PackedDataType, [u8; 16]>(self.v);
+            buffer[..16].copy_from_slice(&data);
+        }
+    }
+
+    /// deserialize bytes into field
+    fn deserialize_from(buffer: &[u8]) -> Self {
+        let mut data = [0; 16];
+        data.copy_from_slice(&buffer[..16]);
+        unsafe {
+            PackedM31 {
+                v: transmute::<[u8; 16], PackedDataType>(data),
+            }
+        }
+    }
 }
 
 impl Field for PackedM31 {
@@ -43,10 +84,15 @@ impl Field for PackedM31 {
 
     const SIZE: usize = size_of::<PackedDataType>();
 
+    const FIELD_SIZE: usize = 31;
+
     const INV_2: Self = Self { v: PACKED_INV_2 };
 
     type BaseField = M31;
 
+    /// challenges are broadcast to all lanes
+    type ChallengeField = Self;
+
     #[inline(always)]
     fn zero() -> Self {
         PackedM31 {
@@ -96,23 +142,42 @@ impl Field for PackedM31 {
         }
     }
 
-    fn exp(&self, _exponent: &Self) -> Self {
-        todo!()
+    fn exp(&self, exponent: usize) -> Self {
+        let mut e = exponent;
+        let mut res = Self::one();
+        let mut t = *self;
+        while e != 0 {
+            if e & 1 == 1 {
+                res *= t;
+            }
+            t = t * t;
+            e >>= 1;
+        }
+        res
     }
 
+    /// lane-wise inverse; None if any lane is zero, in either of its representations 0 and
+    /// `M31_MOD`
     #[inline(always)]
     fn inv(&self) -> Option<Self> {
-        todo!();
+        let has_zero_lane = unsafe {
+            let zero_cmp = vorrq_u32(vceqq_u32(self.v, PACKED_0), vceqq_u32(self.v, PACKED_MOD));
+            vmaxvq_u32(zero_cmp) != 0
+        };
+        if has_zero_lane {
+            return None;
+        }
+        Some(self.exp(M31_MOD as usize - 2))
     }
 
     #[inline(always)]
-    fn add_base_elem(&self, _rhs: &Self::BaseField) -> Self {
-        unimplemented!()
+    fn add_base_elem(&self, rhs: &Self::BaseField) -> Self {
+        *self + PackedM31::pack_full(*rhs)
     }
 
     #[inline(always)]
-    fn add_assign_base_elem(&mut self, _rhs: &Self::BaseField) {
-        unimplemented!()
+    fn add_assign_base_elem(&mut self, rhs: &Self::BaseField) {
+        *self = self.add_base_elem(rhs);
     }
 
     #[inline(always)]
@@ -125,11 +190,14 @@ impl Field for PackedM31 {
         *self = *self * rhs;
     }
 
+    /// the first lane
     fn as_u32_unchecked(&self) -> u32 {
-        unimplemented!("self is a vector, cannot convert to u32")
+        self.unpack()[0].v
     }
-    fn from_uniform_bytes(_bytes: &[u8; 32]) -> Self {
-        unimplemented!(" cannot convert 32 bytes into a vectorized M31")
+
+    /// the same element in all lanes, so that challenges are shared by all instances
+    fn from_uniform_bytes(bytes: &[u8; 32]) -> Self {
+        PackedM31::pack_full(M31::from_uniform_bytes(bytes))
     }
 }
 
@@ -39,6 +39,20 @@ impl FieldSerde for M31Ext3 {
             ],
         }
     }
+
+    /// Also rejects nonzero padding bytes.
+    fn try_deserialize_from(buffer: &[u8]) -> Option<Self> {
+        if buffer.get(12..Self::SIZE)?.iter().any(|&b| b != 0) {
+            return None;
+        }
+        Some(M31Ext3 {
+            v: [
+                M31::try_deserialize_from(&buffer[0..4])?,
+                M31::try_deserialize_from(&buffer[4..8])?,
+                M31::try_deserialize_from(&buffer[8..12])?,
+            ],
+        })
+    }
 }
 
 impl Field for M31Ext3 {
@@ -46,12 +60,16 @@ impl Field for M31Ext3 {
 
     const SIZE: usize = 24;
 
+    const FIELD_SIZE: usize = 93;
+
     const INV_2: M31Ext3 = M31Ext3 {
         v: [M31::INV_2, M31 { v: 0 }, M31 { v: 0 }],
     };
 
     type BaseField = M31;
 
+    type ChallengeField = Self;
+
     #[inline(always)]
     fn zero() -> Self {
         M31Ext3 {
@@ -101,9 +119,18 @@ impl Field for M31Ext3 {
         res
     }
 
+    /// (a0 + a1*x + a2*x^2) * (b0 + b1*x + b2*x^2) is the norm of the element, a base field
+    /// element, for the b's below; the inverse is b divided by the norm
     fn inv(&self) -> Option<Self> {
-        unimplemented!("inverse is not supported for M31Ext3")
-        // self.try_inverse()
+        let [a0, a1, a2] = self.v;
+        let five = M31 { v: 5 };
+        let b0 = a0.square() - five * a1 * a2;
+        let b1 = five * a2.square() - a0 * a1;
+        let b2 = a1.square() - a0 * a2;
+        let norm = a0 * b0 + five * (a1 * b2 + a2 * b1);
+        norm.inv().map(|norm_inv| M31Ext3 {
+            v: [b0 * norm_inv, b1 * norm_inv, b2 * norm_inv],
+        })
     }
 
     #[inline(always)]
@@ -32,8 +32,10 @@ impl FieldSerde for Msn61 {
 impl Field for Msn61 {
     const NAME: &'static str = "Mersenne 61";
     const SIZE: usize = size_of::<u64>();
+    const FIELD_SIZE: usize = 61;
     const INV_2: Self = Msn61 { v: 1u64 << 60 };
     type BaseField = Msn61;
+    type ChallengeField = Msn61;
 
     fn zero() -> Self {
         Msn61 { v: 0 }
@@ -29,6 +29,12 @@ impl<F: Field> MultiLinearPoly<F> {
         scratch[0]
     }
 
+    /// Evaluate at a point of the challenge field, lifting the evaluations into it.
+    pub fn eval_multilinear_challenge(evals: &[F], x: &[F::ChallengeField]) -> F::ChallengeField {
+        let lifted: Vec<F::ChallengeField> = evals.iter().map(|&e| e.into()).collect();
+        MultiLinearPoly::eval_multilinear_ext(&lifted, x)
+    }
+
     pub fn eval_multilinear_ext(evals: &[F], x: &[F]) -> F {
         let timer = start_timer!(|| format!("eval mle with {} vars", x.len()));
         assert_eq!(1 << x.len(), evals.len());
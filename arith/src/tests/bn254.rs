@@ -49,3 +49,14 @@ fn test_custom_serde_vectorize_bn254() {
     let b = Fr::deserialize_from(&buffer_slice);
     assert_eq!(a, b);
 }
+
+#[test]
+fn test_try_deserialize_bn254() {
+    let a = Fr::from(256u32 + 2);
+    let mut bytes = [0u8; 32];
+    a.serialize_into(&mut bytes);
+    assert_eq!(Fr::try_deserialize_from(&bytes), Some(a));
+    // not reduced modulo the order
+    assert_eq!(Fr::try_deserialize_from(&[0xff; 32]), None);
+    assert_eq!(Fr::try_deserialize_from(&bytes[..31]), None);
+}
@@ -1,58 +1,102 @@
-// use crate::{FieldSerde, PackedM31, M31, VECTORIZEDM31_INV_2};
-
-// use super::field::{
-//     random_field_tests, random_inversion_tests, random_small_field_tests,
-//     random_vectorized_field_tests, test_basic_field_op,
-// };
-
-// #[test]
-// fn test_field() {
-//     random_field_tests::<M31>("M31".to_string());
-//     random_inversion_tests::<M31>("M31".to_string());
-//     random_small_field_tests::<M31>("M31".to_string());
-
-//     random_field_tests::<VectorizedM31>("Vectorized M31".to_string());
-//     random_small_field_tests::<VectorizedM31>("Vectorized M31".to_string());
-
-//     random_vectorized_field_tests::<VectorizedM31>("Vectorized M31".to_string());
-// }
-
-// #[test]
-// fn test_m31_basic_field_op() {
-//     test_basic_field_op::<M31>();
-// }
-
-// #[test]
-// fn test_packed_m31_basic_field_op() {
-//     test_basic_field_op::<PackedM31>();
-// }
-
-// #[test]
-// fn test_vectorize_m31_basic_field_op() {
-//     test_basic_field_op::<VectorizedM31>();
-// }
-
-// #[test]
-// fn test_sanity_check_vectorize_m31() {
-//     let mut a = VectorizedM31::from(1);
-//     let b = VectorizedM31::from(2);
-//     a += b;
-//     assert_eq!(a, VectorizedM31::from(3));
-//     assert_eq!(b * VECTORIZEDM31_INV_2, VectorizedM31::from(1));
-//     assert_eq!(b * b * VECTORIZEDM31_INV_2, b);
-// }
-
-// #[test]
-// fn test_custom_serde_vectorize_m31() {
-//     let a = VectorizedM31::from(256 + 2);
-//     let mut buffer = vec![PackedM31::default(); VectorizedM31::VECTORIZE_SIZE];
-//     let buffer_slice: &mut [u8] = unsafe {
-//         std::slice::from_raw_parts_mut(
-//             buffer.as_mut_ptr() as *mut u8,
-//             buffer.len() * std::mem::size_of::<PackedM31>(),
-//         )
-//     };
-//     a.serialize_into(buffer_slice);
-//     let b = VectorizedM31::deserialize_from(&buffer_slice);
-//     assert_eq!(a, b);
-// }
+use crate::{Field, FieldSerde, M31Ext3, M31};
+
+use super::field::{
+    random_field_tests, random_inversion_tests, random_small_field_tests, test_basic_field_op,
+};
+
+#[test]
+fn test_field() {
+    random_field_tests::<M31>("M31".to_string());
+    random_inversion_tests::<M31>("M31".to_string());
+    random_small_field_tests::<M31>("M31".to_string());
+}
+
+#[test]
+fn test_m31_basic_field_op() {
+    test_basic_field_op::<M31>();
+}
+
+#[test]
+fn test_m31_ext3_field() {
+    random_field_tests::<M31Ext3>("M31 Ext3".to_string());
+    random_inversion_tests::<M31Ext3>("M31 Ext3".to_string());
+    random_small_field_tests::<M31Ext3>("M31 Ext3".to_string());
+    test_basic_field_op::<M31Ext3>();
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[test]
+fn test_packed_m31_field() {
+    use crate::PackedM31;
+
+    random_field_tests::<PackedM31>("Packed M31".to_string());
+    random_inversion_tests::<PackedM31>("Packed M31".to_string());
+    random_small_field_tests::<PackedM31>("Packed M31".to_string());
+    test_basic_field_op::<PackedM31>();
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[test]
+fn test_packed_m31_lanes() {
+    use crate::{Field, FieldSerde, PackedM31, M31_MOD};
+    use ark_std::test_rng;
+
+    let mut rng = test_rng();
+    for _ in 0..100 {
+        let a: Vec<M31> = (0..PackedM31::PACK_SIZE)
+            .map(|_| M31::random_unsafe(&mut rng))
+            .collect();
+        let b = M31::random_unsafe(&mut rng);
+        let packed = PackedM31::pack(a.as_slice().try_into().unwrap());
+        assert_eq!(packed.unpack().to_vec(), a);
+
+        let sum = packed.add_base_elem(&b).unpack();
+        let inv = packed.inv().unwrap().unpack();
+        let exp = packed.exp(12345).unpack();
+        for i in 0..PackedM31::PACK_SIZE {
+            assert_eq!(sum[i], a[i] + b);
+            assert_eq!(inv[i], a[i].inv().unwrap());
+            assert_eq!(exp[i], a[i].exp(12345));
+        }
+
+        let mut buffer = vec![0u8; PackedM31::SIZE];
+        packed.serialize_into(&mut buffer);
+        assert_eq!(PackedM31::deserialize_from(&buffer), packed);
+    }
+
+    let mut bytes = [0u8; 32];
+    bytes[0] = 7;
+    let challenge = PackedM31::from_uniform_bytes(&bytes);
+    assert_eq!(
+        challenge,
+        PackedM31::pack_full(M31::from_uniform_bytes(&bytes))
+    );
+    assert_eq!(challenge.as_u32_unchecked(), 7);
+
+    let mut lanes = [M31::one(); PackedM31::PACK_SIZE];
+    lanes[PackedM31::PACK_SIZE - 1] = M31::zero();
+    assert!(PackedM31::pack(&lanes).inv().is_none());
+    // the unreduced zero the packed multiplication may leave in a lane
+    lanes[PackedM31::PACK_SIZE - 1] = M31 { v: M31_MOD as u32 };
+    assert!(PackedM31::pack(&lanes).inv().is_none());
+}
+
+#[test]
+fn test_try_deserialize_m31() {
+    let a = M31Ext3::from(M31::from(3));
+    let mut bytes = vec![0u8; M31Ext3::SIZE];
+    a.serialize_into(&mut bytes);
+    assert_eq!(M31Ext3::try_deserialize_from(&bytes), Some(a));
+    assert_eq!(M31::try_deserialize_from(&bytes), Some(M31::from(3)));
+
+    // nonzero padding
+    let mut padded = bytes.clone();
+    padded[M31Ext3::SIZE - 1] = 1;
+    assert_eq!(M31Ext3::try_deserialize_from(&padded), None);
+    // a component that does not fit in 31 bits
+    let mut large = bytes.clone();
+    large[3] = 0x80;
+    assert_eq!(M31::try_deserialize_from(&large), None);
+    assert_eq!(M31Ext3::try_deserialize_from(&large), None);
+    assert_eq!(M31Ext3::try_deserialize_from(&bytes[..12]), None);
+}
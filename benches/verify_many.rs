@@ -0,0 +1,52 @@
+//! `verify_many` sorts the wiring of the circuit once for all the proofs, where verifying each
+//! of them from its own key sorts it again for every proof.
+
+use arith::M31;
+use criterion::{criterion_group, criterion_main, Criterion};
+use expander_rs::{
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    Circuit, Config, Prover, Verifier, VerifyingKey,
+};
+use rayon::prelude::*;
+
+const FILENAME_MUL: &str = "data/ExtractedCircuitMul.txt";
+const FILENAME_ADD: &str = "data/ExtractedCircuitAdd.txt";
+const PROOF_NUM: usize = 8;
+
+fn bench_verify_many(c: &mut Criterion) {
+    let config = Config::m31_config();
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let proofs: Vec<_> = (0..PROOF_NUM)
+        .map(|_| {
+            circuit.set_random_bool_input_for_test();
+            circuit.evaluate();
+            prover.prove(&circuit)
+        })
+        .collect();
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+
+    let mut group = c.benchmark_group(format!("verify {} proofs", PROOF_NUM));
+    group.sample_size(10);
+    group.bench_function("verify_many", |b| {
+        b.iter(|| {
+            assert!(verifier
+                .verify_many(&circuit, &proofs)
+                .iter()
+                .all(|result| result.is_ok()))
+        })
+    });
+    group.bench_function("a key per proof", |b| {
+        b.iter(|| {
+            assert!(proofs.par_iter().all(|(claimed_v, proof)| {
+                let vk = VerifyingKey::new(&circuit);
+                verifier.verify_with_key(&vk, claimed_v, proof).is_ok()
+            }))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify_many);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+//! Batch proving of several circuits in one proof.
+//!
+//! The input layers of all circuits are committed together, in a single polynomial, and the
+//! claims on all of them are merged into a single PCS opening.
+//!
+//! The circuits are aligned on their output layers, and the layers at the same depth are proved
+//! by a single sumcheck per repetition, on the random linear combination of their sums by the
+//! powers of a challenge; the circuits share the round challenges. A layer with fewer input
+//! variables than the largest one at its depth is extended to them by `eq(0, .)`, and its
+//! claims are on the first challenges of each half. The proof and the verifier's work thus
+//! grow with the largest circuit, plus a few claims per circuit. A batch of one circuit is
+//! proved as the circuit alone.
+//!
+//! Each input layer is placed at an offset that is a multiple of its size, the largest ones
+//! first. The input of a circuit with `n` input variables is then the restriction of the
+//! committed polynomial to the points whose last variables are the bits of `offset >> n`, and
+//! a claim on it at `z` is a claim on the committed polynomial at `z` followed by these bits.
+
+use arith::Field;
+
+use crate::Circuit;
+
+/// Position of the input layers in the committed polynomial.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchLayout {
+    /// number of variables of the committed polynomial, which the PCS parameters must match
+    pub var_num: usize,
+    /// `input_var_nums[k]` is the number of input variables of circuit `k`
+    pub input_var_nums: Vec<usize>,
+    /// `offsets[k]` is the offset of the input of circuit `k`
+    pub offsets: Vec<usize>,
+}
+
+impl BatchLayout {
+    /// The layout of the inputs of `circuits`, with no variable for an empty batch.
+    pub fn new<F: Field>(circuits: &[&Circuit<F>]) -> Self {
+        let input_var_nums: Vec<usize> = circuits.iter().map(|c| c.log_input_size()).collect();
+        let mut order: Vec<usize> = (0..circuits.len()).collect();
+        order.sort_by_key(|&k| std::cmp::Reverse(input_var_nums[k]));
+
+        let mut offsets = vec![0; circuits.len()];
+        let mut offset = 0usize;
+        for k in order {
+            // sizes are powers of two in decreasing order, so the offset stays aligned
+            offsets[k] = offset;
+            offset += 1 << input_var_nums[k];
+        }
+        BatchLayout {
+            var_num: offset.next_power_of_two().trailing_zeros() as usize,
+            input_var_nums,
+            offsets,
+        }
+    }
+
+    /// The polynomial to commit to: the inputs of all circuits, padded with zeros.
+    pub(crate) fn committed_poly<F: Field>(&self, circuits: &[&Circuit<F>]) -> Vec<F> {
+        let mut evals = vec![F::zero(); 1 << self.var_num];
+        for (k, c) in circuits.iter().enumerate() {
+            let input = &c.layers[0].input_vals.evals;
+            evals[self.offsets[k]..self.offsets[k] + input.len()].copy_from_slice(input);
+        }
+        evals
+    }
+
+    /// The point of the committed polynomial at which it evaluates to the input of circuit `k`
+    /// at `z`.
+    pub(crate) fn point<C: Field>(&self, k: usize, z: &[C]) -> Vec<C> {
+        assert_eq!(z.len(), self.input_var_nums[k]);
+        let selector = self.offsets[k] >> self.input_var_nums[k];
+        let mut point = z.to_vec();
+        point.extend((0..self.var_num - z.len()).map(|b| C::from(((selector >> b) & 1) as u32)));
+        point
+    }
+}
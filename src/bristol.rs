@@ -0,0 +1,391 @@
+//! Import of boolean circuits in Bristol Fashion format as layered arithmetic circuits.
+//!
+//! Bits are encoded as 0/1 field elements: `AND` becomes `a * b`, `XOR` becomes
+//! `a + b - 2ab` and `INV` becomes `1 - a`. Gates are placed by depth; a wire read by a gate
+//! more than one layer above its own is carried up with relay add gates, so every gate only
+//! reads from the previous layer. The last layer outputs the circuit outputs in order.
+
+use std::{fmt, fs, io};
+
+use arith::{Field, MultiLinearPoly};
+
+use crate::{Circuit, CircuitLayer, GateAdd, GateConst, GateMul};
+
+/// Errors of the Bristol Fashion reader.
+#[derive(Debug)]
+pub enum BristolError {
+    Io(io::Error),
+    /// the file ends before its header or a list of sizes
+    Truncated,
+    /// a header or a list of sizes that is not made of the expected numbers
+    BadHeader,
+    /// a line that is not a supported gate
+    BadGate(String),
+    /// a gate, an input or an output refers to a wire beyond `num_wires`
+    WireOutOfRange(usize),
+    /// the number of gates differs from the header
+    GateCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for BristolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BristolError::Io(err) => write!(f, "{}", err),
+            BristolError::Truncated => write!(f, "truncated file"),
+            BristolError::BadHeader => write!(f, "bad header"),
+            BristolError::BadGate(line) => write!(f, "unsupported gate: {}", line),
+            BristolError::WireOutOfRange(wire_id) => write!(f, "wire {} out of range", wire_id),
+            BristolError::GateCountMismatch { expected, found } => {
+                write!(f, "{} gates, {} expected", found, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BristolError {}
+
+impl From<io::Error> for BristolError {
+    fn from(err: io::Error) -> Self {
+        BristolError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BristolGate {
+    And {
+        a: usize,
+        b: usize,
+        out: usize,
+    },
+    Xor {
+        a: usize,
+        b: usize,
+        out: usize,
+    },
+    Inv {
+        a: usize,
+        out: usize,
+    },
+    /// copy of wire `a`
+    Eqw {
+        a: usize,
+        out: usize,
+    },
+    /// constant bit
+    Eq {
+        value: bool,
+        out: usize,
+    },
+}
+
+impl BristolGate {
+    fn inputs(&self) -> Vec<usize> {
+        match *self {
+            BristolGate::And { a, b, .. } | BristolGate::Xor { a, b, .. } => vec![a, b],
+            BristolGate::Inv { a, .. } | BristolGate::Eqw { a, .. } => vec![a],
+            BristolGate::Eq { .. } => vec![],
+        }
+    }
+
+    fn output(&self) -> usize {
+        match *self {
+            BristolGate::And { out, .. }
+            | BristolGate::Xor { out, .. }
+            | BristolGate::Inv { out, .. }
+            | BristolGate::Eqw { out, .. }
+            | BristolGate::Eq { out, .. } => out,
+        }
+    }
+}
+
+/// Inputs are the first wires and outputs the last ones, grouped into values of the given
+/// bit lengths.
+#[derive(Debug, Clone, Default)]
+pub struct BristolCircuit {
+    pub num_wires: usize,
+    pub input_sizes: Vec<usize>,
+    pub output_sizes: Vec<usize>,
+    pub gates: Vec<BristolGate>,
+}
+
+fn parse_sizes(line: Option<&str>) -> Result<Vec<usize>, BristolError> {
+    let values = parse_numbers(line.ok_or(BristolError::Truncated)?)?;
+    let (&num, sizes) = values.split_first().ok_or(BristolError::Truncated)?;
+    match sizes.len().cmp(&num) {
+        std::cmp::Ordering::Less => Err(BristolError::Truncated),
+        std::cmp::Ordering::Greater => Err(BristolError::BadHeader),
+        std::cmp::Ordering::Equal => Ok(sizes.to_vec()),
+    }
+}
+
+fn parse_numbers(line: &str) -> Result<Vec<usize>, BristolError> {
+    line.split_whitespace()
+        .map(|s| s.parse::<usize>().map_err(|_| BristolError::BadHeader))
+        .collect()
+}
+
+/// The gate of a line `n_in n_out in... out... OP`, with its wires below `num_wires`.
+fn parse_gate(line: &str, num_wires: usize) -> Result<BristolGate, BristolError> {
+    let bad_gate = || BristolError::BadGate(line.to_string());
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let arity = match tokens.as_slice() {
+        ["2", "1", .., "AND" | "XOR"] => 2,
+        ["1", "1", .., "INV" | "EQW" | "EQ"] => 1,
+        _ => return Err(bad_gate()),
+    };
+    if tokens.len() != arity + 4 {
+        return Err(bad_gate());
+    }
+    let number = |i: usize| tokens[i].parse::<usize>().map_err(|_| bad_gate());
+    let wire = |i: usize| match number(i)? {
+        id if id < num_wires => Ok(id),
+        id => Err(BristolError::WireOutOfRange(id)),
+    };
+    Ok(match tokens[arity + 3] {
+        "AND" => BristolGate::And {
+            a: wire(2)?,
+            b: wire(3)?,
+            out: wire(4)?,
+        },
+        "XOR" => BristolGate::Xor {
+            a: wire(2)?,
+            b: wire(3)?,
+            out: wire(4)?,
+        },
+        "INV" => BristolGate::Inv {
+            a: wire(2)?,
+            out: wire(3)?,
+        },
+        "EQW" => BristolGate::Eqw {
+            a: wire(2)?,
+            out: wire(3)?,
+        },
+        _ => BristolGate::Eq {
+            value: match number(2)? {
+                0 => false,
+                1 => true,
+                _ => return Err(bad_gate()),
+            },
+            out: wire(3)?,
+        },
+    })
+}
+
+impl BristolCircuit {
+    pub fn load(filename: &str) -> Result<Self, BristolError> {
+        let file = fs::read_to_string(filename)?;
+        Self::parse(&file)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, BristolError> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let header = parse_numbers(lines.next().ok_or(BristolError::Truncated)?)?;
+        let &[num_gates, num_wires] = header.as_slice() else {
+            return Err(BristolError::BadHeader);
+        };
+        let input_sizes = parse_sizes(lines.next())?;
+        let output_sizes = parse_sizes(lines.next())?;
+        let num_io = input_sizes.iter().chain(&output_sizes).sum::<usize>();
+        if num_io > num_wires {
+            return Err(BristolError::WireOutOfRange(num_io - 1));
+        }
+
+        let gates = lines
+            .map(|line| parse_gate(line, num_wires))
+            .collect::<Result<Vec<_>, _>>()?;
+        if gates.len() != num_gates {
+            return Err(BristolError::GateCountMismatch {
+                expected: num_gates,
+                found: gates.len(),
+            });
+        }
+
+        Ok(BristolCircuit {
+            num_wires,
+            input_sizes,
+            output_sizes,
+            gates,
+        })
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.input_sizes.iter().sum()
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.output_sizes.iter().sum()
+    }
+
+    fn output_wires(&self) -> std::ops::Range<usize> {
+        self.num_wires - self.num_outputs()..self.num_wires
+    }
+
+    /// Plain boolean evaluation, used as reference.
+    pub fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        assert_eq!(inputs.len(), self.num_inputs());
+        let mut wires = vec![false; self.num_wires];
+        wires[..inputs.len()].copy_from_slice(inputs);
+        for gate in &self.gates {
+            wires[gate.output()] = match *gate {
+                BristolGate::And { a, b, .. } => wires[a] & wires[b],
+                BristolGate::Xor { a, b, .. } => wires[a] ^ wires[b],
+                BristolGate::Inv { a, .. } => !wires[a],
+                BristolGate::Eqw { a, .. } => wires[a],
+                BristolGate::Eq { value, .. } => value,
+            };
+        }
+        wires[self.output_wires()].to_vec()
+    }
+
+    /// Layer `l` of the result reads the wires available at depth `l` and writes those needed
+    /// at depth `l + 1`; copies (`EQW`) are resolved at load time and cost no gate.
+    pub fn to_circuit<F: Field>(&self) -> Circuit<F> {
+        let num_inputs = self.num_inputs();
+
+        // copies are followed back to the wire holding the value: an input or a gate output
+        let mut alias: Vec<usize> = (0..self.num_wires).collect();
+        let mut source = vec![None; self.num_wires];
+        let mut depth = vec![0usize; self.num_wires];
+        let mut gates = vec![];
+        for gate in &self.gates {
+            let out = gate.output();
+            if let BristolGate::Eqw { a, .. } = *gate {
+                alias[out] = alias[a];
+                continue;
+            }
+            let inputs: Vec<usize> = gate.inputs().iter().map(|&w| alias[w]).collect();
+            source[out] = Some(gates.len());
+            depth[out] = inputs.iter().map(|&w| depth[w]).max().unwrap_or(0) + 1;
+            gates.push((*gate, inputs));
+        }
+
+        let outputs: Vec<usize> = self.output_wires().map(|w| alias[w]).collect();
+        let layer_num = outputs.iter().map(|&w| depth[w]).max().unwrap().max(1);
+
+        // the last depth at which a value is read
+        let mut last_use = vec![None; self.num_wires];
+        for (gate, inputs) in &gates {
+            let d = depth[gate.output()] - 1;
+            for &w in inputs {
+                last_use[w] = Some(last_use[w].map_or(d, |u: usize| u.max(d)));
+            }
+        }
+        for &w in &outputs {
+            last_use[w] = Some(layer_num);
+        }
+
+        // wire layout of each depth; the first depth keeps all inputs in order, the last one
+        // holds exactly the outputs
+        let mut slots: Vec<Vec<usize>> = vec![vec![]; layer_num + 1];
+        slots[0] = (0..num_inputs).collect();
+        for w in 0..self.num_wires {
+            if let Some(u) = last_use[w] {
+                for slot in slots
+                    .iter_mut()
+                    .take(u.min(layer_num - 1) + 1)
+                    .skip(depth[w].max(1))
+                {
+                    slot.push(w);
+                }
+            }
+        }
+        slots[layer_num] = outputs;
+
+        let mut circuit = Circuit::default();
+        let one = F::BaseField::one();
+        let mut position = vec![0; self.num_wires];
+        for d in 1..=layer_num {
+            for (i, &w) in slots[d - 1].iter().enumerate() {
+                position[w] = i;
+            }
+            let input_var_num = var_num(slots[d - 1].len());
+            let mut layer = CircuitLayer::<F> {
+                input_var_num,
+                output_var_num: var_num(slots[d].len()),
+                input_vals: MultiLinearPoly {
+                    var_num: input_var_num,
+                    evals: vec![],
+                },
+                ..Default::default()
+            };
+            for (o_id, &w) in slots[d].iter().enumerate() {
+                if depth[w] < d {
+                    layer.add.push(GateAdd {
+                        i_ids: [position[w]],
+                        o_id,
+                        coef: one,
+                    });
+                    continue;
+                }
+                let (gate, inputs) = &gates[source[w].unwrap()];
+                let i_ids: Vec<usize> = inputs.iter().map(|&i| position[i]).collect();
+                match gate {
+                    BristolGate::And { .. } => layer.mul.push(GateMul {
+                        i_ids: [i_ids[0], i_ids[1]],
+                        o_id,
+                        coef: one,
+                    }),
+                    BristolGate::Xor { .. } => {
+                        layer.add.push(GateAdd {
+                            i_ids: [i_ids[0]],
+                            o_id,
+                            coef: one,
+                        });
+                        layer.add.push(GateAdd {
+                            i_ids: [i_ids[1]],
+                            o_id,
+                            coef: one,
+                        });
+                        layer.mul.push(GateMul {
+                            i_ids: [i_ids[0], i_ids[1]],
+                            o_id,
+                            coef: -F::BaseField::from(2),
+                        });
+                    }
+                    BristolGate::Inv { .. } => {
+                        layer.const_.push(GateConst {
+                            i_ids: [],
+                            o_id,
+                            coef: one,
+                        });
+                        layer.add.push(GateAdd {
+                            i_ids: [i_ids[0]],
+                            o_id,
+                            coef: -one,
+                        });
+                    }
+                    BristolGate::Eq { value, .. } => {
+                        if *value {
+                            layer.const_.push(GateConst {
+                                i_ids: [],
+                                o_id,
+                                coef: one,
+                            });
+                        }
+                    }
+                    BristolGate::Eqw { .. } => unreachable!(),
+                }
+            }
+            circuit.layers.push(layer);
+        }
+        circuit
+    }
+}
+
+fn var_num(size: usize) -> usize {
+    size.next_power_of_two().trailing_zeros().max(1) as usize
+}
+
+impl<F: Field> Circuit<F> {
+    /// Set the input layer to the given bits, padded with zeros.
+    pub fn set_bool_input(&mut self, bits: &[bool]) {
+        let input_num = 1 << self.log_input_size();
+        assert!(bits.len() <= input_num);
+        self.layers[0].input_vals.evals = (0..input_num)
+            .map(|i| F::from(bits.get(i).copied().unwrap_or(false) as u32))
+            .collect();
+    }
+}
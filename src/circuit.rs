@@ -1,8 +1,9 @@
 use arith::{Field, FieldSerde, MultiLinearPoly};
 use ark_std::test_rng;
+use rayon::prelude::*;
 use std::{cmp::max, collections::HashMap, fs};
 
-use crate::Transcript;
+use crate::{LookupColumn, Transcript};
 
 #[derive(Debug, Clone)]
 pub struct Gate<F: Field, const INPUT_NUM: usize> {
@@ -11,6 +12,16 @@ pub struct Gate<F: Field, const INPUT_NUM: usize> {
     pub coef: F::BaseField,
 }
 
+impl<F: Field, const INPUT_NUM: usize> Gate<F, INPUT_NUM> {
+    fn to_field<PF: Field<BaseField = F::BaseField>>(&self) -> Gate<PF, INPUT_NUM> {
+        Gate {
+            i_ids: self.i_ids,
+            o_id: self.o_id,
+            coef: self.coef,
+        }
+    }
+}
+
 pub type GateMul<F> = Gate<F, 2>;
 pub type GateAdd<F> = Gate<F, 1>;
 pub type GateConst<F> = Gate<F, 0>;
@@ -30,15 +41,20 @@ pub struct CircuitLayer<F: Field> {
 
 impl<F: Field> CircuitLayer<F> {
     pub fn evaluate(&self) -> Vec<F> {
+        self.evaluate_on(&self.input_vals.evals)
+    }
+
+    /// Evaluate the layer on `input_vals` instead of the stored input.
+    pub fn evaluate_on(&self, input_vals: &[F]) -> Vec<F> {
         let mut res = vec![F::zero(); 1 << self.output_var_num];
         for gate in &self.mul {
-            let i0 = &self.input_vals.evals[gate.i_ids[0]];
-            let i1 = &self.input_vals.evals[gate.i_ids[1]];
+            let i0 = &input_vals[gate.i_ids[0]];
+            let i1 = &input_vals[gate.i_ids[1]];
             let o = &mut res[gate.o_id];
             *o += (*i0 * i1).mul_base_elem(&gate.coef);
         }
         for gate in &self.add {
-            let i0 = &self.input_vals.evals[gate.i_ids[0]];
+            let i0 = &input_vals[gate.i_ids[0]];
             let o = &mut res[gate.o_id];
             *o += i0.mul_base_elem(&gate.coef);
         }
@@ -48,11 +64,87 @@ impl<F: Field> CircuitLayer<F> {
         }
         res
     }
+
+    /// Multi-threaded version of `evaluate`. Gates are bucketed by their output index first,
+    /// so every output value is accumulated by exactly one thread.
+    pub fn evaluate_parallel(&self) -> Vec<F> {
+        let output_num = 1 << self.output_var_num;
+        let mul_buckets = GateBuckets::new(&self.mul, output_num);
+        let add_buckets = GateBuckets::new(&self.add, output_num);
+        let const_buckets = GateBuckets::new(&self.const_, output_num);
+
+        let mut res = vec![F::zero(); output_num];
+        res.par_iter_mut()
+            .enumerate()
+            .with_min_len(PAR_EVAL_MIN_LEN)
+            .for_each(|(o_id, o)| {
+                for gate in mul_buckets.gates(&self.mul, o_id) {
+                    let i0 = &self.input_vals.evals[gate.i_ids[0]];
+                    let i1 = &self.input_vals.evals[gate.i_ids[1]];
+                    *o += (*i0 * i1).mul_base_elem(&gate.coef);
+                }
+                for gate in add_buckets.gates(&self.add, o_id) {
+                    let i0 = &self.input_vals.evals[gate.i_ids[0]];
+                    *o += i0.mul_base_elem(&gate.coef);
+                }
+                for gate in const_buckets.gates(&self.const_, o_id) {
+                    *o += F::one().mul_base_elem(&gate.coef);
+                }
+            });
+        res
+    }
+}
+
+// minimum number of outputs handled by a single rayon task
+const PAR_EVAL_MIN_LEN: usize = 1 << 10;
+
+/// Gate indices grouped by output index (CSR layout), built with a counting sort.
+pub(crate) struct GateBuckets {
+    offsets: Vec<usize>,
+    gate_ids: Vec<usize>,
+}
+
+impl GateBuckets {
+    pub(crate) fn new<F: Field, const INPUT_NUM: usize>(
+        gates: &[Gate<F, INPUT_NUM>],
+        output_num: usize,
+    ) -> Self {
+        let mut offsets = vec![0; output_num + 1];
+        for gate in gates {
+            offsets[gate.o_id + 1] += 1;
+        }
+        for i in 0..output_num {
+            offsets[i + 1] += offsets[i];
+        }
+        let mut cursor = offsets.clone();
+        let mut gate_ids = vec![0; gates.len()];
+        for (i, gate) in gates.iter().enumerate() {
+            gate_ids[cursor[gate.o_id]] = i;
+            cursor[gate.o_id] += 1;
+        }
+        GateBuckets { offsets, gate_ids }
+    }
+
+    #[inline(always)]
+    pub(crate) fn gate_ids(&self, o_id: usize) -> &[usize] {
+        &self.gate_ids[self.offsets[o_id]..self.offsets[o_id + 1]]
+    }
+
+    #[inline(always)]
+    pub(crate) fn gates<'a, F: Field, const INPUT_NUM: usize>(
+        &'a self,
+        gates: &'a [Gate<F, INPUT_NUM>],
+        o_id: usize,
+    ) -> impl Iterator<Item = &'a Gate<F, INPUT_NUM>> + 'a {
+        self.gate_ids(o_id).iter().map(move |&i| &gates[i])
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Circuit<F: Field> {
     pub layers: Vec<CircuitLayer<F>>,
+    /// values of the input layer constrained to fixed tables, see `crate::lookup`
+    pub lookups: Vec<LookupColumn<F>>,
 }
 
 impl<F: Field> Circuit<F> {
@@ -154,8 +246,36 @@ impl<F: Field> Circuit<F> {
     }
 
     pub fn evaluate(&mut self) {
+        self.evaluate_with(CircuitLayer::evaluate);
+    }
+
+    /// Same as `evaluate`, with every layer evaluated by `CircuitLayer::evaluate_parallel`.
+    pub fn evaluate_parallel(&mut self) {
+        self.evaluate_with(CircuitLayer::evaluate_parallel);
+    }
+
+    /// Same as `evaluate`, but only the input values of one layer in `interval` are kept, the
+    /// input layer included, as well as the output values. The prover recomputes the others
+    /// from the checkpoint below when it needs them, trading up to one more evaluation of the
+    /// circuit for the memory of the dropped layers.
+    pub fn evaluate_checkpointed(&mut self, interval: usize) {
+        assert!(interval > 0);
         for i in 0..self.layers.len() - 1 {
             self.layers[i + 1].input_vals.evals = self.layers[i].evaluate();
+            if !i.is_multiple_of(interval) {
+                self.layers[i].input_vals.evals = vec![];
+            }
+        }
+        let last = self.layers.len() - 1;
+        self.layers[last].output_vals.evals = self.layers[last].evaluate();
+        if !last.is_multiple_of(interval) {
+            self.layers[last].input_vals.evals = vec![];
+        }
+    }
+
+    fn evaluate_with(&mut self, layer_eval: impl Fn(&CircuitLayer<F>) -> Vec<F>) {
+        for i in 0..self.layers.len() - 1 {
+            self.layers[i + 1].input_vals.evals = layer_eval(&self.layers[i]);
             log::trace!(
                 "layer {} evaluated - First 10 values: {:?}",
                 i,
@@ -167,7 +287,7 @@ impl<F: Field> Circuit<F> {
                     .collect::<Vec<_>>()
             );
         }
-        self.layers.last_mut().unwrap().output_vals.evals = self.layers.last().unwrap().evaluate();
+        self.layers.last_mut().unwrap().output_vals.evals = layer_eval(self.layers.last().unwrap());
         log::trace!("output evaluated");
         log::trace!(
             "First ten values: {:?}",
@@ -181,11 +301,47 @@ impl<F: Field> Circuit<F> {
                 .collect::<Vec<_>>()
         );
     }
+
+    /// Re-interpret the circuit over another field sharing the same base field, e.g. `M31` to
+    /// `PackedM31`, so that several witnesses can be evaluated at once in the SIMD lanes.
+    /// Only the wiring is copied; the witness has to be set again.
+    pub fn to_field<PF: Field<BaseField = F::BaseField>>(&self) -> Circuit<PF> {
+        Circuit {
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| CircuitLayer {
+                    input_var_num: layer.input_var_num,
+                    output_var_num: layer.output_var_num,
+                    input_vals: MultiLinearPoly {
+                        var_num: layer.input_vals.var_num,
+                        evals: vec![],
+                    },
+                    output_vals: MultiLinearPoly {
+                        var_num: layer.output_vals.var_num,
+                        evals: vec![],
+                    },
+                    mul: layer.mul.iter().map(Gate::to_field).collect(),
+                    add: layer.add.iter().map(Gate::to_field).collect(),
+                    const_: layer.const_.iter().map(Gate::to_field).collect(),
+                })
+                .collect(),
+            lookups: self
+                .lookups
+                .iter()
+                .map(|column| LookupColumn {
+                    input_ids: column.input_ids.clone(),
+                    table: column.table.clone(),
+                })
+                .collect(),
+        }
+    }
 }
 
 // recursive format used in compiler
 pub type SegmentId = usize;
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Allocation {
     pub i_offset: usize,
     pub o_offset: usize,
@@ -338,6 +494,28 @@ impl<F: Field> Segment<F> {
         }
         ret
     }
+    /// Add the gates of the segment, at `allocation`, to `layer`.
+    pub fn place(&self, allocation: &Allocation, layer: &mut CircuitLayer<F>) {
+        for gate in &self.gate_muls {
+            let mut gate = gate.clone();
+            gate.i_ids[0] += allocation.i_offset;
+            gate.i_ids[1] += allocation.i_offset;
+            gate.o_id += allocation.o_offset;
+            layer.mul.push(gate);
+        }
+        for gate in &self.gate_adds {
+            let mut gate = gate.clone();
+            gate.i_ids[0] += allocation.i_offset;
+            gate.o_id += allocation.o_offset;
+            layer.add.push(gate);
+        }
+        for gate in &self.gate_consts {
+            let mut gate = gate.clone();
+            gate.o_id += allocation.o_offset;
+            layer.const_.push(gate);
+        }
+    }
+
     pub fn scan_leaf_segments(
         &self,
         rc: &RecursiveCircuit<F>,
@@ -407,12 +585,23 @@ impl<F: Field> RecursiveCircuit<F> {
         assert_eq!(file_bytes.len(), cur + 32);
         ret
     }
+    /// Leaf segments of a layer together with their absolute allocations, sorted by segment id
+    /// so that the flattened gate order is deterministic.
+    pub fn layer_leaves(&self, layer_id: SegmentId) -> Vec<(SegmentId, Vec<Allocation>)> {
+        let mut leaves = self.segments[layer_id]
+            .scan_leaf_segments(self, layer_id)
+            .into_iter()
+            .collect::<Vec<_>>();
+        leaves.sort_by_key(|(leaf_seg_id, _)| *leaf_seg_id);
+        leaves
+    }
+
     pub fn flatten(&self) -> Circuit<F> {
         let mut ret = Circuit::default();
         // layer-by-layer conversion
         for layer_id in &self.layers {
             let layer_seg = &self.segments[*layer_id];
-            let leaves = layer_seg.scan_leaf_segments(self, *layer_id);
+            let leaves = self.layer_leaves(*layer_id);
             let mut ret_layer = CircuitLayer {
                 input_var_num: layer_seg.i_var_num,
                 output_var_num: layer_seg.o_var_num,
@@ -430,25 +619,8 @@ impl<F: Field> RecursiveCircuit<F> {
             };
             for (leaf_seg_id, leaf_allocs) in leaves {
                 let leaf_seg = &self.segments[leaf_seg_id];
-                for alloc in leaf_allocs {
-                    for gate in &leaf_seg.gate_muls {
-                        let mut gate = gate.clone();
-                        gate.i_ids[0] += alloc.i_offset;
-                        gate.i_ids[1] += alloc.i_offset;
-                        gate.o_id += alloc.o_offset;
-                        ret_layer.mul.push(gate);
-                    }
-                    for gate in &leaf_seg.gate_adds {
-                        let mut gate = gate.clone();
-                        gate.i_ids[0] += alloc.i_offset;
-                        gate.o_id += alloc.o_offset;
-                        ret_layer.add.push(gate);
-                    }
-                    for gate in &leaf_seg.gate_consts {
-                        let mut gate = gate.clone();
-                        gate.o_id += alloc.o_offset;
-                        ret_layer.const_.push(gate);
-                    }
+                for alloc in &leaf_allocs {
+                    leaf_seg.place(alloc, &mut ret_layer);
                 }
             }
             // debug print layer
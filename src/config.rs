@@ -1,3 +1,5 @@
+use arith::Field;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum PolynomialCommitmentType {
     Raw,
@@ -34,10 +36,19 @@ pub enum FiatShamirHashType {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
+    /// number of repetitions of the GKR sumchecks reaching `security_bits` with challenges of
+    /// `field_size` bits, see `for_challenge_field`
     pub num_repetitions: usize,
+    /// bits of the challenge field; the prover and the verifier set it from their field with
+    /// `for_challenge_field`
     pub field_size: usize,
     pub security_bits: usize,
     pub grinding_bits: usize,
+    /// number of threads used by the prover, 1 for single-threaded proving
+    pub num_threads: usize,
+    /// hide the witness from the verifier, see `crate::zk`; needs a hiding polynomial
+    /// commitment
+    pub zero_knowledge: bool,
 
     pub polynomial_commitment_type: PolynomialCommitmentType,
     pub field_type: FieldType, // LATER: consider infer this from trait
@@ -55,13 +66,10 @@ impl Config {
         let security_bits = 100;
         let grinding_bits = 10;
 
-        let field_size = match FieldType::M31 {
-            FieldType::M31 => 31,
-            FieldType::BabyBear => 31,
-            FieldType::BN254 => 254,
-        };
+        // challenges of M31 circuits are drawn from M31Ext3, those of PackedM31 ones from M31
+        let field_size = 93;
 
-        let num_repetitions = (security_bits - grinding_bits + field_size - 1) / field_size;
+        let num_repetitions = Self::repetitions(security_bits, grinding_bits, field_size);
 
         let polynomial_commitment_type = PolynomialCommitmentType::Raw;
         let field_type = FieldType::M31;
@@ -72,17 +80,20 @@ impl Config {
         }
 
         Config {
-            num_repetitions, // update later
-            field_size,      // update later
+            num_repetitions,
+            field_size,
             security_bits,
             grinding_bits,
+            num_threads: 1,
+            zero_knowledge: false,
             polynomial_commitment_type,
             field_type,
             fs_hash,
         }
     }
 
-    // using degree 3 extension of m31
+    // using degree 3 extension of m31, for M31 circuits (whose challenges are drawn from the
+    // extension) and M31Ext3 circuits
     pub fn m31_ext3_config() -> Self {
         let security_bits = 100;
         let grinding_bits = 10;
@@ -90,7 +101,7 @@ impl Config {
         let field_size = 93;
         // let vectorize_size = num_parallel / VectorizedM31::PACK_SIZE;
 
-        let num_repetitions = 1; // we do not need repetitions for m31_ext3
+        let num_repetitions = Self::repetitions(security_bits, grinding_bits, field_size);
 
         let polynomial_commitment_type = PolynomialCommitmentType::Raw;
         let field_type = FieldType::M31;
@@ -101,11 +112,13 @@ impl Config {
         }
 
         Config {
-            num_repetitions, // update later
+            num_repetitions,
             // vectorize_size,  // update later
-            field_size, // update later
+            field_size,
             security_bits,
             grinding_bits,
+            num_threads: 1,
+            zero_knowledge: false,
             polynomial_commitment_type,
             field_type,
             fs_hash,
@@ -118,7 +131,7 @@ impl Config {
 
         let field_size = 254;
 
-        let num_repetitions = 1;
+        let num_repetitions = Self::repetitions(security_bits, grinding_bits, field_size);
 
         let polynomial_commitment_type = PolynomialCommitmentType::Raw;
         let field_type = FieldType::BN254;
@@ -129,10 +142,12 @@ impl Config {
         }
 
         Config {
-            num_repetitions, // update later
-            field_size,      // update later
+            num_repetitions,
+            field_size,
             security_bits,
             grinding_bits,
+            num_threads: 1,
+            zero_knowledge: false,
             polynomial_commitment_type,
             field_type,
             fs_hash,
@@ -145,23 +160,50 @@ impl Config {
 
         let field_size = 61;
 
-        let num_repetitions = 1;
+        let num_repetitions = Self::repetitions(security_bits, grinding_bits, field_size);
 
         let polynomial_commitment_type = PolynomialCommitmentType::Raw;
         let field_type = FieldType::BN254;
         let fs_hash = FiatShamirHashType::SHA256;
 
         Config {
-            num_repetitions, // update later
-            field_size,      // update later
+            num_repetitions,
+            field_size,
             security_bits,
             grinding_bits,
+            num_threads: 1,
+            zero_knowledge: false,
             polynomial_commitment_type,
             field_type,
             fs_hash,
         }
     }
 
+    /// Number of repetitions for `security_bits - grinding_bits` bits of soundness with
+    /// challenges of `field_size` bits.
+    fn repetitions(security_bits: usize, grinding_bits: usize, field_size: usize) -> usize {
+        security_bits
+            .saturating_sub(grinding_bits)
+            .div_ceil(field_size)
+            .max(1)
+    }
+
+    /// The configuration for proofs whose challenges are drawn from `C`: `field_size` is the
+    /// size of `C`, and `num_repetitions` is derived from it. The prover and the verifier apply
+    /// it to their configuration, so that `m31_config` runs a single repetition for M31
+    /// circuits, whose challenges are in M31Ext3, and three for PackedM31 ones.
+    pub fn for_challenge_field<C: Field>(&self) -> Self {
+        Config {
+            num_repetitions: Self::repetitions(
+                self.security_bits,
+                self.grinding_bits,
+                C::FIELD_SIZE,
+            ),
+            field_size: C::FIELD_SIZE,
+            ..self.clone()
+        }
+    }
+
     #[inline(always)]
     /// return the number of repetitions we will need to achieve security
     pub fn get_num_repetitions(&self) -> usize {
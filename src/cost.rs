@@ -0,0 +1,159 @@
+//! Static estimation of the proof size and prover cost of a circuit, computed from the layer
+//! shapes and the `Config` only, without running the prover.
+
+use std::mem::size_of;
+
+use arith::{Field, FieldSerde};
+
+use crate::{
+    lookup_proof_bytes, Circuit, Config, LookupLayout, PolyCommitVerifier, ZkLayout,
+    GRINDING_NONCE_BYTES,
+};
+
+/// Number of evaluations sent per sumcheck round (degree 2 polynomials).
+const EVALS_PER_ROUND: usize = 3;
+
+/// Number of evaluations sent per round of a layer sumcheck in zero-knowledge mode, whose
+/// masks make the messages degree 3.
+const ZK_EVALS_PER_ROUND: usize = 4;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofCostEstimate {
+    /// serialized commitment to the input layer
+    pub commitment_bytes: usize,
+    pub grinding_bytes: usize,
+    /// sumcheck round messages of each layer, indexed like `circuit.layers`
+    pub sumcheck_bytes: Vec<usize>,
+    /// vx and vy claims of each layer, indexed like `circuit.layers`, and in zero-knowledge
+    /// mode the sums and the claims of the masks
+    pub claim_bytes: Vec<usize>,
+    /// the lookup arguments of all lookup columns
+    pub lookup_bytes: usize,
+    /// the sumcheck merging all input claims into a single point
+    pub merge_bytes: usize,
+    /// upper bound for the PCS opening; exact for deterministic schemes
+    pub pcs_opening_bytes: usize,
+
+    /// approximate number of field multiplications done by the prover, PCS excluded
+    pub field_ops: usize,
+    /// memory of the prover scratchpads allocated by `prepare_mem` for this circuit, without the
+    /// per-proof buffers; not a peak
+    pub scratchpad_allocated_bytes: usize,
+}
+
+impl ProofCostEstimate {
+    pub fn proof_bytes(&self) -> usize {
+        self.commitment_bytes
+            + self.grinding_bytes
+            + self.sumcheck_bytes.iter().sum::<usize>()
+            + self.claim_bytes.iter().sum::<usize>()
+            + self.lookup_bytes
+            + self.merge_bytes
+            + self.pcs_opening_bytes
+    }
+}
+
+/// Estimate the cost of proving `circuit` under `config`, with the commitment scheme verified
+/// by `PC` using parameters `pp`.
+pub fn estimate_proof_cost<F, PC>(
+    circuit: &Circuit<F>,
+    config: &Config,
+    pp: &PC::Param,
+) -> ProofCostEstimate
+where
+    F: Field + FieldSerde,
+    PC: PolyCommitVerifier<F>,
+{
+    let reps = config
+        .for_challenge_field::<F::ChallengeField>()
+        .get_num_repetitions();
+    // everything after the commitment is sent in the challenge field
+    let challenge_size = F::ChallengeField::SIZE;
+    let input_var_num = circuit.log_input_size();
+    // with lookups the multiplicities are committed with the input, in zero-knowledge mode
+    // the masks
+    let committed_var_num = if !circuit.lookups.is_empty() {
+        LookupLayout::new(circuit).var_num
+    } else if config.zero_knowledge {
+        ZkLayout::new(circuit, reps).var_num
+    } else {
+        input_var_num
+    };
+
+    // in zero-knowledge mode, a round over w and the sum and the claim of the masks
+    let (sumcheck_bytes, claim_bytes) = if config.zero_knowledge {
+        let sumcheck_bytes = circuit
+            .layers
+            .iter()
+            .map(|layer| (2 * layer.input_var_num + 1) * reps * ZK_EVALS_PER_ROUND * challenge_size)
+            .collect();
+        (
+            sumcheck_bytes,
+            vec![4 * reps * challenge_size; circuit.layers.len()],
+        )
+    } else {
+        let sumcheck_bytes = circuit
+            .layers
+            .iter()
+            .map(|layer| 2 * layer.input_var_num * reps * EVALS_PER_ROUND * challenge_size)
+            .collect();
+        (
+            sumcheck_bytes,
+            vec![2 * reps * challenge_size; circuit.layers.len()],
+        )
+    };
+    // the claim at a point of the random half of the committed polynomial
+    let pad_claim_bytes = if config.zero_knowledge {
+        challenge_size
+    } else {
+        0
+    };
+
+    // circuit evaluation
+    let mut field_ops = circuit
+        .layers
+        .iter()
+        .map(|layer| 2 * layer.mul.len() + layer.add.len() + layer.const_.len())
+        .sum::<usize>();
+    // per repetition: eq tables at rz0/rz1/rx, gate scatter, and two sumchecks over the input
+    // where each round evaluates (3 products) and folds (2 products) the bookkeeping tables
+    for layer in &circuit.layers {
+        let input_num = 1 << layer.input_var_num;
+        let output_num = 1 << layer.output_var_num;
+        let per_rep = 4 * output_num
+            + 2 * input_num
+            + 5 * layer.mul.len()
+            + 2 * layer.add.len()
+            + 2 * 5 * (input_num - 1);
+        field_ops += per_rep * reps;
+    }
+    // merge: eq tables of the 2 * reps points, their combination, then the sumcheck
+    field_ops += (1 << committed_var_num) * (2 * reps * 2 + 2 * reps + 5);
+
+    let max_num_input_var = circuit.layers.iter().map(|l| l.input_var_num).max();
+    let max_num_output_var = circuit.layers.iter().map(|l| l.output_var_num).max();
+    let max_input_num = 1 << max_num_input_var.unwrap();
+    let max_output_num = 1 << max_num_output_var.unwrap();
+    let scratchpad_allocated_bytes = reps
+        * (3 * max_input_num * size_of::<F::ChallengeField>()
+            + 4 * max_output_num * size_of::<F::ChallengeField>()
+            + max_input_num * size_of::<bool>());
+
+    ProofCostEstimate {
+        commitment_bytes: PC::commitment_size(pp, committed_var_num),
+        grinding_bytes: GRINDING_NONCE_BYTES,
+        sumcheck_bytes,
+        claim_bytes,
+        lookup_bytes: circuit
+            .lookups
+            .iter()
+            .map(|column| {
+                lookup_proof_bytes(column.input_ids.len(), column.table.len(), challenge_size)
+            })
+            .sum(),
+        merge_bytes: committed_var_num * EVALS_PER_ROUND * challenge_size + pad_claim_bytes,
+        pcs_opening_bytes: PC::opening_size(pp, committed_var_num),
+        field_ops,
+        scratchpad_allocated_bytes,
+    }
+}
@@ -0,0 +1,254 @@
+//! Diagnostics for circuits whose outputs are not all zero after `Circuit::evaluate`.
+//!
+//! Starting from a nonzero output, the debugger walks backwards through the layers and reports
+//! every gate that contributed to the traced wires, together with the wire values it read.
+//! When the circuit comes from the compiler, gates are mapped back to the leaf `Segment` and
+//! the `Allocation` they were instantiated from.
+
+use std::collections::HashSet;
+
+use arith::Field;
+
+use crate::{Allocation, Circuit, GateBuckets, RecursiveCircuit, SegmentId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateType {
+    Mul,
+    Add,
+    Const,
+}
+
+/// Where a flattened gate comes from in the `RecursiveCircuit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GateOrigin {
+    pub segment_id: SegmentId,
+    pub allocation: Allocation,
+    /// index of the gate inside the leaf segment's gate list of the same type
+    pub local_idx: usize,
+}
+
+/// Gate origins of one flattened layer, in the same order as the layer's gate lists.
+#[derive(Debug, Clone, Default)]
+pub struct LayerGateOrigins {
+    pub mul: Vec<GateOrigin>,
+    pub add: Vec<GateOrigin>,
+    pub const_: Vec<GateOrigin>,
+}
+
+/// A gate writing into a traced wire.
+#[derive(Debug, Clone)]
+pub struct GateTrace<F: Field> {
+    pub gate_type: GateType,
+    /// index of the gate in `layer.mul`, `layer.add` or `layer.const_`
+    pub gate_idx: usize,
+    pub i_ids: Vec<usize>,
+    pub input_vals: Vec<F>,
+    pub coef: F::BaseField,
+    /// the value this gate adds to its output wire
+    pub contribution: F,
+    pub origin: Option<GateOrigin>,
+}
+
+/// A wire at the output of `layer`, with all the gates that feed it.
+#[derive(Debug, Clone)]
+pub struct WireTrace<F: Field> {
+    pub layer: usize,
+    pub wire: usize,
+    pub value: F,
+    pub gates: Vec<GateTrace<F>>,
+}
+
+/// A nonzero circuit output and the wires it depends on, from the output layer backwards.
+#[derive(Debug, Clone)]
+pub struct UnsatisfiedOutput<F: Field> {
+    pub o_id: usize,
+    pub value: F,
+    pub trace: Vec<WireTrace<F>>,
+}
+
+impl<F: Field> RecursiveCircuit<F> {
+    /// Gate origins of every layer of `self.flatten()`.
+    pub fn gate_origins(&self) -> Vec<LayerGateOrigins> {
+        self.layers
+            .iter()
+            .map(|layer_id| {
+                let mut origins = LayerGateOrigins::default();
+                for (segment_id, allocations) in self.layer_leaves(*layer_id) {
+                    let segment = &self.segments[segment_id];
+                    for allocation in allocations {
+                        let origin = |local_idx| GateOrigin {
+                            segment_id,
+                            allocation,
+                            local_idx,
+                        };
+                        origins.mul.extend((0..segment.gate_muls.len()).map(origin));
+                        origins.add.extend((0..segment.gate_adds.len()).map(origin));
+                        origins
+                            .const_
+                            .extend((0..segment.gate_consts.len()).map(origin));
+                    }
+                }
+                origins
+            })
+            .collect()
+    }
+}
+
+struct LayerIndex {
+    mul: GateBuckets,
+    add: GateBuckets,
+    const_: GateBuckets,
+}
+
+pub struct CircuitDebugger<'a, F: Field> {
+    circuit: &'a Circuit<F>,
+    index: Vec<LayerIndex>,
+    origins: Option<Vec<LayerGateOrigins>>,
+}
+
+impl<'a, F: Field> CircuitDebugger<'a, F> {
+    /// `circuit` must have been evaluated.
+    pub fn new(circuit: &'a Circuit<F>) -> Self {
+        let index = circuit
+            .layers
+            .iter()
+            .map(|layer| {
+                let output_num = 1 << layer.output_var_num;
+                LayerIndex {
+                    mul: GateBuckets::new(&layer.mul, output_num),
+                    add: GateBuckets::new(&layer.add, output_num),
+                    const_: GateBuckets::new(&layer.const_, output_num),
+                }
+            })
+            .collect();
+        CircuitDebugger {
+            circuit,
+            index,
+            origins: None,
+        }
+    }
+
+    /// Same as `new`, for a circuit obtained with `rc.flatten()`; gates are reported with their
+    /// segment and allocation.
+    pub fn with_recursive_circuit(circuit: &'a Circuit<F>, rc: &RecursiveCircuit<F>) -> Self {
+        let origins = rc.gate_origins();
+        assert_eq!(origins.len(), circuit.layers.len());
+        let mut ret = Self::new(circuit);
+        ret.origins = Some(origins);
+        ret
+    }
+
+    /// Indices of the nonzero outputs.
+    pub fn unsatisfied_outputs(&self) -> Vec<usize> {
+        self.circuit
+            .layers
+            .last()
+            .unwrap()
+            .output_vals
+            .evals
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| !v.is_zero())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Trace every nonzero output back through at most `max_depth` layers.
+    pub fn diagnose(&self, max_depth: usize) -> Vec<UnsatisfiedOutput<F>> {
+        let output_vals = &self.circuit.layers.last().unwrap().output_vals.evals;
+        self.unsatisfied_outputs()
+            .into_iter()
+            .map(|o_id| UnsatisfiedOutput {
+                o_id,
+                value: output_vals[o_id],
+                trace: self.trace_output(o_id, max_depth),
+            })
+            .collect()
+    }
+
+    /// Breadth-first walk from output `o_id` towards the input layer. The result lists the
+    /// traced wires layer by layer, starting with the output wire itself.
+    pub fn trace_output(&self, o_id: usize, max_depth: usize) -> Vec<WireTrace<F>> {
+        let layer_num = self.circuit.layers.len();
+        let mut ret = vec![];
+        let mut frontier = vec![o_id];
+        for layer_idx in (0..layer_num).rev().take(max_depth) {
+            let mut next_frontier = HashSet::new();
+            for &wire in &frontier {
+                let wire_trace = self.trace_wire(layer_idx, wire);
+                for gate in &wire_trace.gates {
+                    next_frontier.extend(gate.i_ids.iter().cloned());
+                }
+                ret.push(wire_trace);
+            }
+            frontier = next_frontier.into_iter().collect();
+            frontier.sort_unstable();
+        }
+        ret
+    }
+
+    /// All gates of `layer_idx` writing into output wire `wire`.
+    pub fn trace_wire(&self, layer_idx: usize, wire: usize) -> WireTrace<F> {
+        let layer = &self.circuit.layers[layer_idx];
+        let index = &self.index[layer_idx];
+        let origins = self.origins.as_ref().map(|o| &o[layer_idx]);
+
+        let mut gates = vec![];
+        for &gate_idx in index.mul.gate_ids(wire) {
+            let gate = &layer.mul[gate_idx];
+            let input_vals = vec![
+                layer.input_vals.evals[gate.i_ids[0]],
+                layer.input_vals.evals[gate.i_ids[1]],
+            ];
+            gates.push(GateTrace {
+                gate_type: GateType::Mul,
+                gate_idx,
+                i_ids: gate.i_ids.to_vec(),
+                contribution: (input_vals[0] * input_vals[1]).mul_base_elem(&gate.coef),
+                input_vals,
+                coef: gate.coef,
+                origin: origins.map(|o| o.mul[gate_idx]),
+            });
+        }
+        for &gate_idx in index.add.gate_ids(wire) {
+            let gate = &layer.add[gate_idx];
+            let input_vals = vec![layer.input_vals.evals[gate.i_ids[0]]];
+            gates.push(GateTrace {
+                gate_type: GateType::Add,
+                gate_idx,
+                i_ids: gate.i_ids.to_vec(),
+                contribution: input_vals[0].mul_base_elem(&gate.coef),
+                input_vals,
+                coef: gate.coef,
+                origin: origins.map(|o| o.add[gate_idx]),
+            });
+        }
+        for &gate_idx in index.const_.gate_ids(wire) {
+            let gate = &layer.const_[gate_idx];
+            gates.push(GateTrace {
+                gate_type: GateType::Const,
+                gate_idx,
+                i_ids: vec![],
+                contribution: F::one().mul_base_elem(&gate.coef),
+                input_vals: vec![],
+                coef: gate.coef,
+                origin: origins.map(|o| o.const_[gate_idx]),
+            });
+        }
+
+        WireTrace {
+            layer: layer_idx,
+            wire,
+            value: self.output_value(layer_idx, wire),
+            gates,
+        }
+    }
+
+    fn output_value(&self, layer_idx: usize, wire: usize) -> F {
+        let layers = &self.circuit.layers;
+        match layers.get(layer_idx + 1) {
+            Some(next_layer) => next_layer.input_vals.evals[wire],
+            None => layers[layer_idx].output_vals.evals[wire],
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Errors of the verifier and of the prover. A verifier reading a malformed proof returns an
+//! error instead of panicking, and so does a prover given a statement it cannot prove.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationError {
+    /// the proof ends before the verifier has read all of it
+    TruncatedProof,
+    /// bytes left in the proof after the verifier has read all of it
+    TrailingBytes(usize),
+    /// bytes of the proof that do not encode a field element or a commitment
+    MalformedProof,
+    /// the circuit, the claimed outputs and the configuration do not go together
+    InvalidStatement(&'static str),
+    /// the nonce of the proof of work has too few leading zero bits
+    ProofOfWork,
+    /// the sumcheck of the layer `layer` fails at the round `round`; the round after the last
+    /// one is the final check of the layer against its wiring
+    LayerSumcheck { layer: usize, round: usize },
+    /// a round of the sumcheck merging the claims on the committed polynomial, or of another
+    /// sumcheck verified by `sumcheck_verify`
+    Sumcheck { round: usize },
+    /// the lookup argument of the lookup column `column`
+    Lookup { column: usize },
+    /// the opening of the polynomial commitment
+    PolyCommitOpening,
+    /// a Merkle path of the queries in the folding round `round`
+    MerklePath { round: usize },
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::TruncatedProof => write!(f, "truncated proof"),
+            VerificationError::TrailingBytes(n) => write!(f, "{} trailing bytes in the proof", n),
+            VerificationError::MalformedProof => write!(f, "malformed proof"),
+            VerificationError::InvalidStatement(reason) => {
+                write!(f, "invalid statement: {}", reason)
+            }
+            VerificationError::ProofOfWork => write!(f, "invalid proof of work"),
+            VerificationError::LayerSumcheck { layer, round } => {
+                write!(f, "sumcheck of layer {} failed at round {}", layer, round)
+            }
+            VerificationError::Sumcheck { round } => {
+                write!(f, "sumcheck failed at round {}", round)
+            }
+            VerificationError::Lookup { column } => {
+                write!(f, "lookup argument of column {} failed", column)
+            }
+            VerificationError::PolyCommitOpening => write!(f, "invalid polynomial opening"),
+            VerificationError::MerklePath { round } => {
+                write!(f, "invalid merkle path in folding round {}", round)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProverError {
+    /// the circuits, their witnesses and the configuration do not go together
+    InvalidStatement(&'static str),
+    /// the input `input_id` of the lookup column `column` is not in its table
+    NotInLookupTable { column: usize, input_id: usize },
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProverError::InvalidStatement(reason) => write!(f, "invalid statement: {}", reason),
+            ProverError::NotInLookupTable { column, input_id } => write!(
+                f,
+                "input {} of lookup column {} is not in the lookup table",
+                input_id, column
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProverError {}
@@ -1,3 +1,7 @@
 pub mod hashes;
 pub use self::hashes::*;
 pub mod merkle_tree;
+pub mod mimc;
+pub use self::mimc::*;
+pub mod poseidon;
+pub use self::poseidon::*;
@@ -1,4 +1,6 @@
 use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
 #[derive(Debug, Clone, Default)]
 pub struct SHA256hasher;
 
@@ -12,3 +14,18 @@ impl SHA256hasher {
         buffer.copy_from_slice(&hashed[..]);
     }
 }
+
+/// Keccak-256, the hash of Ethereum, with the original padding rather than the one of SHA3-256.
+#[derive(Debug, Clone, Default)]
+pub struct Keccak256hasher;
+
+impl Keccak256hasher {
+    pub fn hash(&self, output: &mut [u8], input: &[u8], input_len: usize) {
+        let hashed = Keccak256::digest(&input[..input_len]);
+        output.copy_from_slice(&hashed[..]);
+    }
+    pub fn hash_inplace(&self, buffer: &mut [u8], input_len: usize) {
+        let hashed = Keccak256::digest(&buffer[..input_len]);
+        buffer.copy_from_slice(&hashed[..]);
+    }
+}
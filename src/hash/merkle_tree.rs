@@ -1,5 +1,8 @@
+use halo2curves::bn256::Fr;
 use rs_merkle::{Hasher, MerkleProof, MerkleTree};
 
+use crate::mimc7_hash;
+
 #[derive(Debug, Clone)]
 pub struct Blake3Algorithm {}
 
@@ -87,7 +90,9 @@ impl MerkleTreeVerifier {
         indices: &Vec<usize>,
         leaves: &Vec<Vec<u8>>,
     ) -> bool {
-        let proof = MerkleProof::<Blake3Algorithm>::try_from(proof_bytes).unwrap();
+        let Ok(proof) = MerkleProof::<Blake3Algorithm>::try_from(proof_bytes) else {
+            return false;
+        };
         let leaves_to_prove: Vec<[u8; HASH_SIZE]> =
             leaves.iter().map(|x| Blake3Algorithm::hash(x)).collect();
         proof.verify(
@@ -115,6 +120,49 @@ impl MerkleRoot {
     }
 }
 
+/// A Merkle tree over BN254 hashed with MiMC7, whose paths are cheap to check in a circuit,
+/// see `crate::recursion`. A node is the hash of its two children.
+#[derive(Debug, Clone)]
+pub struct MiMC7MerkleTree {
+    /// the leaves, then each level up to the root
+    levels: Vec<Vec<Fr>>,
+}
+
+impl MiMC7MerkleTree {
+    // leaf number should be 2^n
+    pub fn new(leaves: Vec<Fr>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap().chunks(2).map(mimc7_hash).collect();
+            levels.push(level);
+        }
+        Self { levels }
+    }
+
+    pub fn root(&self) -> Fr {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// The siblings of the leaf `index` and of its ancestors below the root, from the leaf up.
+    pub fn path(&self, index: usize) -> Vec<Fr> {
+        self.levels[..self.levels.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(level, nodes)| nodes[(index >> level) ^ 1])
+            .collect()
+    }
+
+    /// The root of the tree where the leaf `index` is `leaf`, with the siblings `path`.
+    pub fn root_from_path(leaf: Fr, index: usize, path: &[Fr]) -> Fr {
+        path.iter()
+            .enumerate()
+            .fold(leaf, |node, (level, &sibling)| match (index >> level) & 1 {
+                0 => mimc7_hash(&[node, sibling]),
+                _ => mimc7_hash(&[sibling, node]),
+            })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use arith::{Field, FieldSerde, Msn61};
@@ -157,4 +205,19 @@ mod tests {
         ];
         assert!(verifier.verify(proof_bytes, &leaf_indices, &open_values));
     }
+
+    #[test]
+    fn mimc7_path() {
+        let leaves: Vec<Fr> = (0..8u32).map(Fr::from).collect();
+        let tree = MiMC7MerkleTree::new(leaves.clone());
+        for (i, &leaf) in leaves.iter().enumerate() {
+            let path = tree.path(i);
+            assert_eq!(path.len(), 3);
+            assert_eq!(MiMC7MerkleTree::root_from_path(leaf, i, &path), tree.root());
+            assert_ne!(
+                MiMC7MerkleTree::root_from_path(leaf, i ^ 1, &path),
+                tree.root()
+            );
+        }
+    }
 }
@@ -0,0 +1,72 @@
+//! MiMC-7 over the BN254 scalar field, a hash that is cheap to evaluate in an arithmetic
+//! circuit, see `crate::recursion`.
+//!
+//! The permutation `E_k(x)` runs `MIMC7_ROUNDS` rounds `x <- (x + k + c_i)^7` and returns
+//! `x + k`, with `c_0 = 0` and `c_i` the `i`-th hash of the SHA-256 chain seeded with
+//! `b"mimc7"`, reduced into the field. Messages are hashed with the Miyaguchi-Preneel
+//! construction `h <- h + m + E_h(m)`, from `h = 0`, over their 32-byte little-endian chunks,
+//! the last one padded with zeros.
+
+use std::sync::OnceLock;
+
+use arith::{Field, FieldSerde};
+use halo2curves::bn256::Fr;
+use sha2::{Digest, Sha256};
+
+pub const MIMC7_ROUNDS: usize = 91;
+
+/// The round constants `c_0, ..., c_90`.
+pub fn mimc7_round_constants() -> &'static [Fr] {
+    static CONSTANTS: OnceLock<Vec<Fr>> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        let mut digest: [u8; 32] = Sha256::digest(b"mimc7").into();
+        let mut constants = vec![Fr::zero()];
+        for _ in 1..MIMC7_ROUNDS {
+            constants.push(Fr::from_uniform_bytes(&digest));
+            digest = Sha256::digest(digest).into();
+        }
+        constants
+    })
+}
+
+/// `E_k(x)`.
+pub fn mimc7_permute(x: Fr, k: Fr) -> Fr {
+    let x = mimc7_round_constants().iter().fold(x, |x, c| {
+        let t = x + k + c;
+        let t2 = t.square();
+        t2.square() * t2 * t
+    });
+    x + k
+}
+
+/// The hash of the field elements `ms`.
+pub fn mimc7_hash(ms: &[Fr]) -> Fr {
+    ms.iter()
+        .fold(Fr::zero(), |h, &m| h + m + mimc7_permute(m, h))
+}
+
+/// The 32-byte little-endian chunks of `bytes`, the last one padded with zeros, reduced into
+/// the field: the elements `MiMC7hasher` hashes for `bytes`.
+pub fn bytes_to_fr_chunks(bytes: &[u8]) -> Vec<Fr> {
+    bytes
+        .chunks(32)
+        .map(|chunk| {
+            let mut padded = [0u8; 32];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            Fr::from_uniform_bytes(&padded)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MiMC7hasher;
+
+impl MiMC7hasher {
+    pub fn hash(&self, output: &mut [u8], input: &[u8], input_len: usize) {
+        mimc7_hash(&bytes_to_fr_chunks(&input[..input_len])).serialize_into(output);
+    }
+    pub fn hash_inplace(&self, buffer: &mut [u8], input_len: usize) {
+        let hashed = mimc7_hash(&bytes_to_fr_chunks(&buffer[..input_len]));
+        hashed.serialize_into(buffer);
+    }
+}
@@ -0,0 +1,199 @@
+//! Poseidon over the BN254 scalar field, with the parameters of circomlib: a state of
+//! `POSEIDON_WIDTH = 3` elements, the S-box `x^5`, `POSEIDON_FULL_ROUNDS = 8` full rounds and
+//! `POSEIDON_PARTIAL_ROUNDS = 57` partial rounds, so that `poseidon_hash([a, b])` is
+//! `Poseidon([a, b])` of circomlib.
+//!
+//! The round constants and the Cauchy MDS matrix are sampled as in the reference script
+//! `generate_parameters_grain.sage` of the Poseidon paper, from its Grain LFSR seeded with the
+//! parameters above.
+//!
+//! The transcript hashes with a sponge of rate 2 on the same permutation, see `poseidon_sponge`.
+
+use std::sync::OnceLock;
+
+use arith::{Field, FieldSerde};
+use halo2curves::bn256::Fr;
+
+pub const POSEIDON_WIDTH: usize = 3;
+pub const POSEIDON_FULL_ROUNDS: usize = 8;
+pub const POSEIDON_PARTIAL_ROUNDS: usize = 57;
+
+const FIELD_BITS: usize = 254;
+
+/// The Grain LFSR of the Poseidon parameter generation, in self-shrinking mode.
+struct Grain {
+    /// bit `i` is the `i`-th bit of the 80-bit state, the oldest first
+    state: u128,
+}
+
+impl Grain {
+    fn new() -> Self {
+        // field GF(p), S-box x^alpha, field size, width, rounds, then 30 ones, msb first
+        let mut bits = vec![];
+        for (value, width) in [
+            (1, 2),
+            (0, 4),
+            (FIELD_BITS, 12),
+            (POSEIDON_WIDTH, 12),
+            (POSEIDON_FULL_ROUNDS, 10),
+            (POSEIDON_PARTIAL_ROUNDS, 10),
+        ] {
+            bits.extend((0..width).rev().map(|i| (value >> i) & 1 == 1));
+        }
+        bits.resize(80, true);
+        let state = bits
+            .iter()
+            .enumerate()
+            .fold(0u128, |state, (i, &bit)| state | ((bit as u128) << i));
+        let mut grain = Grain { state };
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let s = self.state;
+        let bit = (s >> 62 ^ s >> 51 ^ s >> 38 ^ s >> 23 ^ s >> 13 ^ s) & 1;
+        self.state = (s >> 1) | (bit << 79);
+        bit == 1
+    }
+
+    /// The next output bit: a bit is kept only after a one.
+    fn next_bit(&mut self) -> bool {
+        while !self.step() {
+            self.step();
+        }
+        self.step()
+    }
+
+    /// The next `FIELD_BITS` bits, msb first, as little-endian bytes.
+    fn next_bytes(&mut self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for i in (0..FIELD_BITS).rev() {
+            if self.next_bit() {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes
+    }
+
+    /// The next element, sampled again while the bits are not below the modulus.
+    fn next_element(&mut self) -> Fr {
+        loop {
+            let bytes = self.next_bytes();
+            let f = Fr::from_uniform_bytes(&bytes);
+            let mut reduced = [0u8; 32];
+            f.serialize_into(&mut reduced);
+            if reduced == bytes {
+                return f;
+            }
+        }
+    }
+}
+
+pub struct PoseidonParams {
+    /// `POSEIDON_WIDTH` constants per round
+    pub round_constants: Vec<Fr>,
+    pub mds: [[Fr; POSEIDON_WIDTH]; POSEIDON_WIDTH],
+}
+
+pub fn poseidon_params() -> &'static PoseidonParams {
+    static PARAMS: OnceLock<PoseidonParams> = OnceLock::new();
+    PARAMS.get_or_init(|| {
+        let mut grain = Grain::new();
+        let round_constants = (0..(POSEIDON_FULL_ROUNDS + POSEIDON_PARTIAL_ROUNDS)
+            * POSEIDON_WIDTH)
+            .map(|_| grain.next_element())
+            .collect();
+        // the matrix 1 / (x_i + y_j), for distinct x_i and y_j reduced mod p; the first
+        // sample passes the checks of the reference script for these parameters
+        let xs: [Fr; 2 * POSEIDON_WIDTH] =
+            std::array::from_fn(|_| Fr::from_uniform_bytes(&grain.next_bytes()));
+        let mds = std::array::from_fn(|i| {
+            std::array::from_fn(|j| (xs[i] + xs[POSEIDON_WIDTH + j]).inv().unwrap())
+        });
+        PoseidonParams {
+            round_constants,
+            mds,
+        }
+    })
+}
+
+fn sbox(x: Fr) -> Fr {
+    let x2 = x.square();
+    x2.square() * x
+}
+
+pub fn poseidon_permute(state: &mut [Fr; POSEIDON_WIDTH]) {
+    let params = poseidon_params();
+    let half_full_rounds = POSEIDON_FULL_ROUNDS / 2;
+    for (r, constants) in params.round_constants.chunks(POSEIDON_WIDTH).enumerate() {
+        for (x, c) in state.iter_mut().zip(constants) {
+            *x += c;
+        }
+        if r < half_full_rounds || r >= half_full_rounds + POSEIDON_PARTIAL_ROUNDS {
+            state.iter_mut().for_each(|x| *x = sbox(*x));
+        } else {
+            state[0] = sbox(state[0]);
+        }
+        *state = params.mds.map(|row| {
+            row.iter()
+                .zip(state.iter())
+                .fold(Fr::zero(), |acc, (m, x)| acc + *m * x)
+        });
+    }
+}
+
+/// `Poseidon([a, b])` of circomlib: the first element of the permutation of `[0, a, b]`.
+pub fn poseidon_hash(inputs: [Fr; 2]) -> Fr {
+    let mut state = [Fr::zero(), inputs[0], inputs[1]];
+    poseidon_permute(&mut state);
+    state[0]
+}
+
+/// The hash of any number of field elements: `ms` padded with a one then zeros to an even
+/// length, each pair added to the rate `state[1..]` before a permutation, from the zero state.
+/// The hash is `state[1]`.
+pub fn poseidon_sponge(ms: &[Fr]) -> Fr {
+    let mut padded = ms.to_vec();
+    padded.push(Fr::one());
+    padded.resize(padded.len().next_multiple_of(2), Fr::zero());
+    let mut state = [Fr::zero(); POSEIDON_WIDTH];
+    for pair in padded.chunks(2) {
+        state[1] += pair[0];
+        state[2] += pair[1];
+        poseidon_permute(&mut state);
+    }
+    state[1]
+}
+
+/// Poseidon sponge of the transcript, see `FiatShamirHasher::absorb_f`: a BN254 element is
+/// absorbed as itself, any other message as its `bytes_to_elements`.
+#[derive(Debug, Clone, Default)]
+pub struct PoseidonHasher;
+
+impl PoseidonHasher {
+    /// The length of `bytes`, then `bytes` by chunks of 31, each zero-padded into an element
+    /// below `2^248`: an injective encoding, unlike the reduction of 32-byte chunks mod p.
+    pub fn bytes_to_elements(bytes: &[u8]) -> Vec<Fr> {
+        let mut elements = vec![Fr::from(bytes.len() as u32)];
+        elements.extend(bytes.chunks(31).map(|chunk| {
+            let mut padded = [0u8; 32];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            Fr::from_uniform_bytes(&padded)
+        }));
+        elements
+    }
+
+    pub fn hash(&self, output: &mut [u8], input: &[u8], input_len: usize) {
+        self.hash_elements(output, &Self::bytes_to_elements(&input[..input_len]));
+    }
+    pub fn hash_inplace(&self, buffer: &mut [u8], input_len: usize) {
+        let hashed = poseidon_sponge(&Self::bytes_to_elements(&buffer[..input_len]));
+        hashed.serialize_into(buffer);
+    }
+    pub fn hash_elements(&self, output: &mut [u8], elements: &[Fr]) {
+        poseidon_sponge(elements).serialize_into(output);
+    }
+}
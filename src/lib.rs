@@ -1,20 +1,56 @@
 // #![cfg_attr(target_arch = "x86_64", feature(stdarch_x86_avx512))]
 
 #![feature(is_sorted)]
+pub mod batch;
+pub use batch::*;
+
+pub mod bristol;
+pub use bristol::*;
+
 pub mod circuit;
 pub use circuit::*;
 
 pub mod config;
 pub use config::*;
 
+pub mod cost;
+pub use cost::*;
+
+pub mod debugger;
+pub use debugger::*;
+
+pub mod error;
+pub use error::*;
+
 pub mod hash;
 pub use hash::*;
 
+pub mod lookup;
+pub use lookup::*;
+
 pub mod poly_commit;
 pub use poly_commit::*;
 
 pub mod prover;
 pub use prover::*;
 
+pub mod r1cs;
+pub use r1cs::*;
+
+pub mod recursion;
+pub use recursion::*;
+
+pub mod sumcheck;
+pub use sumcheck::*;
+
 pub mod verifier;
 pub use verifier::*;
+
+pub mod wiring;
+pub use wiring::*;
+
+pub mod witness;
+pub use witness::*;
+
+pub mod zk;
+pub use zk::*;
@@ -0,0 +1,374 @@
+//! Lookup arguments, in the logUp style with a fractional-sum GKR.
+//!
+//! A circuit declares lookup columns: values of its input layer that must all appear in a
+//! fixed public table. With `m_t` the number of times the table entry `t_t` is looked up,
+//! the column is in the table iff, for a random `X`,
+//! `sum_i 1 / (X - w_i) - sum_t m_t / (X - t_t) = 0`.
+//!
+//! The multiplicities are committed together with the input layer, in a single polynomial
+//! laid out as `[input | multiplicities of each column | zero padding]`. Each side of the
+//! equation is a sum of fractions `p / q`, proved by a binary tree of fraction additions:
+//! the prover sends the two children of the root, then every level reduces a claim on the
+//! numerators and denominators of its fractions to a claim on the level below by a degree 3
+//! sumcheck. The leaves are padded with `0 / 1`, and their final claims reduce to inner
+//! products of the committed polynomial with public sparse vectors, merged with the input
+//! claims into the single opening.
+//!
+//! The multiplicities are counted on whole field elements, so with a SIMD field all the lanes
+//! of a looked up value must be the same table entry. Lookups are not supported in batches,
+//! nor in zero-knowledge mode.
+
+use std::collections::HashMap;
+
+use arith::{Field, FieldSerde};
+
+use crate::{
+    eq_evals_at_primitive, sumcheck_prove, sumcheck_verify, Circuit, Proof, ProverError,
+    Transcript, TranscriptPhase, VerificationError, VirtualPolynomial,
+};
+
+/// The values of the input layer at `input_ids` must all appear in `table`, or the prover
+/// returns `ProverError::NotInLookupTable`. A circuit with lookup columns can be neither proved
+/// in a batch nor in zero-knowledge mode.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LookupColumn<F: Field> {
+    pub input_ids: Vec<usize>,
+    pub table: Vec<F::BaseField>,
+}
+
+/// The claim that the inner product of the committed polynomial with the sparse vector
+/// `weights`, given as `(index, weight)` pairs, is `value`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InnerProductClaim<C> {
+    pub weights: Vec<(usize, C)>,
+    pub value: C,
+}
+
+/// Position of the multiplicities in the committed polynomial.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LookupLayout {
+    pub input_var_num: usize,
+    /// number of variables of the committed polynomial, which the PCS parameters must match
+    pub var_num: usize,
+    /// `multiplicity_offsets[k]` is the offset of the multiplicities of column `k`
+    pub multiplicity_offsets: Vec<usize>,
+}
+
+impl LookupLayout {
+    pub fn new<F: Field>(circuit: &Circuit<F>) -> Self {
+        let input_var_num = circuit.log_input_size();
+        let mut offset = 1usize << input_var_num;
+        let mut multiplicity_offsets = vec![];
+        for column in &circuit.lookups {
+            multiplicity_offsets.push(offset);
+            offset += column.table.len();
+        }
+        LookupLayout {
+            input_var_num,
+            var_num: offset.next_power_of_two().trailing_zeros() as usize,
+            multiplicity_offsets,
+        }
+    }
+
+    /// The polynomial to commit to: `input` followed by the multiplicities of each column, or
+    /// the first input missing from its table.
+    pub(crate) fn committed_poly<F: Field + FieldSerde>(
+        &self,
+        circuit: &Circuit<F>,
+        input: &[F],
+    ) -> Result<Vec<F>, ProverError> {
+        let mut evals = input.to_vec();
+        evals.resize(1 << self.var_num, F::zero());
+        let columns = circuit.lookups.iter().zip(&self.multiplicity_offsets);
+        for (k, (column, &offset)) in columns.enumerate() {
+            let mut table_idx = HashMap::new();
+            for (t, entry) in column.table.iter().enumerate() {
+                table_idx.entry(serialize(&lift::<F>(entry))).or_insert(t);
+            }
+            for &i in &column.input_ids {
+                let t =
+                    *table_idx
+                        .get(&serialize(&input[i]))
+                        .ok_or(ProverError::NotInLookupTable {
+                            column: k,
+                            input_id: i,
+                        })?;
+                evals[offset + t] += F::one();
+            }
+        }
+        Ok(evals)
+    }
+}
+
+fn lift<F: Field>(t: &F::BaseField) -> F {
+    F::one().mul_base_elem(t)
+}
+
+fn serialize<F: FieldSerde + Field>(f: &F) -> Vec<u8> {
+    let mut bytes = vec![0; F::SIZE];
+    f.serialize_into(&mut bytes);
+    bytes
+}
+
+/// Number of variables of a tree of fractions with `len` leaves, at least 1.
+fn tree_var_num(len: usize) -> usize {
+    (len.next_power_of_two().trailing_zeros() as usize).max(1)
+}
+
+/// `eq(point, i)` for all `i < len`.
+fn eq_evals<C: Field>(point: &[C], len: usize) -> Vec<C> {
+    let mut evals = vec![C::zero(); 1 << point.len()];
+    eq_evals_at_primitive(point, &C::one(), &mut evals);
+    evals.truncate(len);
+    evals
+}
+
+/// Proof bytes of the lookup of a column with `column_len` values in a table of `table_len`
+/// entries, fields elements of `field_size` bytes.
+pub(crate) fn lookup_proof_bytes(column_len: usize, table_len: usize, field_size: usize) -> usize {
+    let tree_bytes = |var_num: usize| {
+        // the children of the root, then a sumcheck and the children at every level
+        4 + (1..var_num).map(|d| 4 * d + 4).sum::<usize>()
+    };
+    (tree_bytes(tree_var_num(column_len)) + tree_bytes(tree_var_num(table_len))) * field_size
+}
+
+/// Prove that the sum of the fractions `p[i] / q[i]` is the fraction sent as the children of
+/// the root. Returns the point of the leaves the claims are reduced to.
+fn prove_fractional_sum<C: Field + FieldSerde>(
+    p: Vec<C>,
+    q: Vec<C>,
+    transcript: &mut Transcript,
+) -> Vec<C> {
+    let var_num = p.len().trailing_zeros() as usize;
+    // levels[d] holds the 2^d fractions of level d
+    let mut levels = vec![(vec![], vec![]); var_num + 1];
+    levels[var_num] = (p, q);
+    for d in (1..var_num).rev() {
+        let (p, q) = &levels[d + 1];
+        levels[d] = (0..1 << d)
+            .map(|y| {
+                let (p0, p1, q0, q1) = (p[2 * y], p[2 * y + 1], q[2 * y], q[2 * y + 1]);
+                (p0 * q1 + p1 * q0, q0 * q1)
+            })
+            .unzip();
+    }
+
+    let (p, q) = &levels[1];
+    for v in [p[0], p[1], q[0], q[1]] {
+        transcript.append_f(v);
+    }
+    let mut point = vec![transcript.challenge_fext::<C>()];
+    for d in 1..var_num {
+        let lambda = transcript.challenge_fext::<C>();
+        let (p, q) = &levels[d + 1];
+        let mut poly = VirtualPolynomial::new(d);
+        let eq = poly.add_mle(eq_evals(&point, 1 << d));
+        let p0 = poly.add_mle(p.iter().step_by(2).copied().collect());
+        let p1 = poly.add_mle(p.iter().skip(1).step_by(2).copied().collect());
+        let q0 = poly.add_mle(q.iter().step_by(2).copied().collect());
+        let q1 = poly.add_mle(q.iter().skip(1).step_by(2).copied().collect());
+        poly.add_product(C::one(), vec![eq, p0, q1]);
+        poly.add_product(C::one(), vec![eq, p1, q0]);
+        poly.add_product(lambda, vec![eq, q0, q1]);
+        let (rho, evals) = sumcheck_prove(poly, transcript);
+        for v in &evals[1..] {
+            transcript.append_f(*v);
+        }
+        point = [vec![transcript.challenge_fext::<C>()], rho].concat();
+    }
+    point
+}
+
+/// Result of the verification of a fractional sum.
+struct FractionalSumClaims<C> {
+    root_p: C,
+    root_q: C,
+    point: Vec<C>,
+    leaf_p: C,
+    leaf_q: C,
+}
+
+/// Verify a fractional sum of the lookup column `column`.
+fn verify_fractional_sum<C: Field + FieldSerde>(
+    var_num: usize,
+    column: usize,
+    transcript: &mut Transcript,
+    proof: &mut Proof,
+) -> Result<FractionalSumClaims<C>, VerificationError> {
+    let read = |transcript: &mut Transcript, proof: &mut Proof| {
+        let mut v = [C::zero(); 4];
+        for v in v.iter_mut() {
+            *v = proof.get_next_and_step::<C>()?;
+            transcript.append_f(*v);
+        }
+        Ok::<_, VerificationError>(v)
+    };
+    let failed = VerificationError::Lookup { column };
+    let [p0, p1, q0, q1] = read(transcript, proof)?;
+    let (root_p, root_q) = (p0 * q1 + p1 * q0, q0 * q1);
+    let mu = transcript.challenge_fext::<C>();
+    let mut point = vec![mu];
+    let mut claim_p = p0 + (p1 - p0) * mu;
+    let mut claim_q = q0 + (q1 - q0) * mu;
+    for d in 1..var_num {
+        let lambda = transcript.challenge_fext::<C>();
+        let (rho, eval) = sumcheck_verify(claim_p + lambda * claim_q, d, 3, transcript, proof)
+            .map_err(|e| match e {
+                VerificationError::Sumcheck { .. } => failed.clone(),
+                e => e,
+            })?;
+        let [p0, p1, q0, q1] = read(transcript, proof)?;
+        let eq: C = point
+            .iter()
+            .zip(&rho)
+            .map(|(&x, &y)| x * y + (C::one() - x) * (C::one() - y))
+            .product();
+        if eval != eq * (p0 * q1 + p1 * q0 + lambda * q0 * q1) {
+            return Err(failed);
+        }
+        let mu = transcript.challenge_fext::<C>();
+        point = [vec![mu], rho].concat();
+        claim_p = p0 + (p1 - p0) * mu;
+        claim_q = q0 + (q1 - q0) * mu;
+    }
+    Ok(FractionalSumClaims {
+        root_p,
+        root_q,
+        point,
+        leaf_p: claim_p,
+        leaf_q: claim_q,
+    })
+}
+
+/// Prove the lookups of `circuit`, whose multiplicities are committed in `committed` as laid
+/// out by `layout`. Returns the claims on the committed polynomial to merge.
+pub fn lookup_prove<F: Field + FieldSerde>(
+    circuit: &Circuit<F>,
+    layout: &LookupLayout,
+    committed: &[F],
+    transcript: &mut Transcript,
+) -> Vec<InnerProductClaim<F::ChallengeField>> {
+    let input = &committed[..1 << layout.input_var_num];
+    let mut claims = vec![];
+    let columns = circuit.lookups.iter().zip(&layout.multiplicity_offsets);
+    for (k, (column, &offset)) in columns.enumerate() {
+        transcript.set_phase(TranscriptPhase::Lookup(k));
+        let x = transcript.challenge_fext::<F::ChallengeField>();
+
+        // 1 / (X - w_i)
+        let size = 1 << tree_var_num(column.input_ids.len());
+        let mut p = vec![F::ChallengeField::zero(); size];
+        let mut q = vec![F::ChallengeField::one(); size];
+        for (i, &id) in column.input_ids.iter().enumerate() {
+            p[i] = F::ChallengeField::one();
+            q[i] = x - F::ChallengeField::from(input[id]);
+        }
+        let point = prove_fractional_sum(p, q, transcript);
+        let eq = eq_evals(&point, column.input_ids.len());
+        claims.push(InnerProductClaim {
+            value: column
+                .input_ids
+                .iter()
+                .zip(&eq)
+                .map(|(&id, e)| *e * F::ChallengeField::from(input[id]))
+                .sum(),
+            weights: column.input_ids.iter().copied().zip(eq).collect(),
+        });
+
+        // -m_t / (X - t_t)
+        let size = 1 << tree_var_num(column.table.len());
+        let mut p = vec![F::ChallengeField::zero(); size];
+        let mut q = vec![F::ChallengeField::one(); size];
+        for (t, entry) in column.table.iter().enumerate() {
+            p[t] = -F::ChallengeField::from(committed[offset + t]);
+            q[t] = x - lift::<F::ChallengeField>(entry);
+        }
+        let point = prove_fractional_sum(p, q, transcript);
+        let eq = eq_evals(&point, column.table.len());
+        claims.push(InnerProductClaim {
+            value: eq
+                .iter()
+                .enumerate()
+                .map(|(t, e)| *e * F::ChallengeField::from(committed[offset + t]))
+                .sum(),
+            weights: eq
+                .into_iter()
+                .enumerate()
+                .map(|(t, e)| (offset + t, e))
+                .collect(),
+        });
+    }
+    claims
+}
+
+/// Verify the lookups of `circuit`. Returns the claims on the committed polynomial to merge,
+/// to be checked by the opening.
+pub fn lookup_verify<F: Field + FieldSerde>(
+    circuit: &Circuit<F>,
+    layout: &LookupLayout,
+    transcript: &mut Transcript,
+    proof: &mut Proof,
+) -> Result<Vec<InnerProductClaim<F::ChallengeField>>, VerificationError> {
+    let one = F::ChallengeField::one();
+    let mut claims = vec![];
+    let columns = circuit.lookups.iter().zip(&layout.multiplicity_offsets);
+    for (k, (column, &offset)) in columns.enumerate() {
+        let failed = VerificationError::Lookup { column: k };
+        transcript.set_phase(TranscriptPhase::Lookup(k));
+        let x = transcript.challenge_fext::<F::ChallengeField>();
+
+        let witness = verify_fractional_sum::<F::ChallengeField>(
+            tree_var_num(column.input_ids.len()),
+            k,
+            transcript,
+            proof,
+        )?;
+        // the leaves are 1 / (X - w_i), then 0 / 1
+        let eq = eq_evals(&witness.point, column.input_ids.len());
+        let in_column: F::ChallengeField = eq.iter().sum();
+        if witness.leaf_p != in_column {
+            return Err(failed);
+        }
+        claims.push(InnerProductClaim {
+            value: x * in_column + one - in_column - witness.leaf_q,
+            weights: column.input_ids.iter().copied().zip(eq).collect(),
+        });
+
+        let table = verify_fractional_sum::<F::ChallengeField>(
+            tree_var_num(column.table.len()),
+            k,
+            transcript,
+            proof,
+        )?;
+        // the leaves are -m_t / (X - t_t), then 0 / 1
+        let eq = eq_evals(&table.point, column.table.len());
+        let in_table: F::ChallengeField = eq.iter().sum();
+        let table_eval: F::ChallengeField = eq
+            .iter()
+            .zip(&column.table)
+            .map(|(e, t)| e.mul_base_elem(t))
+            .sum();
+        if table.leaf_q != x * in_table + one - in_table - table_eval {
+            return Err(failed);
+        }
+        claims.push(InnerProductClaim {
+            value: -table.leaf_p,
+            weights: eq
+                .into_iter()
+                .enumerate()
+                .map(|(t, e)| (offset + t, e))
+                .collect(),
+        });
+
+        // both sides sum to the same fraction
+        if witness.root_q.is_zero()
+            || table.root_q.is_zero()
+            || witness.root_p * table.root_q + table.root_p * witness.root_q
+                != F::ChallengeField::zero()
+        {
+            return Err(failed);
+        }
+    }
+    Ok(claims)
+}
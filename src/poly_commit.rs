@@ -1,38 +1,52 @@
 pub mod deepfold;
+pub mod mimc_deepfold;
 pub mod raw;
 pub mod shuffle;
 use std::fmt::Debug;
 
 use arith::{Field, FieldSerde, MultiLinearPoly};
 
-use crate::{Proof, Transcript};
+use crate::{Proof, Transcript, VerificationError};
 
 pub trait CommitmentSerde {
     fn size(&self) -> usize;
     fn serialize_into(&self, buffer: &mut [u8]);
-    fn deserialize_from(buffer: &[u8], poly_size: usize) -> Self;
+    /// `None` if `buffer` is too short or does not encode a commitment.
+    fn deserialize_from(buffer: &[u8], poly_size: usize) -> Option<Self>
+    where
+        Self: Sized;
 }
 
 pub trait PolyCommitProver<F: Field + FieldSerde> {
     type Param: Clone;
     type Commitment: Clone + Debug + Default + CommitmentSerde;
+    /// Whether the commitment and the openings reveal nothing of the polynomial but the opened
+    /// evaluation, as the zero-knowledge mode requires.
+    const HIDING: bool = false;
 
     fn new(pp: &Self::Param, poly: &MultiLinearPoly<F>) -> Self;
     fn commit(&self) -> Self::Commitment;
-    fn open(&self, pp: &Self::Param, point: &[F], transcript: &mut Transcript); // -> Self::Proof;
+    /// Open the committed polynomial at a point of the challenge field.
+    fn open(&self, pp: &Self::Param, point: &[F::ChallengeField], transcript: &mut Transcript); // -> Self::Proof;
 }
 
 pub trait PolyCommitVerifier<F: Field + FieldSerde> {
     type Param: Clone;
     type Commitment: Clone + Debug + Default + CommitmentSerde;
+    /// See `PolyCommitProver::HIDING`.
+    const HIDING: bool = false;
 
     fn new(pp: Self::Param, commit: Self::Commitment) -> Self;
+    /// Size in bytes of the serialized commitment to a polynomial with `var_num` variables.
+    fn commitment_size(pp: &Self::Param, var_num: usize) -> usize;
+    /// Upper bound on the number of bytes the opening adds to the proof.
+    fn opening_size(pp: &Self::Param, var_num: usize) -> usize;
     fn verify(
         &self,
         pp: &Self::Param,
-        point: &[F],
-        eval: F,
+        point: &[F::ChallengeField],
+        eval: F::ChallengeField,
         transcript: &mut Transcript,
         proof: &mut Proof,
-    ) -> bool;
+    ) -> Result<(), VerificationError>;
 }
@@ -4,10 +4,12 @@ use arith::{
     as_bytes_vec, mul_group::Radix2Group, Field, FieldSerde, MultiLinearPoly, TwoAdicField,
 };
 use ark_std::iterable::Iterable;
+use rand::RngCore;
 
 use crate::{
+    eq_at_index,
     merkle_tree::{MerkleTreeProver, MerkleTreeVerifier, HASH_SIZE},
-    Transcript,
+    Proof, Transcript, VerificationError,
 };
 
 use super::{CommitmentSerde, PolyCommitProver, PolyCommitVerifier};
@@ -24,10 +26,8 @@ impl CommitmentSerde for MerkleRoot {
         buffer.copy_from_slice(&self.0);
     }
 
-    fn deserialize_from(buffer: &[u8], _poly_size: usize) -> Self {
-        let mut root = [0; 32];
-        root.copy_from_slice(&buffer[..32]);
-        Self(root)
+    fn deserialize_from(buffer: &[u8], _poly_size: usize) -> Option<Self> {
+        Some(Self(buffer.get(..HASH_SIZE)?.try_into().unwrap()))
     }
 }
 
@@ -42,6 +42,8 @@ pub struct DeepFoldParam<F: TwoAdicField + FieldSerde> {
 pub struct QueryResult<F: TwoAdicField + FieldSerde> {
     pub proof_bytes: Vec<u8>,
     pub proof_values: HashMap<usize, F>,
+    /// salts of the queried leaves, in the order of the indices; empty for unsalted trees
+    pub salts: Vec<[u8; HASH_SIZE]>,
 }
 
 impl<F: TwoAdicField + FieldSerde> QueryResult<F> {
@@ -54,17 +56,20 @@ impl<F: TwoAdicField + FieldSerde> QueryResult<F> {
         let len = merkle_verifier.leave_number;
         let leaves: Vec<Vec<u8>> = leaf_indices
             .iter()
-            .map(|i| {
-                as_bytes_vec(
+            .enumerate()
+            .map(|(k, i)| {
+                let values = as_bytes_vec(
                     &(0..leaf_size)
                         .map(|j| self.proof_values.get(&(i + j * len)).unwrap().clone())
                         .collect::<Vec<_>>(),
-                )
+                );
+                match self.salts.get(k) {
+                    Some(salt) => [salt.as_slice(), &values].concat(),
+                    None => values,
+                }
             })
             .collect();
-        let res = merkle_verifier.verify(self.proof_bytes.clone(), leaf_indices, &leaves);
-        assert!(res);
-        res
+        merkle_verifier.verify(self.proof_bytes.clone(), leaf_indices, &leaves)
     }
 }
 
@@ -73,19 +78,42 @@ pub struct InterpolateValue<F: TwoAdicField> {
     pub value: Vec<F>,
     leaf_size: usize,
     merkle_tree: MerkleTreeProver,
+    /// random bytes hashed in front of each leaf, so that the root and the paths reveal
+    /// nothing of the values of the other leaves; empty for unsalted trees
+    salts: Vec<[u8; HASH_SIZE]>,
 }
 
 impl<F: TwoAdicField + FieldSerde> InterpolateValue<F> {
     pub fn new(value: Vec<F>, leaf_size: usize) -> Self {
+        Self::with_salts(value, leaf_size, vec![])
+    }
+
+    /// `new` with every leaf salted with random bytes, revealed with the leaf when queried.
+    pub fn new_salted(value: Vec<F>, leaf_size: usize, mut rng: impl RngCore) -> Self {
+        let salts = (0..value.len() / leaf_size)
+            .map(|_| {
+                let mut salt = [0u8; HASH_SIZE];
+                rng.fill_bytes(&mut salt);
+                salt
+            })
+            .collect();
+        Self::with_salts(value, leaf_size, salts)
+    }
+
+    fn with_salts(value: Vec<F>, leaf_size: usize, salts: Vec<[u8; HASH_SIZE]>) -> Self {
         let len = value.len() / leaf_size;
         let merkle_tree = MerkleTreeProver::new(
             (0..len)
                 .map(|i| {
-                    as_bytes_vec::<F>(
+                    let values = as_bytes_vec::<F>(
                         &(0..leaf_size)
                             .map(|j| value[len * j + i])
                             .collect::<Vec<_>>(),
-                    )
+                    );
+                    match salts.get(i) {
+                        Some(salt) => [salt.as_slice(), &values].concat(),
+                        None => values,
+                    }
                 })
                 .collect(),
         );
@@ -93,9 +121,14 @@ impl<F: TwoAdicField + FieldSerde> InterpolateValue<F> {
             value,
             leaf_size,
             merkle_tree,
+            salts,
         }
     }
 
+    pub fn is_salted(&self) -> bool {
+        !self.salts.is_empty()
+    }
+
     pub fn leave_num(&self) -> usize {
         self.merkle_tree.leave_num()
     }
@@ -118,6 +151,99 @@ impl<F: TwoAdicField + FieldSerde> InterpolateValue<F> {
         let proof_bytes = self.merkle_tree.open(&leaf_indices);
         (proof_bytes, proof_values)
     }
+
+    /// The salts of the leaves at `leaf_indices`, empty for an unsalted tree.
+    pub fn query_salts(&self, leaf_indices: &[usize]) -> Vec<[u8; HASH_SIZE]> {
+        if self.salts.is_empty() {
+            return vec![];
+        }
+        leaf_indices.iter().map(|&i| self.salts[i]).collect()
+    }
+}
+
+/// The number of variables of the parameters of the hiding DeepFold for polynomials with
+/// `var_num` variables, opened with `query_num` queries. The polynomial is padded with random
+/// blocks in the extra variables, with enough entries to mask what the opening reveals: the
+/// evaluations of the blocks, and in each round a folded evaluation and the queried values.
+pub fn hiding_variable_num(var_num: usize, query_num: usize) -> usize {
+    let mut padding_var_num = 1;
+    loop {
+        let variable_num = var_num + padding_var_num;
+        let blocks = (1 << padding_var_num) - 1;
+        if blocks << var_num > blocks + variable_num * (2 * query_num + 1) + 1 {
+            return variable_num;
+        }
+        padding_var_num += 1;
+    }
+}
+
+/// Open at `point` the polynomial with evaluations `evals`, whose codeword is committed in
+/// `interpolation`. The trees of the folded codewords are salted if `interpolation` is.
+fn open_codeword<F: TwoAdicField<ChallengeField = F> + FieldSerde>(
+    interpolation: &InterpolateValue<F>,
+    evals: &[F],
+    pp: &DeepFoldParam<F>,
+    point: &[F],
+    transcript: &mut Transcript,
+) {
+    let mut poly_evals = evals.to_vec();
+    let mut interpolations = vec![];
+    for i in 0..pp.variable_num {
+        let mut new_point = point[i..].to_vec();
+        new_point[0].add_assign_base_elem(&F::BaseField::one());
+        transcript.append_f(MultiLinearPoly::eval_multilinear_ext(
+            &poly_evals,
+            &new_point,
+        ));
+        let challenge = transcript.challenge_fext();
+        let new_len = poly_evals.len() / 2;
+        for j in 0..new_len {
+            poly_evals[j] =
+                poly_evals[j * 2] + (poly_evals[j * 2 + 1] - poly_evals[j * 2]) * challenge;
+        }
+        poly_evals.truncate(new_len);
+        let next_evaluation = DeepFoldProver::<F>::evaluate_next_domain(
+            if i == 0 {
+                interpolation
+            } else {
+                &interpolations[i - 1]
+            },
+            pp,
+            i,
+            challenge,
+        );
+        if i < pp.variable_num - 1 {
+            let new_interpolation = if interpolation.is_salted() {
+                InterpolateValue::new_salted(next_evaluation, 2, rand::thread_rng())
+            } else {
+                InterpolateValue::new(next_evaluation, 2)
+            };
+            transcript.append_u8_slice(&new_interpolation.commit(), HASH_SIZE);
+            interpolations.push(new_interpolation);
+        } else {
+            transcript.append_f(next_evaluation[0]);
+        }
+    }
+    let mut leaf_indices = transcript.challenge_usizes(pp.query_num);
+    for i in 0..pp.variable_num {
+        let len = pp.mult_subgroups[i].size();
+        leaf_indices = leaf_indices.iter_mut().map(|v| *v % (len >> 1)).collect();
+        leaf_indices.sort();
+        leaf_indices.dedup();
+        let queried = if i == 0 {
+            interpolation
+        } else {
+            &interpolations[i - 1]
+        };
+        let query = queried.query(&leaf_indices);
+        transcript.append_u8_slice(&query.0, query.0.len());
+        for i in query.1 {
+            transcript.append_f(i);
+        }
+        for salt in queried.query_salts(&leaf_indices) {
+            transcript.append_u8_slice(&salt, HASH_SIZE);
+        }
+    }
 }
 
 pub struct DeepFoldProver<F: TwoAdicField + FieldSerde> {
@@ -132,22 +258,34 @@ impl<F: TwoAdicField + FieldSerde> DeepFoldProver<F> {
         round: usize,
         challenge: F,
     ) -> Vec<F> {
-        let mut res = vec![];
-        let len = pp.mult_subgroups[round].size();
-        let get_folding_value = &last_interpolation.value;
-        let subgroup = &pp.mult_subgroups[round];
-        for i in 0..(len / 2) {
-            let x = get_folding_value[i];
-            let nx = get_folding_value[i + len / 2];
+        fold_codeword(
+            &last_interpolation.value,
+            &pp.mult_subgroups[round],
+            challenge,
+        )
+    }
+}
+
+/// The codeword over the squares of `subgroup` of the polynomial of `codeword`, evaluated over
+/// `subgroup`, folded with `challenge`.
+pub(crate) fn fold_codeword<F: TwoAdicField>(
+    codeword: &[F],
+    subgroup: &Radix2Group<F>,
+    challenge: F,
+) -> Vec<F> {
+    let half = subgroup.size() / 2;
+    (0..half)
+        .map(|i| {
+            let x = codeword[i];
+            let nx = codeword[i + half];
             let sum = x + nx;
             let new_v = sum + challenge * ((x - nx) * subgroup.element_inv_at(i) - sum);
-            res.push(new_v.mul_base_elem(&F::BaseField::INV_2));
-        }
-        res
-    }
+            new_v.mul_base_elem(&F::BaseField::INV_2)
+        })
+        .collect()
 }
 
-impl<F: TwoAdicField + FieldSerde> PolyCommitProver<F> for DeepFoldProver<F> {
+impl<F: TwoAdicField<ChallengeField = F> + FieldSerde> PolyCommitProver<F> for DeepFoldProver<F> {
     type Param = DeepFoldParam<F>;
     type Commitment = MerkleRoot;
 
@@ -163,56 +301,7 @@ impl<F: TwoAdicField + FieldSerde> PolyCommitProver<F> for DeepFoldProver<F> {
     }
 
     fn open(&self, pp: &DeepFoldParam<F>, point: &[F], transcript: &mut Transcript) {
-        let mut poly_evals = self.poly.evals.clone();
-        let mut interpolations = vec![];
-        for i in 0..pp.variable_num {
-            let mut new_point = point[i..].to_vec();
-            new_point[0].add_assign_base_elem(&F::BaseField::one());
-            transcript.append_f(MultiLinearPoly::eval_multilinear_ext(
-                &poly_evals,
-                &new_point,
-            ));
-            let challenge = transcript.challenge_fext();
-            let new_len = poly_evals.len() / 2;
-            for j in 0..new_len {
-                poly_evals[j] =
-                    poly_evals[j * 2] + (poly_evals[j * 2 + 1] - poly_evals[j * 2]) * challenge;
-            }
-            poly_evals.truncate(new_len);
-            let next_evaluation = Self::evaluate_next_domain(
-                if i == 0 {
-                    &self.interpolation
-                } else {
-                    &interpolations[i - 1]
-                },
-                pp,
-                i,
-                challenge,
-            );
-            if i < pp.variable_num - 1 {
-                let new_interpolation = InterpolateValue::new(next_evaluation, 2);
-                transcript.append_u8_slice(&new_interpolation.commit(), HASH_SIZE);
-                interpolations.push(new_interpolation);
-            } else {
-                transcript.append_f(next_evaluation[0]);
-            }
-        }
-        let mut leaf_indices = transcript.challenge_usizes(pp.query_num);
-        for i in 0..pp.variable_num {
-            let len = pp.mult_subgroups[i].size();
-            leaf_indices = leaf_indices.iter_mut().map(|v| *v % (len >> 1)).collect();
-            leaf_indices.sort();
-            leaf_indices.dedup();
-            let query = if i == 0 {
-                self.interpolation.query(&leaf_indices)
-            } else {
-                interpolations[i - 1].query(&leaf_indices)
-            };
-            transcript.append_u8_slice(&query.0, query.0.len());
-            for i in query.1 {
-                transcript.append_f(i);
-            }
-        }
+        open_codeword(&self.interpolation, &self.poly.evals, pp, point, transcript);
     }
 }
 
@@ -221,7 +310,9 @@ pub struct DeepFoldVerifier<F: TwoAdicField + FieldSerde> {
     _data: PhantomData<F>,
 }
 
-impl<F: TwoAdicField + FieldSerde> PolyCommitVerifier<F> for DeepFoldVerifier<F> {
+impl<F: TwoAdicField<ChallengeField = F> + FieldSerde> PolyCommitVerifier<F>
+    for DeepFoldVerifier<F>
+{
     type Param = DeepFoldParam<F>;
     type Commitment = MerkleRoot;
 
@@ -232,113 +323,296 @@ impl<F: TwoAdicField + FieldSerde> PolyCommitVerifier<F> for DeepFoldVerifier<F>
         }
     }
 
+    fn commitment_size(_pp: &DeepFoldParam<F>, _var_num: usize) -> usize {
+        HASH_SIZE
+    }
+
+    fn opening_size(pp: &DeepFoldParam<F>, _var_num: usize) -> usize {
+        codeword_opening_size(pp, false)
+    }
+
     fn verify(
         &self,
         pp: &DeepFoldParam<F>,
         point: &[F],
         eval: F,
         transcript: &mut Transcript,
-        proof: &mut crate::Proof,
-    ) -> bool {
-        let mut eval = eval;
-        let mut challenges = vec![];
-        let mut commits = vec![];
-        for i in 0..point.len() {
-            let next_eval = proof.get_next_and_step::<F>();
-            transcript.append_f(next_eval);
-            let challenge = transcript.challenge_fext::<F>();
-
-            eval += (challenge - point[i]) * (next_eval - eval);
-            challenges.push(challenge);
-            if i < pp.variable_num - 1 {
-                let merkle_root = proof.get_next_hash();
-                transcript.append_u8_slice(&merkle_root, HASH_SIZE);
-                commits.push(MerkleTreeVerifier::new(
-                    pp.mult_subgroups[i + 1].size() / 2,
-                    merkle_root,
-                ));
-            } else {
-                let final_value = proof.get_next_and_step::<F>();
-                transcript.append_f(final_value);
-                if final_value != eval {
-                    return false;
-                }
+        proof: &mut Proof,
+    ) -> Result<(), VerificationError> {
+        verify_codeword(&self.commit, pp, point, eval, transcript, proof, false)
+    }
+}
+
+/// Upper bound on the bytes of the opening of `open_codeword`, with salted leaves if `salted`.
+fn codeword_opening_size<F: TwoAdicField + FieldSerde>(
+    pp: &DeepFoldParam<F>,
+    salted: bool,
+) -> usize {
+    // one evaluation and one merkle root per round, the final value instead of the last root
+    let mut size = pp.variable_num * F::SIZE + (pp.variable_num - 1) * HASH_SIZE + F::SIZE;
+    // queries: merkle paths and two values per queried leaf; at most query_num leaves survive
+    // the dedup in each round, and a tree level needs a sibling only if exactly one child
+    // of a node is queried
+    for i in 0..pp.variable_num {
+        let leave_num = pp.mult_subgroups[i].size() / 2;
+        let query_num = pp.query_num.min(leave_num);
+        size += query_num * 2 * F::SIZE;
+        if salted {
+            size += query_num * HASH_SIZE;
+        }
+        for level in 0..leave_num.ilog2() {
+            let node_num = leave_num >> level;
+            let queried = query_num.min(node_num);
+            size += queried.min(node_num - queried) * HASH_SIZE;
+        }
+    }
+    size
+}
+
+/// Verify the opening of `open_codeword` at `point`, against the tree committed in `commit`,
+/// whose leaves are salted if `salted`.
+fn verify_codeword<F: TwoAdicField<ChallengeField = F> + FieldSerde>(
+    commit: &MerkleTreeVerifier,
+    pp: &DeepFoldParam<F>,
+    point: &[F],
+    eval: F,
+    transcript: &mut Transcript,
+    proof: &mut Proof,
+    salted: bool,
+) -> Result<(), VerificationError> {
+    let mut eval = eval;
+    let mut challenges = vec![];
+    let mut commits = vec![];
+    for i in 0..point.len() {
+        let next_eval = proof.get_next_and_step::<F>()?;
+        transcript.append_f(next_eval);
+        let challenge = transcript.challenge_fext::<F>();
+
+        eval += (challenge - point[i]) * (next_eval - eval);
+        challenges.push(challenge);
+        if i < pp.variable_num - 1 {
+            let merkle_root = proof.get_next_hash()?;
+            transcript.append_u8_slice(&merkle_root, HASH_SIZE);
+            commits.push(MerkleTreeVerifier::new(
+                pp.mult_subgroups[i + 1].size() / 2,
+                merkle_root,
+            ));
+        } else {
+            let final_value = proof.get_next_and_step::<F>()?;
+            transcript.append_f(final_value);
+            if final_value != eval {
+                return Err(VerificationError::PolyCommitOpening);
             }
         }
+    }
+
+    let mut leaf_indices = transcript.challenge_usizes(pp.query_num);
+    let mut indices = leaf_indices.clone();
+    let mut query_results = vec![];
+    for i in 0..pp.variable_num {
+        let len = pp.mult_subgroups[i].size();
+        leaf_indices = leaf_indices.iter_mut().map(|v| *v % (len >> 1)).collect();
+        leaf_indices.sort();
+        leaf_indices.dedup();
 
-        let mut leaf_indices = transcript.challenge_usizes(pp.query_num);
-        let mut indices = leaf_indices.clone();
-        let mut query_results = vec![];
-        for i in 0..pp.variable_num {
-            let len = pp.mult_subgroups[i].size();
-            leaf_indices = leaf_indices.iter_mut().map(|v| *v % (len >> 1)).collect();
-            leaf_indices.sort();
-            leaf_indices.dedup();
-
-            let proof_bytes = proof.get_next_slice(if i == 0 {
-                self.commit.proof_length(&leaf_indices)
+        let proof_bytes = proof.get_next_slice(if i == 0 {
+            commit.proof_length(&leaf_indices)
+        } else {
+            commits[i - 1].proof_length(&leaf_indices)
+        })?;
+        let proof_values = (0..leaf_indices.len() * 2)
+            .map(|_| proof.get_next_and_step::<F>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let salts = if salted {
+            (0..leaf_indices.len())
+                .map(|_| proof.get_next_hash())
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            vec![]
+        };
+        transcript.append_u8_slice(&proof_bytes, proof_bytes.len());
+        for i in &proof_values {
+            transcript.append_f(*i);
+        }
+        for salt in &salts {
+            transcript.append_u8_slice(salt, HASH_SIZE);
+        }
+        let query = QueryResult {
+            proof_bytes,
+            proof_values: leaf_indices
+                .iter()
+                .map(|&x| x)
+                .chain(leaf_indices.iter().map(|x| x + len / 2))
+                .zip(proof_values.into_iter())
+                .collect(),
+            salts,
+        };
+        query_results.push(query);
+    }
+    drop(leaf_indices);
+    for i in 0..pp.variable_num {
+        let len = pp.mult_subgroups[i].size();
+        indices = indices.iter_mut().map(|v| *v % (len >> 1)).collect();
+        indices.sort();
+        indices.dedup();
+
+        if !query_results[i].verify_merkle_tree(
+            &indices,
+            2,
+            if i == 0 { commit } else { &commits[i - 1] },
+        ) {
+            return Err(VerificationError::MerklePath { round: i });
+        }
+        for j in indices.iter() {
+            let x = query_results[i].proof_values.get(&j).unwrap().clone();
+            let nx = query_results[i]
+                .proof_values
+                .get(&(j + len / 2))
+                .unwrap()
+                .clone();
+            let sum = x + nx;
+            let new_v =
+                sum + challenges[i] * ((x - nx) * pp.mult_subgroups[i].element_inv_at(*j) - sum);
+            if i < pp.variable_num - 1 {
+                if new_v != query_results[i + 1].proof_values[j].double() {
+                    return Err(VerificationError::PolyCommitOpening);
+                }
             } else {
-                commits[i - 1].proof_length(&leaf_indices)
-            });
-            let proof_values = (0..leaf_indices.len() * 2)
-                .map(|_| proof.get_next_and_step::<F>())
-                .collect::<Vec<_>>();
-            transcript.append_u8_slice(&proof_bytes, proof_bytes.len());
-            for i in &proof_values {
-                transcript.append_f(*i);
+                if new_v.mul_base_elem(&F::BaseField::INV_2) != eval {
+                    return Err(VerificationError::PolyCommitOpening);
+                }
             }
-            let query = QueryResult {
-                proof_bytes,
-                proof_values: leaf_indices
-                    .iter()
-                    .map(|&x| x)
-                    .chain(leaf_indices.iter().map(|x| x + len / 2))
-                    .zip(proof_values.into_iter())
-                    .collect(),
-            };
-            query_results.push(query);
         }
-        drop(leaf_indices);
-        for i in 0..pp.variable_num {
-            let len = pp.mult_subgroups[i].size();
-            indices = indices.iter_mut().map(|v| *v % (len >> 1)).collect();
-            indices.sort();
-            indices.dedup();
-
-            if !query_results[i].verify_merkle_tree(
-                &indices,
+    }
+    Ok(())
+}
+
+/// DeepFold made hiding, for the zero-knowledge mode: the leaves of all the trees are salted,
+/// and the polynomial is padded to `pp.variable_num` variables with random blocks, see
+/// `hiding_variable_num`. To open at `z`, the prover sends the evaluations of the blocks at
+/// `z`, then the padded polynomial is opened at `z` followed by random variables `u`, where it
+/// evaluates to `eq(u, 0)` times the claimed evaluation plus the blocks weighted by `eq(u, b)`.
+pub struct HidingDeepFoldProver<F: TwoAdicField + FieldSerde> {
+    interpolation: InterpolateValue<F>,
+    /// the padded polynomial
+    poly: MultiLinearPoly<F>,
+}
+
+impl<F: TwoAdicField<ChallengeField = F> + FieldSerde> PolyCommitProver<F>
+    for HidingDeepFoldProver<F>
+{
+    type Param = DeepFoldParam<F>;
+    type Commitment = MerkleRoot;
+    const HIDING: bool = true;
+
+    fn new(pp: &Self::Param, poly: &MultiLinearPoly<F>) -> Self {
+        assert!(
+            pp.variable_num >= hiding_variable_num(poly.var_num, pp.query_num),
+            "too few variables to pad the polynomial"
+        );
+        let mut rng = rand::thread_rng();
+        let mut evals = poly.evals.clone();
+        evals.extend((evals.len()..1 << pp.variable_num).map(|_| F::random_unsafe(&mut rng)));
+        HidingDeepFoldProver {
+            interpolation: InterpolateValue::new_salted(
+                pp.mult_subgroups[0].fft(evals.clone()),
                 2,
-                if i == 0 {
-                    &self.commit
-                } else {
-                    &commits[i - 1]
-                },
-            ) {
-                println!("{} {} {}", file!(), line!(), i);
-                return false;
-            }
-            for j in indices.iter() {
-                let x = query_results[i].proof_values.get(&j).unwrap().clone();
-                let nx = query_results[i]
-                    .proof_values
-                    .get(&(j + len / 2))
-                    .unwrap()
-                    .clone();
-                let sum = x + nx;
-                let new_v = sum
-                    + challenges[i] * ((x - nx) * pp.mult_subgroups[i].element_inv_at(*j) - sum);
-                if i < pp.variable_num - 1 {
-                    if new_v != query_results[i + 1].proof_values[j].double() {
-                        println!("{} {}", file!(), line!());
-                        return false;
-                    }
-                } else {
-                    if new_v.mul_base_elem(&F::BaseField::INV_2) != eval {
-                        return false;
-                    }
-                }
-            }
+                &mut rng,
+            ),
+            poly: MultiLinearPoly {
+                var_num: pp.variable_num,
+                evals,
+            },
+        }
+    }
+
+    fn commit(&self) -> Self::Commitment {
+        MerkleRoot(self.interpolation.commit())
+    }
+
+    fn open(&self, pp: &DeepFoldParam<F>, point: &[F], transcript: &mut Transcript) {
+        let block_size = 1 << point.len();
+        for block in self.poly.evals.chunks(block_size).skip(1) {
+            transcript.append_f(MultiLinearPoly::eval_multilinear_ext(block, point));
         }
-        true
+        let mut padded_point = point.to_vec();
+        padded_point
+            .extend((point.len()..pp.variable_num).map(|_| transcript.challenge_fext::<F>()));
+        open_codeword(
+            &self.interpolation,
+            &self.poly.evals,
+            pp,
+            &padded_point,
+            transcript,
+        );
+    }
+}
+
+pub struct HidingDeepFoldVerifier<F: TwoAdicField + FieldSerde> {
+    commit: MerkleTreeVerifier,
+    _data: PhantomData<F>,
+}
+
+impl<F: TwoAdicField<ChallengeField = F> + FieldSerde> PolyCommitVerifier<F>
+    for HidingDeepFoldVerifier<F>
+{
+    type Param = DeepFoldParam<F>;
+    type Commitment = MerkleRoot;
+    const HIDING: bool = true;
+
+    fn new(pp: Self::Param, commit: Self::Commitment) -> Self {
+        HidingDeepFoldVerifier {
+            commit: MerkleTreeVerifier::new(pp.mult_subgroups[0].size() / 2, commit.0),
+            _data: PhantomData,
+        }
+    }
+
+    fn commitment_size(_pp: &DeepFoldParam<F>, _var_num: usize) -> usize {
+        HASH_SIZE
+    }
+
+    fn opening_size(pp: &DeepFoldParam<F>, var_num: usize) -> usize {
+        // the evaluations of the padding blocks
+        ((1 << (pp.variable_num - var_num)) - 1) * F::SIZE + codeword_opening_size(pp, true)
+    }
+
+    fn verify(
+        &self,
+        pp: &DeepFoldParam<F>,
+        point: &[F],
+        eval: F,
+        transcript: &mut Transcript,
+        proof: &mut Proof,
+    ) -> Result<(), VerificationError> {
+        if pp.variable_num < hiding_variable_num(point.len(), pp.query_num) {
+            return Err(VerificationError::InvalidStatement(
+                "too few variables to pad the polynomial",
+            ));
+        }
+        let padding_var_num = pp.variable_num - point.len();
+        let mut block_evals = vec![eval];
+        for _ in 1..1 << padding_var_num {
+            block_evals.push(proof.get_next_and_step::<F>()?);
+            transcript.append_f(*block_evals.last().unwrap());
+        }
+        let u: Vec<F> = (0..padding_var_num)
+            .map(|_| transcript.challenge_fext::<F>())
+            .collect();
+        let padded_eval = block_evals
+            .iter()
+            .enumerate()
+            .map(|(b, v)| eq_at_index(b, &u) * v)
+            .sum();
+        let mut padded_point = point.to_vec();
+        padded_point.extend(u);
+        verify_codeword(
+            &self.commit,
+            pp,
+            &padded_point,
+            padded_eval,
+            transcript,
+            proof,
+            true,
+        )
     }
 }
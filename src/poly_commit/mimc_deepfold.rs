@@ -0,0 +1,217 @@
+//! DeepFold over BN254 with its codewords committed in MiMC7 Merkle trees, for the proofs
+//! checked by a verifier circuit, see `crate::recursion`.
+//!
+//! The commit and fold phases are those of `crate::deepfold`, the roots being sent as field
+//! elements. The query phase has a layout that does not depend on the queried indices, so that
+//! a circuit can read it: each of the `query_num` indices is opened on its own in every round,
+//! as the two values of its leaf followed by the siblings of its Merkle path, without merging
+//! the paths of the indices nor dropping the repeated ones.
+
+use arith::{Field, FieldSerde, MultiLinearPoly};
+use halo2curves::bn256::Fr;
+
+use crate::{
+    deepfold::{fold_codeword, DeepFoldParam},
+    merkle_tree::MiMC7MerkleTree,
+    mimc7_hash, Proof, Transcript, VerificationError,
+};
+
+use super::{CommitmentSerde, PolyCommitProver, PolyCommitVerifier};
+
+#[derive(Debug, Clone, Default)]
+pub struct MiMC7MerkleRoot(pub Fr);
+
+impl CommitmentSerde for MiMC7MerkleRoot {
+    fn size(&self) -> usize {
+        Fr::SIZE
+    }
+
+    fn serialize_into(&self, buffer: &mut [u8]) {
+        self.0.serialize_into(buffer);
+    }
+
+    fn deserialize_from(buffer: &[u8], _poly_size: usize) -> Option<Self> {
+        Fr::try_deserialize_from(buffer.get(..Fr::SIZE)?).map(Self)
+    }
+}
+
+/// The tree of `codeword`, whose leaf `i` holds its values at `i` and `i + len / 2`.
+fn codeword_tree(codeword: &[Fr]) -> MiMC7MerkleTree {
+    let half = codeword.len() / 2;
+    MiMC7MerkleTree::new(
+        (0..half)
+            .map(|i| mimc7_hash(&[codeword[i], codeword[i + half]]))
+            .collect(),
+    )
+}
+
+/// `InvalidStatement` unless the subgroups of `pp` fold a polynomial with `var_num` variables
+/// down to a constant, each of them the squares of the previous one.
+pub(crate) fn check_param(pp: &DeepFoldParam<Fr>, var_num: usize) -> Result<(), VerificationError> {
+    let invalid = |reason| Err(VerificationError::InvalidStatement(reason));
+    if pp.variable_num != var_num || pp.mult_subgroups.len() < var_num {
+        return invalid("the DeepFold parameters are for another number of variables");
+    }
+    let size = pp.mult_subgroups[0].size();
+    if size < 2 << var_num {
+        return invalid("the DeepFold codeword is too short to fold");
+    }
+    if (0..var_num).any(|i| pp.mult_subgroups[i].size() != size >> i) {
+        return invalid("each DeepFold subgroup must be the squares of the previous one");
+    }
+    Ok(())
+}
+
+pub struct MiMC7DeepFoldProver {
+    codeword: Vec<Fr>,
+    tree: MiMC7MerkleTree,
+    poly: MultiLinearPoly<Fr>,
+}
+
+impl PolyCommitProver<Fr> for MiMC7DeepFoldProver {
+    type Param = DeepFoldParam<Fr>;
+    type Commitment = MiMC7MerkleRoot;
+
+    fn new(pp: &Self::Param, poly: &MultiLinearPoly<Fr>) -> Self {
+        let codeword = pp.mult_subgroups[0].fft(poly.evals.clone());
+        MiMC7DeepFoldProver {
+            tree: codeword_tree(&codeword),
+            codeword,
+            poly: poly.clone(),
+        }
+    }
+
+    fn commit(&self) -> Self::Commitment {
+        MiMC7MerkleRoot(self.tree.root())
+    }
+
+    fn open(&self, pp: &DeepFoldParam<Fr>, point: &[Fr], transcript: &mut Transcript) {
+        let mut poly_evals = self.poly.evals.clone();
+        let mut codewords = vec![self.codeword.clone()];
+        let mut trees = vec![self.tree.clone()];
+        for i in 0..pp.variable_num {
+            let mut new_point = point[i..].to_vec();
+            new_point[0] += Fr::one();
+            transcript.append_f(MultiLinearPoly::eval_multilinear_ext(
+                &poly_evals,
+                &new_point,
+            ));
+            let challenge = transcript.challenge_fext::<Fr>();
+            let new_len = poly_evals.len() / 2;
+            for j in 0..new_len {
+                poly_evals[j] =
+                    poly_evals[j * 2] + (poly_evals[j * 2 + 1] - poly_evals[j * 2]) * challenge;
+            }
+            poly_evals.truncate(new_len);
+            let next = fold_codeword(&codewords[i], &pp.mult_subgroups[i], challenge);
+            if i < pp.variable_num - 1 {
+                let tree = codeword_tree(&next);
+                transcript.append_f(tree.root());
+                codewords.push(next);
+                trees.push(tree);
+            } else {
+                transcript.append_f(next[0]);
+            }
+        }
+        let indices = transcript.challenge_usizes(pp.query_num);
+        for (codeword, tree) in codewords.iter().zip(&trees) {
+            let half = codeword.len() / 2;
+            for &index in &indices {
+                let j = index % half;
+                transcript.append_f(codeword[j]);
+                transcript.append_f(codeword[j + half]);
+                for sibling in tree.path(j) {
+                    transcript.append_f(sibling);
+                }
+            }
+        }
+    }
+}
+
+pub struct MiMC7DeepFoldVerifier {
+    root: Fr,
+}
+
+impl PolyCommitVerifier<Fr> for MiMC7DeepFoldVerifier {
+    type Param = DeepFoldParam<Fr>;
+    type Commitment = MiMC7MerkleRoot;
+
+    fn new(_pp: Self::Param, commit: Self::Commitment) -> Self {
+        MiMC7DeepFoldVerifier { root: commit.0 }
+    }
+
+    fn commitment_size(_pp: &DeepFoldParam<Fr>, _var_num: usize) -> usize {
+        Fr::SIZE
+    }
+
+    fn opening_size(pp: &DeepFoldParam<Fr>, _var_num: usize) -> usize {
+        // one evaluation and one root per round, the final value instead of the last root,
+        // then the two values and the path of each query in each round
+        let queries: usize = (0..pp.variable_num)
+            .map(|i| pp.query_num * (2 + (pp.mult_subgroups[i].size() / 2).ilog2() as usize))
+            .sum();
+        (2 * pp.variable_num + queries) * Fr::SIZE
+    }
+
+    fn verify(
+        &self,
+        pp: &DeepFoldParam<Fr>,
+        point: &[Fr],
+        eval: Fr,
+        transcript: &mut Transcript,
+        proof: &mut Proof,
+    ) -> Result<(), VerificationError> {
+        check_param(pp, point.len())?;
+        let mut read = |transcript: &mut Transcript| {
+            let v = proof.get_next_and_step::<Fr>()?;
+            transcript.append_f(v);
+            Ok::<_, VerificationError>(v)
+        };
+        let mut eval = eval;
+        let mut challenges = vec![];
+        let mut roots = vec![self.root];
+        for i in 0..point.len() {
+            let next_eval = read(transcript)?;
+            let challenge = transcript.challenge_fext::<Fr>();
+            eval += (challenge - point[i]) * (next_eval - eval);
+            challenges.push(challenge);
+            if i < point.len() - 1 {
+                roots.push(read(transcript)?);
+            } else if read(transcript)? != eval {
+                return Err(VerificationError::PolyCommitOpening);
+            }
+        }
+
+        let indices = transcript.challenge_usizes(pp.query_num);
+        // twice the value of each query in the codeword of the round, folded from the last one
+        let mut folded = vec![Fr::zero(); indices.len()];
+        for (i, subgroup) in pp.mult_subgroups[..point.len()].iter().enumerate() {
+            let half = subgroup.size() / 2;
+            for (k, &index) in indices.iter().enumerate() {
+                let j = index % half;
+                let x = read(transcript)?;
+                let nx = read(transcript)?;
+                let path = (0..half.ilog2())
+                    .map(|_| read(transcript))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let leaf = mimc7_hash(&[x, nx]);
+                if MiMC7MerkleTree::root_from_path(leaf, j, &path) != roots[i] {
+                    return Err(VerificationError::MerklePath { round: i });
+                }
+                let value = match index % (2 * half) < half {
+                    true => x,
+                    false => nx,
+                };
+                if i > 0 && folded[k] != value.double() {
+                    return Err(VerificationError::PolyCommitOpening);
+                }
+                let sum = x + nx;
+                folded[k] = sum + challenges[i] * ((x - nx) * subgroup.element_inv_at(j) - sum);
+            }
+        }
+        if folded.iter().any(|&v| v * Fr::INV_2 != eval) {
+            return Err(VerificationError::PolyCommitOpening);
+        }
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@
 
 use arith::{Field, FieldSerde, MultiLinearPoly};
 
-use crate::{Proof, Transcript};
+use crate::{Proof, Transcript, VerificationError};
 
 use super::{CommitmentSerde, PolyCommitProver, PolyCommitVerifier};
 
@@ -24,12 +24,13 @@ impl<F: Field + FieldSerde> CommitmentSerde for RawCommitment<F> {
             .enumerate()
             .for_each(|(i, v)| v.serialize_into(&mut buffer[i * F::SIZE..(i + 1) * F::SIZE]));
     }
-    fn deserialize_from(buffer: &[u8], poly_size: usize) -> Self {
-        let mut poly_vals = Vec::new();
-        for i in 0..poly_size {
-            poly_vals.push(F::deserialize_from(&buffer[i * F::SIZE..(i + 1) * F::SIZE]));
-        }
-        RawCommitment { poly_vals }
+    fn deserialize_from(buffer: &[u8], poly_size: usize) -> Option<Self> {
+        let poly_vals = buffer
+            .get(..poly_size * F::SIZE)?
+            .chunks_exact(F::SIZE)
+            .map(F::try_deserialize_from)
+            .collect::<Option<_>>()?;
+        Some(RawCommitment { poly_vals })
     }
 }
 
@@ -51,7 +52,7 @@ impl<F: Field + FieldSerde> PolyCommitProver<F> for RawCommitmentProver<F> {
         }
     }
 
-    fn open(&self, _pp: &(), _point: &[F], _transcript: &mut Transcript) {}
+    fn open(&self, _pp: &(), _point: &[F::ChallengeField], _transcript: &mut Transcript) {}
 }
 
 pub struct RawCommitmentVerifier<F: Field> {
@@ -66,14 +67,25 @@ impl<F: Field + FieldSerde> PolyCommitVerifier<F> for RawCommitmentVerifier<F> {
         RawCommitmentVerifier { commit }
     }
 
+    fn commitment_size(_pp: &(), var_num: usize) -> usize {
+        (1 << var_num) * F::SIZE
+    }
+
+    fn opening_size(_pp: &(), _var_num: usize) -> usize {
+        0
+    }
+
     fn verify(
         &self,
         _pp: &(),
-        point: &[F],
-        eval: F,
+        point: &[F::ChallengeField],
+        eval: F::ChallengeField,
         _transcript: &mut Transcript,
         _proof: &mut Proof,
-    ) -> bool {
-        eval == MultiLinearPoly::eval_multilinear_ext(&self.commit.poly_vals, point)
+    ) -> Result<(), VerificationError> {
+        if eval != MultiLinearPoly::eval_multilinear_challenge(&self.commit.poly_vals, point) {
+            return Err(VerificationError::PolyCommitOpening);
+        }
+        Ok(())
     }
 }
@@ -1,6 +1,6 @@
 use arith::{Field, FieldSerde, MultiLinearPoly};
 
-use crate::{Proof, Transcript};
+use crate::{Proof, Transcript, VerificationError};
 
 use super::{raw::RawCommitment, PolyCommitProver, PolyCommitVerifier};
 
@@ -22,8 +22,9 @@ impl<F: Field + FieldSerde> PolyCommitProver<F> for ShufflePcProver<F> {
         }
     }
 
-    fn open(&self, _pp: &(), point: &[F], transcript: &mut Transcript) {
-        let mut poly_evals = self.poly.evals.clone();
+    fn open(&self, _pp: &(), point: &[F::ChallengeField], transcript: &mut Transcript) {
+        let mut poly_evals: Vec<F::ChallengeField> =
+            self.poly.evals.iter().map(|&e| e.into()).collect();
         for i in 0..point.len() {
             let mut new_point = point[i..].to_vec();
             new_point[0].add_assign_base_elem(&F::BaseField::one());
@@ -31,7 +32,7 @@ impl<F: Field + FieldSerde> PolyCommitProver<F> for ShufflePcProver<F> {
                 &poly_evals,
                 &new_point,
             ));
-            let r = transcript.challenge_fext::<F>();
+            let r = transcript.challenge_fext::<F::ChallengeField>();
             let new_len = poly_evals.len() / 2;
             for j in 0..new_len {
                 poly_evals[j] = poly_evals[j * 2] + (poly_evals[j * 2 + 1] - poly_evals[j * 2]) * r;
@@ -53,24 +54,35 @@ impl<F: Field + FieldSerde> PolyCommitVerifier<F> for ShufflePcVerifier<F> {
         ShufflePcVerifier { commit }
     }
 
+    fn commitment_size(_pp: &(), var_num: usize) -> usize {
+        (1 << var_num) * F::SIZE
+    }
+
+    fn opening_size(_pp: &(), var_num: usize) -> usize {
+        var_num * F::ChallengeField::SIZE
+    }
+
     fn verify(
         &self,
         _pp: &(),
-        point: &[F],
-        eval: F,
+        point: &[F::ChallengeField],
+        eval: F::ChallengeField,
         transcript: &mut Transcript,
         proof: &mut Proof,
-    ) -> bool {
+    ) -> Result<(), VerificationError> {
         let mut eval = eval;
         let mut new_point = vec![];
         for i in 0..point.len() {
-            let next_eval = proof.get_next_and_step::<F>();
+            let next_eval = proof.get_next_and_step::<F::ChallengeField>()?;
             transcript.append_f(next_eval);
-            let r = transcript.challenge_fext::<F>();
+            let r = transcript.challenge_fext::<F::ChallengeField>();
 
             eval += (r - point[i]) * (next_eval - eval);
             new_point.push(r);
         }
-        eval == MultiLinearPoly::eval_multilinear_ext(&self.commit.poly_vals, &new_point)
+        if eval != MultiLinearPoly::eval_multilinear_challenge(&self.commit.poly_vals, &new_point) {
+            return Err(VerificationError::PolyCommitOpening);
+        }
+        Ok(())
     }
 }
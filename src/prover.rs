@@ -13,6 +13,9 @@ pub(crate) use sumcheck_helper::*;
 pub mod sumcheck;
 pub use sumcheck::*;
 
+pub mod stats;
+pub use stats::*;
+
 pub mod gkr;
 pub use gkr::*;
 
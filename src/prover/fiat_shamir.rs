@@ -1,14 +1,295 @@
-use std::mem::size_of;
+use std::{fmt, mem::size_of, ops::Range};
 
 use arith::{Field, FieldSerde};
+use halo2curves::bn256::Fr;
 
-use crate::{Proof, SHA256hasher};
+use crate::{
+    FiatShamirHashType, Keccak256hasher, MiMC7hasher, PoseidonHasher, Proof, SHA256hasher,
+};
+
+/// The messages absorbed by a transcript since its last hash: their bytes, or for the
+/// field-native `FiatShamirHasher::Poseidon` the field elements encoding them.
+#[derive(Debug, Clone)]
+pub enum HashInput {
+    Bytes(Vec<u8>),
+    Elements(Vec<Fr>),
+}
+
+impl HashInput {
+    fn is_empty(&self) -> bool {
+        match self {
+            HashInput::Bytes(bytes) => bytes.is_empty(),
+            HashInput::Elements(elements) => elements.is_empty(),
+        }
+    }
+}
+
+/// The hash of the transcript, chosen by `Config::fs_hash`.
+#[derive(Debug, Clone)]
+pub enum FiatShamirHasher {
+    SHA256(SHA256hasher),
+    Keccak256(Keccak256hasher),
+    MIMC7(MiMC7hasher),
+    /// field-native, for BN254 proofs only
+    Poseidon(PoseidonHasher),
+}
+
+impl FiatShamirHasher {
+    /// Panics for the hashes not implemented yet.
+    pub fn new(fs_hash: &FiatShamirHashType) -> Self {
+        match fs_hash {
+            FiatShamirHashType::SHA256 => FiatShamirHasher::SHA256(SHA256hasher),
+            FiatShamirHashType::Keccak256 => FiatShamirHasher::Keccak256(Keccak256hasher),
+            FiatShamirHashType::MIMC7 => FiatShamirHasher::MIMC7(MiMC7hasher),
+            FiatShamirHashType::Poseidon => FiatShamirHasher::Poseidon(PoseidonHasher),
+            _ => panic!("unsupported Fiat-Shamir hash {:?}", fs_hash),
+        }
+    }
+
+    pub fn hash(&self, output: &mut [u8], input: &[u8], input_len: usize) {
+        match self {
+            FiatShamirHasher::SHA256(hasher) => hasher.hash(output, input, input_len),
+            FiatShamirHasher::Keccak256(hasher) => hasher.hash(output, input, input_len),
+            FiatShamirHasher::MIMC7(hasher) => hasher.hash(output, input, input_len),
+            FiatShamirHasher::Poseidon(hasher) => hasher.hash(output, input, input_len),
+        }
+    }
+
+    pub fn hash_inplace(&self, buffer: &mut [u8], input_len: usize) {
+        match self {
+            FiatShamirHasher::SHA256(hasher) => hasher.hash_inplace(buffer, input_len),
+            FiatShamirHasher::Keccak256(hasher) => hasher.hash_inplace(buffer, input_len),
+            FiatShamirHasher::MIMC7(hasher) => hasher.hash_inplace(buffer, input_len),
+            FiatShamirHasher::Poseidon(hasher) => hasher.hash_inplace(buffer, input_len),
+        }
+    }
+
+    /// An empty input for `absorb_f`, `absorb_u8_slice` and `squeeze`.
+    pub fn new_input(&self) -> HashInput {
+        match self {
+            FiatShamirHasher::Poseidon(_) => HashInput::Elements(vec![]),
+            _ => HashInput::Bytes(vec![]),
+        }
+    }
+
+    /// Absorb the field element `f` into `input`. Poseidon absorbs a BN254 element as itself.
+    pub fn absorb_f<F: Field + FieldSerde>(&self, input: &mut HashInput, f: &F) {
+        let mut bytes = vec![0u8; F::SIZE];
+        f.serialize_into(&mut bytes);
+        match input {
+            HashInput::Bytes(absorbed) => absorbed.extend_from_slice(&bytes),
+            HashInput::Elements(absorbed) => {
+                let element = (F::SIZE == Fr::SIZE)
+                    .then(|| Fr::try_deserialize_from(&bytes))
+                    .flatten();
+                match element {
+                    Some(element) => absorbed.push(element),
+                    None => absorbed.extend(PoseidonHasher::bytes_to_elements(&bytes)),
+                }
+            }
+        }
+    }
+
+    /// Absorb `bytes`, a message that is not a field element, into `input`.
+    pub fn absorb_u8_slice(&self, input: &mut HashInput, bytes: &[u8]) {
+        match input {
+            HashInput::Bytes(absorbed) => absorbed.extend_from_slice(bytes),
+            HashInput::Elements(absorbed) => {
+                absorbed.extend(PoseidonHasher::bytes_to_elements(bytes))
+            }
+        }
+    }
+
+    /// Hash the messages of `input` into `digest` and empty `input`; with no message, hash
+    /// `digest` itself.
+    pub fn squeeze(&self, input: &mut HashInput, digest: &mut [u8]) {
+        if input.is_empty() {
+            let len = digest.len();
+            self.hash_inplace(digest, len);
+            return;
+        }
+        match (self, input) {
+            (FiatShamirHasher::Poseidon(hasher), HashInput::Elements(absorbed)) => {
+                hasher.hash_elements(digest, absorbed);
+                absorbed.clear();
+            }
+            (_, HashInput::Bytes(absorbed)) => {
+                self.hash(digest, absorbed, absorbed.len());
+                absorbed.clear();
+            }
+            _ => unreachable!("the input of another hasher"),
+        }
+    }
+}
+
+/// The part of the proof a message of the transcript belongs to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TranscriptPhase {
+    #[default]
+    Commitment,
+    ProofOfWork,
+    /// the sumcheck of a GKR layer, from the output layer down
+    Layer(usize),
+    /// the lookup argument of a lookup column
+    Lookup(usize),
+    /// the sumcheck merging the claims on the committed polynomial
+    Merge,
+    Opening,
+}
+
+/// Where a message of the transcript is sent: its phase, and its sumcheck round in the phase.
+/// The rounds of a layer are numbered as in `VerificationError::LayerSumcheck`, the claims
+/// after the last round being sent in the round `2 * input_var_num`. In zero-knowledge mode,
+/// the sumcheck of a layer has the extra round `2 * input_var_num` over `w`, and the claims of
+/// its masks are sent in the round after it, see `crate::zk`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TranscriptLabel {
+    pub phase: TranscriptPhase,
+    pub round: usize,
+}
+
+impl fmt::Display for TranscriptLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.phase {
+            TranscriptPhase::Commitment => write!(f, "commitment"),
+            TranscriptPhase::ProofOfWork => write!(f, "proof of work"),
+            TranscriptPhase::Layer(layer) => write!(f, "layer {}, round {}", layer, self.round),
+            TranscriptPhase::Lookup(column) => {
+                write!(f, "lookup of column {}, round {}", column, self.round)
+            }
+            TranscriptPhase::Merge => write!(f, "merge, round {}", self.round),
+            TranscriptPhase::Opening => write!(f, "opening"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    /// `bytes` appended to the proof at `range`
+    Append {
+        label: TranscriptLabel,
+        range: Range<usize>,
+        bytes: Vec<u8>,
+    },
+    /// a challenge drawn, from the digest of the transcript
+    Challenge {
+        label: TranscriptLabel,
+        digest: [u8; Transcript::DIGEST_SIZE],
+    },
+}
+
+impl TranscriptEvent {
+    pub fn label(&self) -> TranscriptLabel {
+        match self {
+            TranscriptEvent::Append { label, .. } | TranscriptEvent::Challenge { label, .. } => {
+                *label
+            }
+        }
+    }
+}
+
+impl fmt::Display for TranscriptEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptEvent::Append { label, range, .. } => {
+                write!(f, "{}: append bytes {}..{}", label, range.start, range.end)
+            }
+            TranscriptEvent::Challenge { label, digest } => {
+                write!(f, "{}: challenge from digest {:02x?}", label, &digest[..8])
+            }
+        }
+    }
+}
+
+/// The messages of a transcript in recording mode, see `Transcript::start_recording`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TranscriptRecording {
+    pub events: Vec<TranscriptEvent>,
+}
+
+/// The first message a prover and a verifier recording disagree on; a missing event is past the
+/// end of its recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptDivergence {
+    pub index: usize,
+    pub prover: Option<TranscriptEvent>,
+    pub verifier: Option<TranscriptEvent>,
+}
+
+impl TranscriptDivergence {
+    /// Where the divergent message is sent, by the prover if it has sent it.
+    pub fn label(&self) -> TranscriptLabel {
+        self.prover
+            .as_ref()
+            .or(self.verifier.as_ref())
+            .unwrap()
+            .label()
+    }
+}
+
+impl fmt::Display for TranscriptDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |event: &Option<TranscriptEvent>| match event {
+            Some(event) => event.to_string(),
+            None => "nothing".to_string(),
+        };
+        write!(
+            f,
+            "message {} diverges at {}: the prover sends {}, the verifier {}",
+            self.index,
+            self.label(),
+            side(&self.prover),
+            side(&self.verifier)
+        )
+    }
+}
+
+/// Compare the recordings of the transcripts of a prover and a verifier, message by message,
+/// labels aside. Returns `None` if they are the same.
+pub fn first_divergence(
+    prover: &TranscriptRecording,
+    verifier: &TranscriptRecording,
+) -> Option<TranscriptDivergence> {
+    let same = |a: &TranscriptEvent, b: &TranscriptEvent| match (a, b) {
+        (
+            TranscriptEvent::Append {
+                range: ra,
+                bytes: ba,
+                ..
+            },
+            TranscriptEvent::Append {
+                range: rb,
+                bytes: bb,
+                ..
+            },
+        ) => ra == rb && ba == bb,
+        (
+            TranscriptEvent::Challenge { digest: da, .. },
+            TranscriptEvent::Challenge { digest: db, .. },
+        ) => da == db,
+        _ => false,
+    };
+    let len = prover.events.len().max(verifier.events.len());
+    (0..len)
+        .find(|&i| match (prover.events.get(i), verifier.events.get(i)) {
+            (Some(a), Some(b)) => !same(a, b),
+            _ => true,
+        })
+        .map(|index| TranscriptDivergence {
+            index,
+            prover: prover.events.get(index).cloned(),
+            verifier: verifier.events.get(index).cloned(),
+        })
+}
 
 pub struct Transcript {
-    pub hasher: SHA256hasher,
-    hash_start_idx: usize,
+    pub hasher: FiatShamirHasher,
+    /// the messages appended since the last challenge
+    absorbed: HashInput,
     digest: [u8; Self::DIGEST_SIZE],
     pub proof: Proof,
+    label: TranscriptLabel,
+    recording: Option<TranscriptRecording>,
 }
 
 impl Default for Transcript {
@@ -21,38 +302,79 @@ impl Transcript {
     pub const DIGEST_SIZE: usize = 32;
 
     fn hash_to_digest(&mut self) {
-        let hash_end_idx = self.proof.bytes.len();
-        if hash_end_idx > self.hash_start_idx {
-            self.hasher.hash(
-                &mut self.digest,
-                &self.proof.bytes[self.hash_start_idx..],
-                hash_end_idx - self.hash_start_idx,
-            );
-            self.hash_start_idx = hash_end_idx;
-        } else {
-            self.hasher
-                .hash_inplace(&mut self.digest, Self::DIGEST_SIZE)
+        self.hasher.squeeze(&mut self.absorbed, &mut self.digest);
+        if let Some(recording) = &mut self.recording {
+            recording.events.push(TranscriptEvent::Challenge {
+                label: self.label,
+                digest: self.digest,
+            });
+        }
+    }
+
+    /// Record the message appended from `start` on.
+    fn record_append(&mut self, start: usize) {
+        if let Some(recording) = &mut self.recording {
+            let range = start..self.proof.bytes.len();
+            recording.events.push(TranscriptEvent::Append {
+                label: self.label,
+                bytes: self.proof.bytes[range.clone()].to_vec(),
+                range,
+            });
         }
     }
 
     #[inline]
     pub fn new() -> Self {
+        Self::with_hash(&FiatShamirHashType::SHA256)
+    }
+
+    /// An empty transcript hashed with `fs_hash`.
+    pub fn with_hash(fs_hash: &FiatShamirHashType) -> Self {
+        let hasher = FiatShamirHasher::new(fs_hash);
         Transcript {
-            hasher: SHA256hasher,
-            hash_start_idx: 0,
+            absorbed: hasher.new_input(),
+            hasher,
             digest: [0u8; Self::DIGEST_SIZE],
             proof: Proof::default(),
+            label: TranscriptLabel::default(),
+            recording: None,
         }
     }
 
+    /// Record every message from now on, see `take_recording`.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(TranscriptRecording::default());
+    }
+
+    /// The messages recorded since `start_recording`, which stops the recording.
+    pub fn take_recording(&mut self) -> Option<TranscriptRecording> {
+        self.recording.take()
+    }
+
+    /// Label the next messages with `phase`, from its round 0.
+    pub fn set_phase(&mut self, phase: TranscriptPhase) {
+        self.label = TranscriptLabel { phase, round: 0 };
+    }
+
+    /// Label the next messages with the round `round` of the current phase.
+    pub fn set_round(&mut self, round: usize) {
+        self.label.round = round;
+    }
+
     pub fn append_f<F: Field + FieldSerde>(&mut self, f: F) {
         let cur_size = self.proof.bytes.len();
         self.proof.bytes.resize(cur_size + F::SIZE, 0);
         f.serialize_into(&mut self.proof.bytes[cur_size..]);
+        self.hasher.absorb_f(&mut self.absorbed, &f);
+        self.record_append(cur_size);
     }
 
     pub fn append_u8_slice(&mut self, buffer: &[u8], size: usize) {
+        let cur_size = self.proof.bytes.len();
         self.proof.append_u8_slice(buffer, size);
+        self.hasher
+            .absorb_u8_slice(&mut self.absorbed, &buffer[..size]);
+        self.record_append(cur_size);
     }
 
     pub fn challenge_f<F: Field>(&mut self) -> F::BaseField {
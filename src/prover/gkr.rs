@@ -3,16 +3,31 @@
 use arith::{Field, FieldSerde, MultiLinearPoly};
 use ark_std::{end_timer, start_timer};
 
-use crate::{sumcheck_prove_gkr_layer, Circuit, Config, GkrScratchpad, Transcript};
+use crate::{
+    sumcheck_prove_gkr_batch_layer, sumcheck_prove_gkr_layer, sumcheck_prove_gkr_layer_zk, Circuit,
+    Config, GkrScratchpad, InnerProductClaim, PhaseStats, Transcript, TranscriptPhase, WitnessRef,
+    ZkLayout,
+};
 
+/// `witness` holds the values of the layers of `circuit`. Also returns the time and proof
+/// bytes of each layer. In zero-knowledge mode, `zk` is the layout and the evaluations of the
+/// committed polynomial, and the claims the masks of each layer reduce to are returned too.
 // FIXME
 #[allow(clippy::type_complexity)]
 pub fn gkr_prove<F>(
     circuit: &Circuit<F>,
+    witness: WitnessRef<F>,
     sp: &mut [GkrScratchpad<F>],
     transcript: &mut Transcript,
     config: &Config,
-) -> (Vec<F>, Vec<Vec<F::BaseField>>, Vec<Vec<F::BaseField>>)
+    zk: Option<(&ZkLayout, &[F])>,
+) -> (
+    Vec<F::ChallengeField>,
+    Vec<Vec<F::ChallengeField>>,
+    Vec<Vec<F::ChallengeField>>,
+    Vec<InnerProductClaim<F::ChallengeField>>,
+    Vec<PhaseStats>,
+)
 where
     F: Field + FieldSerde,
 {
@@ -22,47 +37,236 @@ where
     let mut rz0 = vec![vec![]; config.get_num_repetitions()];
     let mut rz1 = vec![vec![]; config.get_num_repetitions()];
     log::trace!("repetition: {}", config.get_num_repetitions());
+    transcript.set_phase(TranscriptPhase::Layer(layer_num - 1));
     for _i in 0..circuit.layers.last().unwrap().output_var_num {
         for j in 0..config.get_num_repetitions() {
-            rz0[j].push(transcript.challenge_f::<F>());
-            rz1[j].push(F::BaseField::zero());
+            rz0[j].push(transcript.challenge_fext::<F::ChallengeField>());
+            rz1[j].push(F::ChallengeField::zero());
         }
     }
 
-    let mut alpha = F::BaseField::one();
-    let mut beta = F::BaseField::zero();
+    let mut alpha = F::ChallengeField::one();
+    let mut beta = F::ChallengeField::zero();
     let mut claimed_v = vec![];
+    let mut mask_claims = vec![];
+    let mut layer_vals = LayerValues::new(circuit, witness);
+    let mut layer_stats = vec![PhaseStats::default(); layer_num];
 
     for t in rz0.iter().take(config.get_num_repetitions()) {
-        claimed_v.push(MultiLinearPoly::<F>::eval_multilinear(
-            &circuit.layers.last().unwrap().output_vals.evals,
+        claimed_v.push(MultiLinearPoly::<F>::eval_multilinear_challenge(
+            witness.output(),
             t,
         ))
     }
 
     for i in (0..layer_num).rev() {
-        (rz0, rz1) = sumcheck_prove_gkr_layer(
-            &circuit.layers[i],
-            &rz0,
-            &rz1,
+        transcript.set_phase(TranscriptPhase::Layer(i));
+        let (stats, (new_rz0, new_rz1)) = PhaseStats::measure(transcript, |transcript| {
+            let input_vals = layer_vals.input_vals(i);
+            match zk {
+                Some((layout, committed)) => {
+                    let (new_rz0, new_rz1, claims) = sumcheck_prove_gkr_layer_zk(
+                        &circuit.layers[i],
+                        i,
+                        input_vals,
+                        &rz0,
+                        &rz1,
+                        &alpha,
+                        &beta,
+                        transcript,
+                        sp,
+                        config,
+                        layout,
+                        committed,
+                    );
+                    mask_claims.extend(claims);
+                    (new_rz0, new_rz1)
+                }
+                None => sumcheck_prove_gkr_layer(
+                    &circuit.layers[i],
+                    input_vals,
+                    &rz0,
+                    &rz1,
+                    &alpha,
+                    &beta,
+                    transcript,
+                    sp,
+                    config,
+                ),
+            }
+        });
+        (rz0, rz1) = (new_rz0, new_rz1);
+        layer_stats[i] = stats;
+        alpha = transcript.challenge_fext::<F::ChallengeField>();
+        beta = transcript.challenge_fext::<F::ChallengeField>();
+
+        log::trace!("Layer {} proved with alpha={:?}, beta={:?}", i, alpha, beta);
+        for (j, (rz0, rz1)) in rz0.iter().zip(&rz1).enumerate() {
+            log::trace!("rz0.{}: {:?}", j, rz0);
+            log::trace!("rz1.{}: {:?}", j, rz1);
+        }
+    }
+
+    end_timer!(timer);
+    (claimed_v, rz0, rz1, mask_claims, layer_stats)
+}
+
+/// `gkr_prove` for several circuits at once: the layers at the same depth from the output are
+/// proved by a single sumcheck per repetition; see `crate::batch`. `sp[k]` are the scratchpads
+/// of `circuits[k]`. Returns the claimed outputs of each circuit and the points of the claims on
+/// each input layer.
+#[allow(clippy::type_complexity)]
+pub fn gkr_prove_batch<F>(
+    circuits: &[&Circuit<F>],
+    sp: &mut [Vec<GkrScratchpad<F>>],
+    transcript: &mut Transcript,
+    config: &Config,
+) -> (
+    Vec<Vec<F::ChallengeField>>,
+    Vec<Vec<Vec<F::ChallengeField>>>,
+    Vec<Vec<Vec<F::ChallengeField>>>,
+)
+where
+    F: Field + FieldSerde,
+{
+    let timer = start_timer!(|| "gkr prove batch");
+    let reps = config.get_num_repetitions();
+    let depth = circuits.iter().map(|c| c.layers.len()).max().unwrap();
+
+    // the phases are labelled by the layers of the deepest circuit
+    transcript.set_phase(TranscriptPhase::Layer(depth - 1));
+    let mut rz0 = vec![];
+    let mut rz1 = vec![];
+    let mut claimed_v = vec![];
+    for c in circuits {
+        let mut rz0_k = vec![vec![]; reps];
+        let mut rz1_k = vec![vec![]; reps];
+        for _i in 0..c.layers.last().unwrap().output_var_num {
+            for j in 0..reps {
+                rz0_k[j].push(transcript.challenge_fext::<F::ChallengeField>());
+                rz1_k[j].push(F::ChallengeField::zero());
+            }
+        }
+        let output = WitnessRef::Circuit(c).output();
+        claimed_v.push(
+            rz0_k
+                .iter()
+                .map(|t| MultiLinearPoly::<F>::eval_multilinear_challenge(output, t))
+                .collect(),
+        );
+        rz0.push(rz0_k);
+        rz1.push(rz1_k);
+    }
+
+    let mut alpha = F::ChallengeField::one();
+    let mut beta = F::ChallengeField::zero();
+    let mut layer_vals: Vec<_> = circuits
+        .iter()
+        .map(|c| LayerValues::new(c, WitnessRef::Circuit(c)))
+        .collect();
+    for d in 0..depth {
+        transcript.set_phase(TranscriptPhase::Layer(depth - 1 - d));
+        // the circuits with a layer at this depth, and the index of that layer
+        let active: Vec<(usize, usize)> = circuits
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.layers.len() > d)
+            .map(|(k, c)| (k, c.layers.len() - 1 - d))
+            .collect();
+        let layers: Vec<_> = active
+            .iter()
+            .map(|&(k, i)| &circuits[k].layers[i])
+            .collect();
+        let active_rz0: Vec<_> = active
+            .iter()
+            .map(|&(k, _)| std::mem::take(&mut rz0[k]))
+            .collect();
+        let active_rz1: Vec<_> = active
+            .iter()
+            .map(|&(k, _)| std::mem::take(&mut rz1[k]))
+            .collect();
+        let input_vals: Vec<&[F]> = layer_vals
+            .iter_mut()
+            .zip(circuits)
+            .filter(|(_, c)| c.layers.len() > d)
+            .map(|(vals, c)| vals.input_vals(c.layers.len() - 1 - d))
+            .collect();
+        let active_sp: Vec<&mut [GkrScratchpad<F>]> = sp
+            .iter_mut()
+            .zip(circuits)
+            .filter(|(_, c)| c.layers.len() > d)
+            .map(|(sp, _)| &mut sp[..])
+            .collect();
+        let (new_rz0, new_rz1) = sumcheck_prove_gkr_batch_layer(
+            &layers,
+            &input_vals,
+            &active_rz0,
+            &active_rz1,
             &alpha,
             &beta,
             transcript,
-            sp,
+            active_sp,
             config,
         );
-        alpha = transcript.challenge_f::<F>();
-        beta = transcript.challenge_f::<F>();
-
-        log::trace!("Layer {} proved with alpha={:?}, beta={:?}", i, alpha, beta);
-        log::trace!("rz0.0: {:?}", rz0[0]);
-        log::trace!("rz0.1: {:?}", rz0[1]);
-        log::trace!("rz0.2: {:?}", rz0[2]);
-        log::trace!("rz1.0: {:?}", rz1[0]);
-        log::trace!("rz1.1: {:?}", rz1[1]);
-        log::trace!("rz1.2: {:?}", rz1[2]);
+        for ((&(k, _), new_rz0), new_rz1) in active.iter().zip(new_rz0).zip(new_rz1) {
+            rz0[k] = new_rz0;
+            rz1[k] = new_rz1;
+        }
+        alpha = transcript.challenge_fext::<F::ChallengeField>();
+        beta = transcript.challenge_fext::<F::ChallengeField>();
+        log::trace!("Depth {} proved with alpha={:?}, beta={:?}", d, alpha, beta);
     }
 
     end_timer!(timer);
     (claimed_v, rz0, rz1)
 }
+
+/// Input values of the layers, for a witness evaluated with `Circuit::evaluate_checkpointed`
+/// or `ProvingKey::evaluate_checkpointed`.
+/// The dropped values are recomputed from the checkpoint below, for all the layers up to the
+/// requested one at once; layers must be requested from the output to the input.
+struct LayerValues<'a, F: Field> {
+    circuit: &'a Circuit<F>,
+    witness: WitnessRef<'a, F>,
+    /// input values of the layers `first..first + recomputed.len()`
+    recomputed: Vec<Vec<F>>,
+    first: usize,
+}
+
+impl<'a, F: Field> LayerValues<'a, F> {
+    fn new(circuit: &'a Circuit<F>, witness: WitnessRef<'a, F>) -> Self {
+        LayerValues {
+            circuit,
+            witness,
+            recomputed: vec![],
+            first: 0,
+        }
+    }
+
+    fn input_vals(&mut self, i: usize) -> &[F] {
+        let layers = &self.circuit.layers;
+        let witness = self.witness;
+        if !witness.layer_input(i).is_empty() {
+            return witness.layer_input(i);
+        }
+        if i < self.first || i >= self.first + self.recomputed.len() {
+            let timer = start_timer!(|| format!("recompute layer {}", i));
+            let checkpoint = (0..i)
+                .rev()
+                .find(|&c| !witness.layer_input(c).is_empty())
+                .expect("the circuit is not evaluated");
+            self.recomputed.clear();
+            let vals = layers[checkpoint].evaluate_on(witness.layer_input(checkpoint));
+            self.recomputed.push(vals);
+            for layer in &layers[checkpoint + 1..i] {
+                let vals = layer.evaluate_on(self.recomputed.last().unwrap());
+                self.recomputed.push(vals);
+            }
+            self.first = checkpoint + 1;
+            end_timer!(timer);
+        }
+        // the layers above are proved already
+        self.recomputed.truncate(i - self.first + 1);
+        &self.recomputed[i - self.first]
+    }
+}
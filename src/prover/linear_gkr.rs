@@ -2,108 +2,421 @@
 
 use arith::{Field, FieldSerde, MultiLinearPoly};
 use ark_std::{end_timer, start_timer};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use std::{mem::size_of, time::Instant};
 
 use crate::{
-    gkr_prove, merge_multilinear_evals, Circuit, CommitmentSerde, Config, GkrScratchpad,
-    PolyCommitProver, Proof, Transcript,
+    gkr_prove, gkr_prove_batch, lookup_prove, merge_multilinear_evals, pad_point, BatchLayout,
+    Circuit, CircuitLayer, CommitmentSerde, Config, FiatShamirHasher, GkrScratchpad, LayerStats,
+    LookupLayout, PhaseStats, PolyCommitProver, Proof, ProofStats, ProverError, ProvingKey,
+    Transcript, TranscriptPhase, TranscriptRecording, VerificationError, Witness, WitnessRef,
+    ZkLayout,
 };
 
-pub fn grind<F: Field>(transcript: &mut Transcript, config: &Config) {
-    let timer = start_timer!(|| format!("grind {} bits", config.grinding_bits));
+/// Bytes of the nonce appended to the transcript by `grind`.
+pub const GRINDING_NONCE_BYTES: usize = size_of::<u64>();
 
-    let initial_hash = transcript.challenge_fs::<F>(256 / config.field_size);
-    let mut hash_bytes = [0u8; 256 / 8];
+/// The seed of the proof of work, derived from the transcript as elements of the base field.
+fn grinding_seed<F: Field>(transcript: &mut Transcript) -> [u8; 256 / 8] {
+    let field_size = F::BaseField::FIELD_SIZE;
+    let initial_hash = transcript.challenge_fs::<F>(256 / field_size);
+    let mut seed = [0u8; 256 / 8];
     let mut offset = 0;
-    let step = (config.field_size + 7) / 8;
+    let step = (field_size + 7) / 8;
 
     for h in initial_hash.iter() {
-        h.serialize_into(&mut hash_bytes[offset..]);
+        h.serialize_into(&mut seed[offset..]);
         offset += step;
     }
+    seed
+}
 
-    for _ in 0..(1 << config.grinding_bits) {
-        transcript.hasher.hash_inplace(&mut hash_bytes, 256 / 8);
+/// Whether `H(seed || nonce)` has at least `grinding_bits` leading zero bits.
+fn check_nonce(transcript: &Transcript, seed: &[u8], nonce: u64, grinding_bits: usize) -> bool {
+    let mut buffer = [0u8; 256 / 8 + GRINDING_NONCE_BYTES];
+    buffer[..seed.len()].copy_from_slice(seed);
+    buffer[seed.len()..].copy_from_slice(&nonce.to_le_bytes());
+    let mut hash = [0u8; 256 / 8];
+    transcript.hasher.hash(&mut hash, &buffer, buffer.len());
+
+    let mut zeros = 0;
+    for byte in hash {
+        zeros += byte.leading_zeros() as usize;
+        if byte != 0 || zeros >= grinding_bits {
+            break;
+        }
     }
-    transcript.append_u8_slice(&hash_bytes, 256 / 8);
+    zeros >= grinding_bits
+}
+
+/// Proof of work: search for the smallest nonce such that `H(seed || nonce)` has
+/// `config.grinding_bits` leading zero bits, and append it to the transcript. The search runs
+/// on the current rayon thread pool.
+pub fn grind<F: Field>(transcript: &mut Transcript, config: &Config) {
+    let timer = start_timer!(|| format!("grind {} bits", config.grinding_bits));
+    transcript.set_phase(TranscriptPhase::ProofOfWork);
+
+    let seed = grinding_seed::<F>(transcript);
+    let nonce = (0..u64::MAX)
+        .into_par_iter()
+        .find_first(|&nonce| check_nonce(transcript, &seed, nonce, config.grinding_bits))
+        .unwrap();
+    transcript.append_u8_slice(&nonce.to_le_bytes(), GRINDING_NONCE_BYTES);
     end_timer!(timer);
 }
 
+/// Check the nonce found by `grind`, read from `proof`, with a single hash, and append it to
+/// the transcript.
+pub fn verify_grind<F: Field>(
+    transcript: &mut Transcript,
+    config: &Config,
+    proof: &mut Proof,
+) -> Result<(), VerificationError> {
+    transcript.set_phase(TranscriptPhase::ProofOfWork);
+    let seed = grinding_seed::<F>(transcript);
+    let nonce_bytes = proof.get_next_slice(GRINDING_NONCE_BYTES)?;
+    let nonce = u64::from_le_bytes(nonce_bytes.as_slice().try_into().unwrap());
+    transcript.append_u8_slice(&nonce_bytes, GRINDING_NONCE_BYTES);
+    if !check_nonce(transcript, &seed, nonce, config.grinding_bits) {
+        return Err(VerificationError::ProofOfWork);
+    }
+    Ok(())
+}
+
+/// The largest numbers of input and output variables of the layers of `c`.
+fn max_var_nums<F: Field>(c: &Circuit<F>) -> [usize; 2] {
+    let max = |var_num: fn(&CircuitLayer<F>) -> usize| c.layers.iter().map(var_num).max().unwrap();
+    [
+        max(|layer| layer.input_var_num),
+        max(|layer| layer.output_var_num),
+    ]
+}
+
 pub struct Prover<F: Field + FieldSerde, PC: PolyCommitProver<F>> {
     config: Config,
     sp: Vec<GkrScratchpad<F>>,
+    /// scratchpads of each circuit of a batch
+    batch_sp: Vec<Vec<GkrScratchpad<F>>>,
     pp: PC::Param,
+    pool: ThreadPool,
 }
 
 impl<F: Field + FieldSerde, PC: PolyCommitProver<F>> Prover<F, PC> {
     pub fn new(config: &Config, pp: PC::Param) -> Self {
         // assert_eq!(config.field_type, crate::config::FieldType::M31);
-        assert_eq!(config.fs_hash, crate::config::FiatShamirHashType::SHA256);
+        // panics on the hashes not implemented yet
+        FiatShamirHasher::new(&config.fs_hash);
+        if config.fs_hash == crate::config::FiatShamirHashType::Poseidon {
+            // the Poseidon transcript absorbs the messages as BN254 elements
+            assert_eq!(config.field_type, crate::config::FieldType::BN254);
+        }
         assert_eq!(
             config.polynomial_commitment_type,
             crate::config::PolynomialCommitmentType::Raw
         );
+        if config.zero_knowledge {
+            assert!(
+                PC::HIDING,
+                "the zero-knowledge mode needs a hiding commitment"
+            );
+        }
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(config.num_threads)
+            .build()
+            .unwrap();
         Prover {
-            config: config.clone(),
+            config: config.for_challenge_field::<F::ChallengeField>(),
             sp: Vec::new(),
+            batch_sp: Vec::new(),
             pp,
+            pool,
         }
     }
 
     pub fn prepare_mem(&mut self, c: &Circuit<F>) {
-        let max_num_input_var = c
-            .layers
-            .iter()
-            .map(|layer| layer.input_var_num)
-            .max()
-            .unwrap();
-        let max_num_output_var = c
-            .layers
-            .iter()
-            .map(|layer| layer.output_var_num)
-            .max()
-            .unwrap();
-        self.sp = (0..self.config.get_num_repetitions())
+        self.sp = self.scratchpads(c);
+    }
+
+    /// Allocate the scratchpads of every circuit of a batch, whose layers at the same depth are
+    /// proved together.
+    pub fn prepare_mem_batch(&mut self, circuits: &[&Circuit<F>]) {
+        self.batch_sp = circuits.iter().map(|c| self.scratchpads(c)).collect();
+    }
+
+    /// One scratchpad per repetition, for the largest layers of `c`.
+    fn scratchpads(&self, c: &Circuit<F>) -> Vec<GkrScratchpad<F>> {
+        let [max_num_input_var, max_num_output_var] = max_var_nums(c);
+        (0..self.config.get_num_repetitions())
             .map(|_| GkrScratchpad::new(max_num_input_var, max_num_output_var))
-            .collect();
+            .collect()
+    }
+
+    /// Prove the evaluation of `c`. Panics on a statement `try_prove` rejects.
+    pub fn prove(&mut self, c: &Circuit<F>) -> (Vec<F::ChallengeField>, Proof) {
+        self.try_prove(c).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Same as `prove`, or the reason why `c` cannot be proved: an input missing from its
+    /// lookup table, or lookups in zero-knowledge mode.
+    pub fn try_prove(
+        &mut self,
+        c: &Circuit<F>,
+    ) -> Result<(Vec<F::ChallengeField>, Proof), ProverError> {
+        let mut transcript = self.transcript();
+        let (claimed_v, proof, _) =
+            self.prove_witness(c, WitnessRef::Circuit(c), &mut transcript)?;
+        Ok((claimed_v, proof))
+    }
+
+    /// Same as `prove`, also returning the time and proof bytes of each phase.
+    pub fn prove_with_stats(
+        &mut self,
+        c: &Circuit<F>,
+    ) -> (Vec<F::ChallengeField>, Proof, ProofStats) {
+        let mut transcript = self.transcript();
+        self.prove_witness(c, WitnessRef::Circuit(c), &mut transcript)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Same as `prove`, also returning the recording of the transcript, to be compared with
+    /// the one of `Verifier::verify_recorded` by `first_divergence`.
+    pub fn prove_recorded(
+        &mut self,
+        c: &Circuit<F>,
+    ) -> (Vec<F::ChallengeField>, Proof, TranscriptRecording) {
+        let mut transcript = self.transcript();
+        transcript.start_recording();
+        let (claimed_v, proof, _) = self
+            .prove_witness(c, WitnessRef::Circuit(c), &mut transcript)
+            .unwrap_or_else(|err| panic!("{}", err));
+        (claimed_v, proof, transcript.take_recording().unwrap())
+    }
+
+    /// Prove `witness` for the circuit of `pk`. The key is not modified, so the proofs of
+    /// several witnesses can be run concurrently by as many provers sharing it. Panics on a
+    /// statement `try_prove_with_key` rejects.
+    pub fn prove_with_key(
+        &mut self,
+        pk: &ProvingKey<F>,
+        witness: &Witness<F>,
+    ) -> (Vec<F::ChallengeField>, Proof) {
+        self.try_prove_with_key(pk, witness)
+            .unwrap_or_else(|err| panic!("{}", err))
     }
 
-    pub fn prove(&mut self, c: &Circuit<F>) -> (Vec<F>, Proof) {
+    /// Same as `prove_with_key`, or the reason why `witness` cannot be proved, as in
+    /// `try_prove`.
+    pub fn try_prove_with_key(
+        &mut self,
+        pk: &ProvingKey<F>,
+        witness: &Witness<F>,
+    ) -> Result<(Vec<F::ChallengeField>, Proof), ProverError> {
+        let mut transcript = self.transcript();
+        let (claimed_v, proof, _) =
+            self.prove_witness(pk.circuit(), WitnessRef::Witness(witness), &mut transcript)?;
+        Ok((claimed_v, proof))
+    }
+
+    fn transcript(&self) -> Transcript {
+        Transcript::with_hash(&self.config.fs_hash)
+    }
+
+    fn prove_witness(
+        &mut self,
+        c: &Circuit<F>,
+        witness: WitnessRef<F>,
+        transcript: &mut Transcript,
+    ) -> Result<(Vec<F::ChallengeField>, Proof, ProofStats), ProverError> {
+        if self.config.zero_knowledge && !c.lookups.is_empty() {
+            return Err(ProverError::InvalidStatement(
+                "lookups are not supported in zero-knowledge mode",
+            ));
+        }
         let timer = start_timer!(|| "prove");
-        // std::thread::sleep(std::time::Duration::from_secs(1)); // TODO
+        let start = Instant::now();
+        let mut stats = ProofStats {
+            layers: c
+                .layers
+                .iter()
+                .map(|layer| LayerStats {
+                    mul_gates: layer.mul.len(),
+                    add_gates: layer.add.len(),
+                    const_gates: layer.const_.len(),
+                    ..Default::default()
+                })
+                .collect(),
+            scratchpad_allocated_bytes: self.sp.iter().map(GkrScratchpad::size_in_bytes).sum(),
+            ..Default::default()
+        };
 
-        // PC commit
-        let pc_prover = PC::new(
-            &self.pp,
-            &MultiLinearPoly {
+        // with lookups the input is committed along with the multiplicities, in
+        // zero-knowledge mode along with the masks
+        let lookup_layout = (!c.lookups.is_empty()).then(|| LookupLayout::new(c));
+        let zk_layout = self
+            .config
+            .zero_knowledge
+            .then(|| ZkLayout::new(c, self.config.get_num_repetitions()));
+        let input = witness.layer_input(0);
+        let committed = match (&lookup_layout, &zk_layout) {
+            (Some(layout), _) => MultiLinearPoly {
+                var_num: layout.var_num,
+                evals: layout.committed_poly(c, input)?,
+            },
+            (_, Some(layout)) => MultiLinearPoly {
+                var_num: layout.var_num,
+                evals: layout.committed_poly(input, rand::thread_rng()),
+            },
+            (None, None) => MultiLinearPoly {
                 var_num: c.layers[0].input_var_num,
-                evals: c.layers[0].input_vals.evals.clone(),
+                evals: input.to_vec(),
             },
-        );
-        let commitment = pc_prover.commit();
-        let buffer_v = vec![F::default(); commitment.size() / F::SIZE];
-        let buffer = unsafe {
-            std::slice::from_raw_parts_mut(buffer_v.as_ptr() as *mut u8, commitment.size())
         };
-        commitment.serialize_into(buffer);
-        let mut transcript = Transcript::new();
-        transcript.append_u8_slice(buffer, commitment.size());
 
-        grind::<F>(&mut transcript, &self.config);
+        let pc_prover = self.commit(&committed, transcript, &mut stats);
 
-        let (claimed_v, rz0s, rz1s) = gkr_prove(c, &mut self.sp, &mut transcript, &self.config);
+        let zk = zk_layout
+            .as_ref()
+            .map(|layout| (layout, &committed.evals[..]));
+        let (claimed_v, rz0s, rz1s, mask_claims, layer_stats) = self
+            .pool
+            .install(|| gkr_prove(c, witness, &mut self.sp, transcript, &self.config, zk));
+        for (layer, sumcheck) in stats.layers.iter_mut().zip(layer_stats) {
+            layer.sumcheck = sumcheck;
+        }
 
-        let new_point = merge_multilinear_evals(
-            MultiLinearPoly {
-                var_num: c.layers[0].input_var_num,
-                evals: c.layers[0].input_vals.evals.clone(),
-            },
-            rz0s.into_iter().chain(rz1s.into_iter()).collect(),
-            &mut transcript,
-        );
+        let mut weights: Vec<_> = mask_claims.into_iter().map(|claim| claim.weights).collect();
+        if let Some(layout) = &lookup_layout {
+            let claims;
+            (stats.lookups, claims) = PhaseStats::measure(transcript, |transcript| {
+                lookup_prove(c, layout, &committed.evals, transcript)
+            });
+            weights = claims.into_iter().map(|claim| claim.weights).collect();
+        }
+
+        // the input is the first block of the committed polynomial; in zero-knowledge mode
+        // its claims are on the masked values
+        let mut z_weights = vec![];
+        if let Some(layout) = &zk_layout {
+            for (side, zs) in [&rz0s, &rz1s].into_iter().enumerate() {
+                for (j, z) in zs.iter().enumerate() {
+                    z_weights.push(layout.input_claim_weights(j, side, z));
+                }
+            }
+        }
+        let mut zs: Vec<_> = rz0s.into_iter().chain(rz1s).collect();
+        for z in zs.iter_mut() {
+            z.resize(committed.var_num, F::ChallengeField::zero());
+        }
+        let new_point: Vec<F::ChallengeField>;
+        (stats.merge, new_point) = PhaseStats::measure(transcript, |transcript| {
+            if zk_layout.is_some() {
+                // a claim in the random half masks the merge sumcheck
+                transcript.set_phase(TranscriptPhase::Merge);
+                let z = pad_point(
+                    (1..committed.var_num)
+                        .map(|_| transcript.challenge_fext::<F::ChallengeField>())
+                        .collect(),
+                );
+                transcript.append_f(MultiLinearPoly::<F>::eval_multilinear_challenge(
+                    &committed.evals,
+                    &z,
+                ));
+                zs.push(z);
+            }
+            merge_multilinear_evals(committed, zs, z_weights, weights, transcript)
+        });
 
+        transcript.set_phase(TranscriptPhase::Opening);
+        (stats.open, _) = PhaseStats::measure(transcript, |transcript| {
+            pc_prover.open(&self.pp, &new_point, transcript)
+        });
+
+        stats.total_time = start.elapsed();
+        end_timer!(timer);
+        Ok((claimed_v, std::mem::take(&mut transcript.proof), stats))
+    }
+
+    /// Prove several circuits at once, with a single commitment to all input layers and a
+    /// single opening; see `crate::batch`. The layers at the same depth from the output are
+    /// proved by a single sumcheck, so the proof grows with the largest circuit rather than with
+    /// the sum of their sizes. The scratchpads are those of `prepare_mem_batch`, allocated here
+    /// if they do not fit the batch. Returns the claimed outputs of each circuit.
+    #[allow(clippy::type_complexity)]
+    pub fn prove_batch(
+        &mut self,
+        circuits: &[&Circuit<F>],
+    ) -> Result<(Vec<Vec<F::ChallengeField>>, Proof), ProverError> {
+        let timer = start_timer!(|| format!("prove batch of {}", circuits.len()));
+        if circuits.is_empty() {
+            return Err(ProverError::InvalidStatement("the batch is empty"));
+        }
+        if circuits.iter().any(|c| !c.lookups.is_empty()) {
+            return Err(ProverError::InvalidStatement(
+                "batches do not support lookups",
+            ));
+        }
+        if self.config.zero_knowledge {
+            return Err(ProverError::InvalidStatement(
+                "batches do not support the zero-knowledge mode",
+            ));
+        }
+        let fits = self.batch_sp.len() == circuits.len()
+            && self.batch_sp.iter().zip(circuits).all(|(sp, c)| {
+                let [input_var_num, output_var_num] = max_var_nums(c);
+                sp.iter().all(|sp| sp.fits(input_var_num, output_var_num))
+            });
+        if !fits {
+            self.prepare_mem_batch(circuits);
+        }
+        let layout = BatchLayout::new(circuits);
+
+        let committed = MultiLinearPoly {
+            var_num: layout.var_num,
+            evals: layout.committed_poly(circuits),
+        };
+        let mut transcript = self.transcript();
+        let pc_prover = self.commit(&committed, &mut transcript, &mut ProofStats::default());
+
+        let (claimed_vs, rz0s, rz1s) = self.pool.install(|| {
+            gkr_prove_batch(circuits, &mut self.batch_sp, &mut transcript, &self.config)
+        });
+        let mut zs = vec![];
+        for (k, (rz0, rz1)) in rz0s.iter().zip(&rz1s).enumerate() {
+            zs.extend(rz0.iter().chain(rz1).map(|z| layout.point(k, z)));
+        }
+        let new_point = merge_multilinear_evals(committed, zs, vec![], vec![], &mut transcript);
+
+        transcript.set_phase(TranscriptPhase::Opening);
         pc_prover.open(&self.pp, &new_point, &mut transcript);
 
         end_timer!(timer);
-        (claimed_v, transcript.proof)
+        Ok((claimed_vs, transcript.proof))
+    }
+
+    /// Commit to `poly` and start `transcript` with the commitment and the grinding.
+    fn commit(
+        &self,
+        poly: &MultiLinearPoly<F>,
+        transcript: &mut Transcript,
+        stats: &mut ProofStats,
+    ) -> PC {
+        let pc_prover;
+        (stats.commit, pc_prover) = PhaseStats::measure(transcript, |transcript| {
+            let pc_prover = PC::new(&self.pp, poly);
+            let commitment = pc_prover.commit();
+            let buffer_v = vec![F::default(); commitment.size() / F::SIZE];
+            let buffer = unsafe {
+                std::slice::from_raw_parts_mut(buffer_v.as_ptr() as *mut u8, commitment.size())
+            };
+            commitment.serialize_into(buffer);
+            transcript.append_u8_slice(buffer, commitment.size());
+            pc_prover
+        });
+
+        (stats.grind, _) = PhaseStats::measure(transcript, |transcript| {
+            let config = &self.config;
+            self.pool.install(|| grind::<F>(transcript, config))
+        });
+        pc_prover
     }
 }
@@ -1,6 +1,6 @@
 use arith::{Field, FieldSerde};
 
-use crate::merkle_tree::HASH_SIZE;
+use crate::{merkle_tree::HASH_SIZE, VerificationError};
 
 /// Proof. In the serialized mode.
 #[derive(Debug, Clone, Default)]
@@ -22,24 +22,31 @@ impl Proof {
         self.idx += size;
     }
 
+    /// Number of bytes not read yet.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.idx)
+    }
+
+    fn next_bytes(&mut self, len: usize) -> Result<&[u8], VerificationError> {
+        let start = self.idx;
+        let end = start
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or(VerificationError::TruncatedProof)?;
+        self.step(len);
+        Ok(&self.bytes[start..end])
+    }
+
     #[inline(always)]
-    pub fn get_next_and_step<F: Field + FieldSerde>(&mut self) -> F {
-        let ret = F::deserialize_from(&self.bytes[self.idx..(self.idx + F::SIZE)]);
-        self.step(F::SIZE);
-        ret
+    pub fn get_next_and_step<F: Field + FieldSerde>(&mut self) -> Result<F, VerificationError> {
+        F::try_deserialize_from(self.next_bytes(F::SIZE)?).ok_or(VerificationError::MalformedProof)
     }
 
-    pub fn get_next_hash(&mut self) -> [u8; HASH_SIZE] {
-        let ret = self.bytes[self.idx..(self.idx + HASH_SIZE)]
-            .try_into()
-            .unwrap();
-        self.step(HASH_SIZE);
-        ret
+    pub fn get_next_hash(&mut self) -> Result<[u8; HASH_SIZE], VerificationError> {
+        Ok(self.next_bytes(HASH_SIZE)?.try_into().unwrap())
     }
 
-    pub fn get_next_slice(&mut self, len: usize) -> Vec<u8> {
-        let ret = self.bytes[self.idx..(self.idx + len)].to_vec();
-        self.step(len);
-        ret
+    pub fn get_next_slice(&mut self, len: usize) -> Result<Vec<u8>, VerificationError> {
+        Ok(self.next_bytes(len)?.to_vec())
     }
 }
@@ -1,15 +1,19 @@
+use std::mem::size_of;
+
 use arith::Field;
 
+/// Bookkeeping tables of the layer sumchecks. They hold challenge field elements: the input
+/// values are lifted when the first challenge is received.
 #[derive(Clone, Debug)]
 pub struct GkrScratchpad<F: Field> {
-    pub(crate) v_evals: Vec<F>,
-    pub(crate) hg_evals: Vec<F>,
+    pub(crate) v_evals: Vec<F::ChallengeField>,
+    pub(crate) hg_evals: Vec<F::ChallengeField>,
 
-    pub(crate) eq_evals_at_rx: Vec<F::BaseField>,
-    pub(crate) eq_evals_at_rz0: Vec<F::BaseField>,
-    pub(crate) eq_evals_at_rz1: Vec<F::BaseField>,
-    pub(crate) eq_evals_first_half: Vec<F::BaseField>,
-    pub(crate) eq_evals_second_half: Vec<F::BaseField>,
+    pub(crate) eq_evals_at_rx: Vec<F::ChallengeField>,
+    pub(crate) eq_evals_at_rz0: Vec<F::ChallengeField>,
+    pub(crate) eq_evals_at_rz1: Vec<F::ChallengeField>,
+    pub(crate) eq_evals_first_half: Vec<F::ChallengeField>,
+    pub(crate) eq_evals_second_half: Vec<F::ChallengeField>,
 
     pub(crate) gate_exists: Vec<bool>,
 }
@@ -19,16 +23,36 @@ impl<F: Field> GkrScratchpad<F> {
         let max_input_num = 1 << max_num_input_var;
         let max_output_num = 1 << max_num_output_var;
         GkrScratchpad {
-            v_evals: vec![F::default(); max_input_num],
-            hg_evals: vec![F::default(); max_input_num],
+            v_evals: vec![F::ChallengeField::default(); max_input_num],
+            hg_evals: vec![F::ChallengeField::default(); max_input_num],
 
-            eq_evals_at_rx: vec![F::BaseField::default(); max_input_num],
-            eq_evals_at_rz0: vec![F::BaseField::default(); max_output_num],
-            eq_evals_at_rz1: vec![F::BaseField::default(); max_output_num],
-            eq_evals_first_half: vec![F::BaseField::default(); max_output_num],
-            eq_evals_second_half: vec![F::BaseField::default(); max_output_num],
+            eq_evals_at_rx: vec![F::ChallengeField::default(); max_input_num],
+            eq_evals_at_rz0: vec![F::ChallengeField::default(); max_output_num],
+            eq_evals_at_rz1: vec![F::ChallengeField::default(); max_output_num],
+            eq_evals_first_half: vec![F::ChallengeField::default(); max_output_num],
+            eq_evals_second_half: vec![F::ChallengeField::default(); max_output_num],
 
             gate_exists: vec![false; max_input_num],
         }
     }
+
+    /// Whether the tables are large enough for layers of up to these numbers of variables.
+    pub(crate) fn fits(&self, max_num_input_var: usize, max_num_output_var: usize) -> bool {
+        self.v_evals.len() >= 1 << max_num_input_var
+            && self.eq_evals_at_rz0.len() >= 1 << max_num_output_var
+    }
+
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        let tables = [
+            &self.v_evals,
+            &self.hg_evals,
+            &self.eq_evals_at_rx,
+            &self.eq_evals_at_rz0,
+            &self.eq_evals_at_rz1,
+            &self.eq_evals_first_half,
+            &self.eq_evals_second_half,
+        ];
+        tables.iter().map(|t| t.len()).sum::<usize>() * size_of::<F::ChallengeField>()
+            + self.gate_exists.len() * size_of::<bool>()
+    }
 }
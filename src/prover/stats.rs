@@ -0,0 +1,67 @@
+//! Statistics of a run of the prover, returned by `Prover::prove_with_stats`.
+
+use std::time::{Duration, Instant};
+
+use crate::Transcript;
+
+/// Wall time spent in a phase of the prover and proof bytes it produced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseStats {
+    pub time: Duration,
+    pub bytes: usize,
+}
+
+impl PhaseStats {
+    /// Measure the time of `f` and the bytes it appends to the proof in `transcript`.
+    pub(crate) fn measure<T>(
+        transcript: &mut Transcript,
+        f: impl FnOnce(&mut Transcript) -> T,
+    ) -> (Self, T) {
+        let start = Instant::now();
+        let start_len = transcript.proof.bytes.len();
+        let res = f(transcript);
+        let stats = PhaseStats {
+            time: start.elapsed(),
+            bytes: transcript.proof.bytes.len() - start_len,
+        };
+        (stats, res)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayerStats {
+    /// the sumchecks of all repetitions, with the claims of the layer
+    pub sumcheck: PhaseStats,
+    pub mul_gates: usize,
+    pub add_gates: usize,
+    pub const_gates: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofStats {
+    pub commit: PhaseStats,
+    pub grind: PhaseStats,
+    /// indexed like `circuit.layers`; the layers are proved from the last one
+    pub layers: Vec<LayerStats>,
+    /// the lookup arguments, if the circuit has lookup columns
+    pub lookups: PhaseStats,
+    /// the sumcheck merging all input claims into a single point
+    pub merge: PhaseStats,
+    pub open: PhaseStats,
+    pub total_time: Duration,
+    /// memory held by the prover scratchpads, as allocated by `prepare_mem`; not a peak: the
+    /// scratchpads may be sized for a larger circuit, and the buffers allocated for this proof
+    /// (committed polynomial, lookup trees, merge sumcheck, recomputed layers) are not counted
+    pub scratchpad_allocated_bytes: usize,
+}
+
+impl ProofStats {
+    pub fn proof_bytes(&self) -> usize {
+        self.commit.bytes
+            + self.grind.bytes
+            + self.layers.iter().map(|l| l.sumcheck.bytes).sum::<usize>()
+            + self.lookups.bytes
+            + self.merge.bytes
+            + self.open.bytes
+    }
+}
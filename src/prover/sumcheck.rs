@@ -1,20 +1,26 @@
 use arith::{Field, FieldSerde, MultiLinearPoly};
+use rayon::prelude::*;
 
-use crate::{CircuitLayer, Config, GkrScratchpad, SumcheckGkrHelper, Transcript};
+use crate::{
+    inner_product, sumcheck_prove, CircuitLayer, Config, GkrScratchpad, InnerProductClaim,
+    LayerMask, SumcheckGkrHelper, Transcript, TranscriptPhase, VirtualPolynomial, ZkLayout,
+};
 
+/// `input_vals` are the values of the layer input, which may have been dropped from `layer`.
 // FIXME
 #[allow(clippy::too_many_arguments)]
 #[allow(clippy::type_complexity)]
 pub fn sumcheck_prove_gkr_layer<F>(
     layer: &CircuitLayer<F>,
-    rz0: &[Vec<F::BaseField>],
-    rz1: &[Vec<F::BaseField>],
-    alpha: &F::BaseField,
-    beta: &F::BaseField,
+    input_vals: &[F],
+    rz0: &[Vec<F::ChallengeField>],
+    rz1: &[Vec<F::ChallengeField>],
+    alpha: &F::ChallengeField,
+    beta: &F::ChallengeField,
     transcript: &mut Transcript,
     sp: &mut [GkrScratchpad<F>],
     config: &Config,
-) -> (Vec<Vec<F::BaseField>>, Vec<Vec<F::BaseField>>)
+) -> (Vec<Vec<F::ChallengeField>>, Vec<Vec<F::ChallengeField>>)
 where
     F: Field + FieldSerde,
 {
@@ -22,39 +28,62 @@ where
     assert_eq!(config.get_num_repetitions(), sp.len());
     for (j, sp_) in sp.iter_mut().enumerate() {
         helpers.push(SumcheckGkrHelper::new(
-            layer, &rz0[j], &rz1[j], alpha, beta, sp_,
+            layer, input_vals, &rz0[j], &rz1[j], alpha, beta, sp_,
         ));
     }
 
-    for i_var in 0..layer.input_var_num * 2 {
-        for (_j, helper) in helpers
-            .iter_mut()
-            .enumerate()
-            .take(config.get_num_repetitions())
-        {
-            if i_var == 0 {
-                helper.prepare_g_x_vals()
-            }
-            if i_var == layer.input_var_num {
+    let sumcheck_var_num = layer.input_var_num * 2;
+    for i_var in 0..sumcheck_var_num {
+        transcript.set_round(i_var);
+        if i_var == 0 {
+            helpers
+                .par_iter_mut()
+                .for_each(|helper| helper.prepare_g_x_vals());
+        }
+        if i_var == layer.input_var_num {
+            helpers.par_iter_mut().for_each(|helper| {
                 let vx_claim = helper.vx_claim();
                 helper.prepare_h_y_vals(vx_claim)
-            }
-
-            let evals = helper.poly_evals_at(i_var, 2);
+            });
+        }
 
-            transcript.append_f(evals[0]);
-            transcript.append_f(evals[1]);
-            transcript.append_f(evals[2]);
+        // repetitions are independent until they meet in the transcript: evaluate them
+        // concurrently, then append and draw challenges in repetition order
+        let evals: Vec<[F::ChallengeField; 3]> = helpers
+            .par_iter_mut()
+            .map(|helper| helper.poly_evals_at(i_var, 2))
+            .collect();
 
-            let r = transcript.challenge_f::<F>();
-            helper.receive_challenge(i_var, r);
-            if i_var == layer.input_var_num - 1 {
+        if i_var == layer.input_var_num - 1 {
+            // each vx claim goes to the transcript before the next repetition's evaluations;
+            // the tables are down to two entries here, so folding sequentially is free
+            for (helper, evals) in helpers.iter_mut().zip(evals.iter()) {
+                transcript.append_f(evals[0]);
+                transcript.append_f(evals[1]);
+                transcript.append_f(evals[2]);
+                let r = transcript.challenge_fext::<F::ChallengeField>();
+                helper.receive_challenge(i_var, r);
                 log::trace!("vx claim: {:?}", helper.vx_claim());
                 transcript.append_f(helper.vx_claim());
             }
+        } else {
+            let rs: Vec<F::ChallengeField> = evals
+                .iter()
+                .map(|evals| {
+                    transcript.append_f(evals[0]);
+                    transcript.append_f(evals[1]);
+                    transcript.append_f(evals[2]);
+                    transcript.challenge_fext::<F::ChallengeField>()
+                })
+                .collect();
+            helpers
+                .par_iter_mut()
+                .zip(rs)
+                .for_each(|(helper, r)| helper.receive_challenge(i_var, r));
         }
     }
 
+    transcript.set_round(sumcheck_var_num);
     for (j, helper) in helpers
         .iter()
         .enumerate()
@@ -73,63 +102,379 @@ where
     (rz0s, rz1s)
 }
 
+/// `sumcheck_prove_gkr_layer` in zero-knowledge mode, for the layer `layer_id`, with the masks
+/// laid out by `layout` in `committed`; see `crate::zk`. Also returns the claims the masks
+/// reduce to, one per repetition.
+// FIXME
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn sumcheck_prove_gkr_layer_zk<F>(
+    layer: &CircuitLayer<F>,
+    layer_id: usize,
+    input_vals: &[F],
+    rz0: &[Vec<F::ChallengeField>],
+    rz1: &[Vec<F::ChallengeField>],
+    alpha: &F::ChallengeField,
+    beta: &F::ChallengeField,
+    transcript: &mut Transcript,
+    sp: &mut [GkrScratchpad<F>],
+    config: &Config,
+    layout: &ZkLayout,
+    committed: &[F],
+) -> (
+    Vec<Vec<F::ChallengeField>>,
+    Vec<Vec<F::ChallengeField>>,
+    Vec<InnerProductClaim<F::ChallengeField>>,
+)
+where
+    F: Field + FieldSerde,
+{
+    let reps = config.get_num_repetitions();
+    assert_eq!(reps, sp.len());
+    let mut masks: Vec<LayerMask<F::ChallengeField>> = (0..reps)
+        .map(|j| {
+            LayerMask::new(
+                layout, committed, layer_id, j, &rz0[j], &rz1[j], *alpha, *beta,
+            )
+        })
+        .collect();
+    let mut helpers = vec![];
+    for (j, sp_) in sp.iter_mut().enumerate() {
+        helpers.push(SumcheckGkrHelper::new(
+            layer, input_vals, &rz0[j], &rz1[j], alpha, beta, sp_,
+        ));
+    }
+
+    // the sums of the sumcheck masks come before their coefficient
+    transcript.set_round(0);
+    for mask in &masks {
+        transcript.append_f(mask.sum());
+    }
+    let rho = transcript.challenge_fext::<F::ChallengeField>();
+    masks.iter_mut().for_each(|mask| mask.rho = rho);
+
+    let var_num = layer.input_var_num;
+    for i_var in 0..var_num * 2 {
+        transcript.set_round(i_var);
+        if i_var == 0 {
+            helpers
+                .par_iter_mut()
+                .for_each(|helper| helper.prepare_g_x_vals());
+        }
+        if i_var == var_num {
+            helpers
+                .par_iter_mut()
+                .zip(&masks)
+                .for_each(|(helper, mask)| {
+                    let vx_claim = helper.vx_claim() + mask.masked_value(0);
+                    helper.prepare_h_y_vals(vx_claim)
+                });
+        }
+
+        let evals: Vec<[F::ChallengeField; 4]> = helpers
+            .par_iter_mut()
+            .zip(&masks)
+            .map(|(helper, mask)| {
+                let p = helper.poly_evals_at(i_var, 2);
+                mask.round_evals(p, helper.hg_sums_at(i_var))
+            })
+            .collect();
+        for ((helper, mask), evals) in helpers.iter_mut().zip(&mut masks).zip(evals) {
+            for eval in evals {
+                transcript.append_f(eval);
+            }
+            let r = transcript.challenge_fext::<F::ChallengeField>();
+            helper.receive_challenge(i_var, r);
+            mask.receive_challenge(r);
+            if i_var == var_num - 1 {
+                transcript.append_f(helper.vx_claim() + mask.masked_value(0));
+            }
+            if i_var == var_num * 2 - 1 {
+                transcript.append_f(helper.vy_claim() + mask.masked_value(1));
+            }
+        }
+    }
+
+    // the round over w, which reveals the masks of the layer above at a random point only
+    transcript.set_round(var_num * 2);
+    let mut points = vec![];
+    for (helper, mask) in helpers.iter().zip(&mut masks) {
+        let vy_claim = helper.vy_claim() + mask.masked_value(1);
+        for eval in mask.w_round_evals(helper.hg_claim() * vy_claim) {
+            transcript.append_f(eval);
+        }
+        let c = transcript.challenge_fext::<F::ChallengeField>();
+        mask.receive_challenge(c);
+        let mut point = helper.rx.clone();
+        point.extend(&helper.ry);
+        point.push(c);
+        points.push(point);
+    }
+
+    transcript.set_round(var_num * 2 + 1);
+    let claims = points
+        .iter()
+        .enumerate()
+        .map(|(j, point)| {
+            let weights = layout
+                .layer_claim_weights(layer_id, j, &rz0[j], &rz1[j], *alpha, *beta, point, rho);
+            let value = inner_product(committed, &weights);
+            transcript.append_f(value);
+            InnerProductClaim { weights, value }
+        })
+        .collect();
+
+    let rz0s = helpers.iter().map(|helper| helper.rx.clone()).collect();
+    let rz1s = helpers.iter().map(|helper| helper.ry.clone()).collect();
+    (rz0s, rz1s, claims)
+}
+
+/// A circuit in `sumcheck_prove_gkr_batch_layer`. Its layer has `var_num` input variables, the
+/// first ones of the `x` and `y` halves of the batch sumcheck; over the others its sum is
+/// multiplied by `eq(0, .)`, so it only contributes `rest * (1 - r)` in their rounds.
+struct BatchedGkrHelper<'a, F: Field> {
+    helper: SumcheckGkrHelper<'a, F>,
+    var_num: usize,
+    /// power of the batching challenge
+    weight: F::ChallengeField,
+    /// `eq(0, .)` at the challenges of the rounds past the layer variables
+    eq: F::ChallengeField,
+    /// sum over the layer variables left, once those of the current half are bound
+    rest: F::ChallengeField,
+}
+
+impl<'a, F: Field> BatchedGkrHelper<'a, F> {
+    /// The variable of the layer in round `i_var` of a batch sumcheck over `batch_var_num`
+    /// variables per half, if any.
+    fn layer_var(&self, i_var: usize, batch_var_num: usize) -> Option<usize> {
+        if i_var < batch_var_num {
+            (i_var < self.var_num).then_some(i_var)
+        } else {
+            (i_var - batch_var_num < self.var_num).then_some(self.var_num + i_var - batch_var_num)
+        }
+    }
+
+    fn poly_evals_at(&mut self, i_var: usize, batch_var_num: usize) -> [F::ChallengeField; 3] {
+        let evals = match self.layer_var(i_var, batch_var_num) {
+            Some(var_idx) => self.helper.poly_evals_at(var_idx, 2),
+            None => [self.rest, F::ChallengeField::zero(), -self.rest],
+        };
+        evals.map(|eval| eval * self.eq * self.weight)
+    }
+
+    fn receive_challenge(&mut self, i_var: usize, batch_var_num: usize, r: F::ChallengeField) {
+        let Some(var_idx) = self.layer_var(i_var, batch_var_num) else {
+            self.eq *= F::ChallengeField::one() - r;
+            return;
+        };
+        self.helper.receive_challenge(var_idx, r);
+        if var_idx + 1 == self.var_num {
+            let vx_claim = self.helper.vx_claim();
+            self.helper.prepare_h_y_vals(vx_claim);
+            if self.var_num < batch_var_num {
+                let [p0, p1, _] = self.helper.poly_evals_at(self.var_num, 2);
+                self.rest = p0 + p1;
+            }
+        } else if var_idx + 1 == self.var_num * 2 {
+            self.rest = self.helper.hg_claim() * self.helper.vy_claim();
+        }
+    }
+}
+
+/// `sumcheck_prove_gkr_layer` for the layers at the same depth of several circuits, with a
+/// single sumcheck per repetition over the random linear combination of their sums; see
+/// `crate::batch`. `rz0[k][j]`, `rz1[k][j]` and `sp[k][j]` are those of `layers[k]` in
+/// repetition `j`, and so are the returned points.
+// FIXME
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn sumcheck_prove_gkr_batch_layer<F>(
+    layers: &[&CircuitLayer<F>],
+    input_vals: &[&[F]],
+    rz0: &[Vec<Vec<F::ChallengeField>>],
+    rz1: &[Vec<Vec<F::ChallengeField>>],
+    alpha: &F::ChallengeField,
+    beta: &F::ChallengeField,
+    transcript: &mut Transcript,
+    sp: Vec<&mut [GkrScratchpad<F>]>,
+    config: &Config,
+) -> (
+    Vec<Vec<Vec<F::ChallengeField>>>,
+    Vec<Vec<Vec<F::ChallengeField>>>,
+)
+where
+    F: Field + FieldSerde,
+{
+    let reps = config.get_num_repetitions();
+    // a single layer is proved as when its circuit is alone
+    let lambda = if layers.len() > 1 {
+        transcript.challenge_fext::<F::ChallengeField>()
+    } else {
+        F::ChallengeField::one()
+    };
+    let mut weight = F::ChallengeField::one();
+    let mut helpers: Vec<Vec<BatchedGkrHelper<F>>> = vec![];
+    for (k, sp_k) in sp.into_iter().enumerate() {
+        assert_eq!(reps, sp_k.len());
+        helpers.push(
+            sp_k.iter_mut()
+                .enumerate()
+                .map(|(j, sp_)| BatchedGkrHelper {
+                    helper: SumcheckGkrHelper::new(
+                        layers[k],
+                        input_vals[k],
+                        &rz0[k][j],
+                        &rz1[k][j],
+                        alpha,
+                        beta,
+                        sp_,
+                    ),
+                    var_num: layers[k].input_var_num,
+                    weight,
+                    eq: F::ChallengeField::one(),
+                    rest: F::ChallengeField::zero(),
+                })
+                .collect(),
+        );
+        weight *= lambda;
+    }
+
+    let var_num = layers
+        .iter()
+        .map(|layer| layer.input_var_num)
+        .max()
+        .unwrap();
+    for i_var in 0..var_num * 2 {
+        transcript.set_round(i_var);
+        if i_var == 0 {
+            helpers.par_iter_mut().flatten().for_each(|helper| {
+                helper.helper.prepare_g_x_vals();
+            });
+        }
+
+        let evals: Vec<Vec<[F::ChallengeField; 3]>> = helpers
+            .par_iter_mut()
+            .map(|helpers| {
+                helpers
+                    .par_iter_mut()
+                    .map(|helper| helper.poly_evals_at(i_var, var_num))
+                    .collect()
+            })
+            .collect();
+        let sums: Vec<[F::ChallengeField; 3]> = (0..reps)
+            .map(|j| {
+                evals
+                    .iter()
+                    .fold([F::ChallengeField::zero(); 3], |acc, evals| {
+                        [0, 1, 2].map(|d| acc[d] + evals[j][d])
+                    })
+            })
+            .collect();
+
+        if helpers
+            .iter()
+            .any(|helpers| i_var + 1 == helpers[0].var_num)
+        {
+            // the vx claims go to the transcript right after the challenge of their
+            // repetition, in circuit order
+            for (j, sums) in sums.iter().enumerate() {
+                sums.iter().for_each(|&eval| transcript.append_f(eval));
+                let r = transcript.challenge_fext::<F::ChallengeField>();
+                for helpers in helpers.iter_mut() {
+                    helpers[j].receive_challenge(i_var, var_num, r);
+                    if i_var + 1 == helpers[j].var_num {
+                        transcript.append_f(helpers[j].helper.vx_claim());
+                    }
+                }
+            }
+        } else {
+            let rs: Vec<F::ChallengeField> = sums
+                .iter()
+                .map(|sums| {
+                    sums.iter().for_each(|&eval| transcript.append_f(eval));
+                    transcript.challenge_fext::<F::ChallengeField>()
+                })
+                .collect();
+            helpers.par_iter_mut().for_each(|helpers| {
+                helpers
+                    .par_iter_mut()
+                    .zip(&rs)
+                    .for_each(|(helper, &r)| helper.receive_challenge(i_var, var_num, r))
+            });
+        }
+    }
+
+    transcript.set_round(var_num * 2);
+    for j in 0..reps {
+        for helpers in &helpers {
+            transcript.append_f(helpers[j].helper.vy_claim());
+        }
+    }
+
+    let rz0s = helpers
+        .iter()
+        .map(|helpers| helpers.iter().map(|h| h.helper.rx.clone()).collect())
+        .collect();
+    let rz1s = helpers
+        .iter()
+        .map(|helpers| helpers.iter().map(|h| h.helper.ry.clone()).collect())
+        .collect();
+    (rz0s, rz1s)
+}
+
+/// Reduce the claims on the input layer at the points `zs` to a single point, by a sumcheck
+/// over a random linear combination of them. The claims are followed by claims on inner
+/// products of the polynomial with the sparse `weights`, given as `(index, weight)` pairs.
+/// `z_weights[k]`, if any, are sparse weights added to the claim at `zs[k]`.
 pub fn merge_multilinear_evals<F: Field + FieldSerde>(
     poly: MultiLinearPoly<F>,
-    zs: Vec<Vec<F::BaseField>>,
+    zs: Vec<Vec<F::ChallengeField>>,
+    z_weights: Vec<Vec<(usize, F::ChallengeField)>>,
+    weights: Vec<Vec<(usize, F::ChallengeField)>>,
     transcript: &mut Transcript,
-) -> Vec<F> {
-    let mut eqs: Vec<Vec<<F as Field>::BaseField>> = vec![];
+) -> Vec<F::ChallengeField> {
+    transcript.set_phase(TranscriptPhase::Merge);
+    let one = F::ChallengeField::one();
+    let mut eqs: Vec<Vec<F::ChallengeField>> = vec![];
     for z in zs.iter() {
-        let mut res = vec![F::BaseField::one()];
+        let mut res = vec![one];
         for &b in z.iter().rev() {
             res = res
                 .iter()
-                .flat_map(|&prod| [prod * (F::BaseField::one() - b), prod * b])
+                .flat_map(|&prod| [prod * (one - b), prod * b])
                 .collect();
         }
         eqs.push(res);
     }
-    let r = transcript.challenge_fext::<F>();
+    for (eq, w) in eqs.iter_mut().zip(&z_weights) {
+        for &(i, w_i) in w {
+            eq[i] += w_i;
+        }
+    }
+    let r = transcript.challenge_fext::<F::ChallengeField>();
     let mut eq = vec![];
     for i in 0..eqs[0].len() {
-        let mut res = F::zero();
+        let mut res = F::ChallengeField::zero();
         for j in 0..eqs.len() {
-            res = (res * r).add_base_elem(&eqs[j][i]);
+            res = res * r + eqs[j][i];
         }
         eq.push(res);
     }
-    let mut poly_evals = poly.evals;
-    let var_num = poly.var_num;
-    let mut new_point = vec![];
-    for i in 0..var_num {
-        let m = 1 << (var_num - i);
-        let (sum_0, sum_1, sum_2) =
-            (0..m)
-                .step_by(2)
-                .fold((F::zero(), F::zero(), F::zero()), |acc, x| {
-                    let p_0 = poly_evals[x];
-                    let p_1 = poly_evals[x + 1];
-                    let e_0 = eq[x];
-                    let e_1 = eq[x + 1];
-                    (
-                        acc.0 + p_0 * e_0,
-                        acc.1 + p_1 * e_1,
-                        acc.2 + (p_1 + p_1 - p_0) * (e_1 + e_1 - e_0),
-                    )
-                });
-        transcript.append_f(sum_0);
-        transcript.append_f(sum_1);
-        transcript.append_f(sum_2);
-        let challenge = transcript.challenge_fext::<F>();
-        new_point.push(challenge);
-        sumcheck_next_domain(&mut poly_evals, m / 2, challenge);
-        sumcheck_next_domain(&mut eq, m / 2, challenge);
+    for w in &weights {
+        eq.iter_mut().for_each(|e| *e *= r);
+        for &(i, w_i) in w {
+            eq[i] += w_i;
+        }
     }
+    let poly_evals: Vec<F::ChallengeField> = poly
+        .evals
+        .into_iter()
+        .map(F::ChallengeField::from)
+        .collect();
+    let mut virtual_poly = VirtualPolynomial::new(poly.var_num);
+    let poly_idx = virtual_poly.add_mle(poly_evals);
+    let eq_idx = virtual_poly.add_mle(eq);
+    virtual_poly.add_product(F::ChallengeField::one(), vec![poly_idx, eq_idx]);
+    let (new_point, _) = sumcheck_prove(virtual_poly, transcript);
     new_point
 }
-
-fn sumcheck_next_domain<F: Field>(poly_evals: &mut Vec<F>, m: usize, challenge: F) {
-    for j in 0..m {
-        poly_evals[j] = poly_evals[j * 2] + (poly_evals[j * 2 + 1] - poly_evals[j * 2]) * challenge;
-    }
-}
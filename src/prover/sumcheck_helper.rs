@@ -1,7 +1,12 @@
 use arith::Field;
+use rayon::prelude::*;
 
 use crate::{CircuitLayer, GkrScratchpad};
 
+/// Minimum number of entries handled by a thread in the parallel loops over the bookkeeping
+/// tables, below which splitting costs more than it saves.
+const PAR_MIN_LEN: usize = 1 << 10;
+
 #[inline(always)]
 fn _eq<F: Field>(x: &F, y: &F) -> F {
     // x * y + (1 - x) * (1 - y)
@@ -44,13 +49,89 @@ fn eq_eval_at<F: Field>(
     eq_evals_at_primitive(&r[0..first_half_bits], mul_factor, sqrt_n_1st);
     eq_evals_at_primitive(&r[first_half_bits..], &F::one(), sqrt_n_2nd);
 
-    for (i, eq_eval) in eq_evals.iter_mut().enumerate().take(1 << r.len()) {
-        let first_half = i & first_half_mask;
-        let second_half = i >> first_half_bits;
-        *eq_eval = sqrt_n_1st[first_half] * sqrt_n_2nd[second_half];
+    let (sqrt_n_1st, sqrt_n_2nd) = (&*sqrt_n_1st, &*sqrt_n_2nd);
+    eq_evals[..1 << r.len()]
+        .par_iter_mut()
+        .with_min_len(PAR_MIN_LEN)
+        .enumerate()
+        .for_each(|(i, eq_eval)| {
+            let first_half = i & first_half_mask;
+            let second_half = i >> first_half_bits;
+            *eq_eval = sqrt_n_1st[first_half] * sqrt_n_2nd[second_half];
+        });
+}
+
+/// Set `buf[i] = f(i, buf[2i], buf[2i + 1])` for all `i < n`, in place.
+///
+/// Outputs `[lo, 2lo)` only read `[2lo, 4lo)`, so processing these blocks by increasing `lo`
+/// never overwrites an entry that is still to be read, and each block can be split across
+/// threads.
+fn fold_in_place<T: Copy + Send + Sync>(
+    buf: &mut [T],
+    n: usize,
+    f: impl Fn(usize, T, T) -> T + Sync,
+) {
+    buf[0] = f(0, buf[0], buf[1]);
+    let mut lo = 1;
+    while lo < n {
+        let hi = (2 * lo).min(n);
+        let (dst, src) = buf.split_at_mut(2 * lo);
+        dst[lo..hi]
+            .par_iter_mut()
+            .with_min_len(PAR_MIN_LEN)
+            .enumerate()
+            .for_each(|(k, v)| *v = f(lo + k, src[2 * k], src[2 * k + 1]));
+        lo = hi;
     }
 }
 
+/// Apply `update(g, &mut hg_vals[index(g)])` for every gate and flag the touched entries in
+/// `gate_exists`. With more than one thread, each thread scatters a chunk of the gates into its
+/// own accumulator and the accumulators are summed afterwards.
+fn scatter_gates<F: Field, G: Sync>(
+    gates: &[G],
+    hg_vals: &mut [F],
+    gate_exists: &mut [bool],
+    index: impl Fn(&G) -> usize + Sync,
+    update: impl Fn(&G, &mut F) + Sync,
+) {
+    let thread_num = rayon::current_num_threads();
+    if thread_num == 1 || gates.len() < PAR_MIN_LEN {
+        for g in gates {
+            let i = index(g);
+            update(g, &mut hg_vals[i]);
+            gate_exists[i] = true;
+        }
+        return;
+    }
+
+    let size = hg_vals.len();
+    let partials: Vec<(Vec<F>, Vec<bool>)> = gates
+        .par_chunks(gates.len().div_ceil(thread_num))
+        .map(|chunk| {
+            let mut hg = vec![F::zero(); size];
+            let mut exists = vec![false; size];
+            for g in chunk {
+                let i = index(g);
+                update(g, &mut hg[i]);
+                exists[i] = true;
+            }
+            (hg, exists)
+        })
+        .collect();
+    hg_vals
+        .par_iter_mut()
+        .zip(gate_exists.par_iter_mut())
+        .with_min_len(PAR_MIN_LEN)
+        .enumerate()
+        .for_each(|(i, (hg, exists))| {
+            for (partial_hg, partial_exists) in &partials {
+                *hg += partial_hg[i];
+                *exists |= partial_exists[i];
+            }
+        });
+}
+
 struct SumcheckMultilinearProdHelper {
     var_num: usize,
     sumcheck_var_idx: usize,
@@ -70,93 +151,117 @@ impl SumcheckMultilinearProdHelper {
         &self,
         var_idx: usize,
         degree: usize,
-        bk_f: &mut [F],
-        bk_hg: &mut [F],
+        bk_f: &mut [F::ChallengeField],
+        bk_hg: &mut [F::ChallengeField],
         init_v: &[F],
         gate_exists: &[bool],
-    ) -> [F; 3] {
+    ) -> [F::ChallengeField; 3] {
         assert_eq!(degree, 2);
-        let mut p0 = F::zero();
-        let mut p1 = F::zero();
-        let mut p2 = F::zero();
         log::trace!("bk_f: {:?}", &bk_f[..4]);
         log::trace!("bk_hg: {:?}", &bk_hg[..4]);
         log::trace!("init_v: {:?}", &init_v[..4]);
-        let src_v = if var_idx == 0 { init_v } else { bk_f };
         let eval_size = 1 << (self.var_num - var_idx - 1);
         log::trace!("Eval size: {}", eval_size);
-        for i in 0..eval_size {
-            if !gate_exists[i * 2] && !gate_exists[i * 2 + 1] {
-                continue;
-            }
-            // for j in 0..F::VECTORIZE_SIZE {
-            let f_v_0 = src_v[i * 2];
-            let f_v_1 = src_v[i * 2 + 1];
-            let hg_v_0 = bk_hg[i * 2];
-            let hg_v_1 = bk_hg[i * 2 + 1];
-            p0 += f_v_0 * hg_v_0;
-            log::trace!(
-                "p0.v += {:?} * {:?} =  {:?}",
-                f_v_0,
-                hg_v_0,
-                f_v_0 * hg_v_0 + p1
-            );
-            p1 += f_v_1 * hg_v_1;
-            p2 += (f_v_0 + f_v_1) * (hg_v_0 + hg_v_1);
-            // }
-        }
-        p2 = p1 * F::from(6) + p0 * F::from(3) - p2 * F::from(2);
+        let [p0, p1, p2] = if var_idx == 0 {
+            products_at_0_1_2(init_v, bk_hg, gate_exists, eval_size)
+        } else {
+            products_at_0_1_2(bk_f, bk_hg, gate_exists, eval_size)
+        };
+        let p2 = p1 * F::ChallengeField::from(6u32) + p0 * F::ChallengeField::from(3u32)
+            - p2 * F::ChallengeField::from(2u32);
         [p0, p1, p2]
     }
 
     fn receive_challenge<F: Field>(
         &mut self,
         var_idx: usize,
-        r: F::BaseField,
-        bk_f: &mut [F],
-        bk_hg: &mut [F],
+        r: F::ChallengeField,
+        bk_f: &mut [F::ChallengeField],
+        bk_hg: &mut [F::ChallengeField],
         init_v: &[F],
         gate_exists: &mut [bool],
     ) {
         assert_eq!(var_idx, self.sumcheck_var_idx);
         assert!(var_idx < self.var_num);
         log::trace!("challenge eval size: {}", self.cur_eval_size);
-        for i in 0..self.cur_eval_size >> 1 {
-            if !gate_exists[i * 2] && !gate_exists[i * 2 + 1] {
-                gate_exists[i] = false;
-                if var_idx == 0 {
-                    bk_f[i] = init_v[2 * i] + (init_v[2 * i + 1] - init_v[2 * i]).mul_base_elem(&r);
-                } else {
-                    bk_f[i] = bk_f[2 * i] + (bk_f[2 * i + 1] - bk_f[2 * i]).mul_base_elem(&r);
-                }
-                bk_hg[i] = F::zero();
+        let half = self.cur_eval_size >> 1;
+        if var_idx == 0 {
+            bk_f[..half]
+                .par_iter_mut()
+                .with_min_len(PAR_MIN_LEN)
+                .enumerate()
+                .for_each(|(i, f)| {
+                    let f_0 = F::ChallengeField::from(init_v[2 * i]);
+                    let f_1 = F::ChallengeField::from(init_v[2 * i + 1]);
+                    *f = f_0 + (f_1 - f_0) * r;
+                });
+        } else {
+            fold_in_place(bk_f, half, |_, f_0, f_1| f_0 + (f_1 - f_0) * r);
+        }
+        // hg is zero wherever no gate exists, so it only needs folding next to existing gates
+        let exists = &*gate_exists;
+        fold_in_place(bk_hg, half, |i, hg_0, hg_1| {
+            if exists[2 * i] || exists[2 * i + 1] {
+                hg_0 + (hg_1 - hg_0) * r
             } else {
-                gate_exists[i] = true;
-                if var_idx == 0 {
-                    bk_f[i] = init_v[2 * i] + (init_v[2 * i + 1] - init_v[2 * i]).mul_base_elem(&r);
-                } else {
-                    bk_f[i] = bk_f[2 * i] + (bk_f[2 * i + 1] - bk_f[2 * i]).mul_base_elem(&r);
-                }
-                bk_hg[i] = bk_hg[2 * i] + (bk_hg[2 * i + 1] - bk_hg[2 * i]).mul_base_elem(&r);
+                F::ChallengeField::zero()
             }
-        }
+        });
+        fold_in_place(gate_exists, half, |_, e_0, e_1| e_0 || e_1);
 
         self.cur_eval_size >>= 1;
         self.sumcheck_var_idx += 1;
     }
 }
 
+/// Sums of `f * hg` over the pairs of entries next to an existing gate, with the variable set
+/// to 0, 1, and 2 for the last one (before interpolation). `f` is either the input layer
+/// values or the folded table, which is why it is lifted here.
+fn products_at_0_1_2<V, C>(
+    bk_f: &[V],
+    bk_hg: &[C],
+    gate_exists: &[bool],
+    eval_size: usize,
+) -> [C; 3]
+where
+    V: Copy + Sync,
+    C: Field + From<V>,
+{
+    let (p0, p1, p2) = (0..eval_size)
+        .into_par_iter()
+        .with_min_len(PAR_MIN_LEN)
+        .filter(|&i| gate_exists[i * 2] || gate_exists[i * 2 + 1])
+        .map(|i| {
+            let f_v_0 = C::from(bk_f[i * 2]);
+            let f_v_1 = C::from(bk_f[i * 2 + 1]);
+            let hg_v_0 = bk_hg[i * 2];
+            let hg_v_1 = bk_hg[i * 2 + 1];
+            (
+                f_v_0 * hg_v_0,
+                f_v_1 * hg_v_1,
+                (f_v_0 + f_v_1) * (hg_v_0 + hg_v_1),
+            )
+        })
+        .reduce(
+            || (C::zero(), C::zero(), C::zero()),
+            |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2),
+        );
+    [p0, p1, p2]
+}
+
 #[allow(dead_code)]
 pub(crate) struct SumcheckGkrHelper<'a, F: Field> {
-    pub(crate) rx: Vec<F::BaseField>,
-    pub(crate) ry: Vec<F::BaseField>,
+    pub(crate) rx: Vec<F::ChallengeField>,
+    pub(crate) ry: Vec<F::ChallengeField>,
 
     layer: &'a CircuitLayer<F>,
+    /// values of the layer input, which may not be stored in `layer`
+    input_vals: &'a [F],
     sp: &'a mut GkrScratchpad<F>,
-    rz0: &'a [F::BaseField],
-    rz1: &'a [F::BaseField],
-    alpha: F::BaseField,
-    beta: F::BaseField,
+    rz0: &'a [F::ChallengeField],
+    rz1: &'a [F::ChallengeField],
+    alpha: F::ChallengeField,
+    beta: F::ChallengeField,
 
     input_var_num: usize,
     output_var_num: usize,
@@ -171,10 +276,11 @@ where
 {
     pub fn new(
         layer: &'a CircuitLayer<F>,
-        rz0: &'a [F::BaseField],
-        rz1: &'a [F::BaseField],
-        alpha: &'a F::BaseField,
-        beta: &'a F::BaseField,
+        input_vals: &'a [F],
+        rz0: &'a [F::ChallengeField],
+        rz1: &'a [F::ChallengeField],
+        alpha: &'a F::ChallengeField,
+        beta: &'a F::ChallengeField,
         sp: &'a mut GkrScratchpad<F>,
     ) -> Self {
         SumcheckGkrHelper {
@@ -182,6 +288,7 @@ where
             ry: vec![],
 
             layer,
+            input_vals,
             sp,
             rz0,
             rz1,
@@ -196,14 +303,14 @@ where
         }
     }
 
-    pub fn poly_evals_at(&mut self, var_idx: usize, degree: usize) -> [F; 3] {
+    pub fn poly_evals_at(&mut self, var_idx: usize, degree: usize) -> [F::ChallengeField; 3] {
         if var_idx < self.input_var_num {
             self.x_helper.poly_eval_at(
                 var_idx,
                 degree,
                 &mut self.sp.v_evals,
                 &mut self.sp.hg_evals,
-                &self.layer.input_vals.evals,
+                self.input_vals,
                 &self.sp.gate_exists,
             )
         } else {
@@ -212,13 +319,13 @@ where
                 degree,
                 &mut self.sp.v_evals,
                 &mut self.sp.hg_evals,
-                &self.layer.input_vals.evals,
+                self.input_vals,
                 &self.sp.gate_exists,
             )
         }
     }
 
-    pub fn receive_challenge(&mut self, var_idx: usize, r: F::BaseField)
+    pub fn receive_challenge(&mut self, var_idx: usize, r: F::ChallengeField)
     where
         F: Field,
     {
@@ -228,7 +335,7 @@ where
                 r,
                 &mut self.sp.v_evals,
                 &mut self.sp.hg_evals,
-                &self.layer.input_vals.evals,
+                self.input_vals,
                 &mut self.sp.gate_exists,
             );
             log::trace!("v_eval[0]:= {:?}", self.sp.v_evals[0]);
@@ -239,36 +346,56 @@ where
                 r,
                 &mut self.sp.v_evals,
                 &mut self.sp.hg_evals,
-                &self.layer.input_vals.evals,
+                self.input_vals,
                 &mut self.sp.gate_exists,
             );
             self.ry.push(r);
         }
     }
 
-    pub fn vx_claim(&self) -> F {
+    pub fn vx_claim(&self) -> F::ChallengeField {
         self.sp.v_evals[0]
     }
 
-    pub fn vy_claim(&self) -> F {
+    pub fn vy_claim(&self) -> F::ChallengeField {
         self.sp.v_evals[0]
     }
 
+    /// Sums of the entries of the `hg` table with the variable `var_idx` set to 0 and 1, which
+    /// the masks of the zero-knowledge mode multiply.
+    pub fn hg_sums_at(&self, var_idx: usize) -> [F::ChallengeField; 2] {
+        let eval_size = 1 << (self.input_var_num - var_idx % self.input_var_num - 1);
+        let (h0, h1) = self.sp.hg_evals[..2 * eval_size]
+            .par_chunks(2)
+            .with_min_len(PAR_MIN_LEN)
+            .map(|hg| (hg[0], hg[1]))
+            .reduce(
+                || (F::ChallengeField::zero(), F::ChallengeField::zero()),
+                |a, b| (a.0 + b.0, a.1 + b.1),
+            );
+        [h0, h1]
+    }
+
+    /// `hg` at `ry` once all the variables are bound, `vx * mul(rx, ry)`.
+    pub fn hg_claim(&self) -> F::ChallengeField {
+        self.sp.hg_evals[0]
+    }
+
     pub fn prepare_g_x_vals(&mut self) {
         let mul = &self.layer.mul;
         let add = &self.layer.add;
-        let vals = &self.layer.input_vals;
+        let vals = self.input_vals;
         let eq_evals_at_rz0 = &mut self.sp.eq_evals_at_rz0;
         let eq_evals_at_rz1 = &mut self.sp.eq_evals_at_rz1;
         let gate_exists = &mut self.sp.gate_exists;
         let hg_vals = &mut self.sp.hg_evals;
-        // hg_vals[0..vals.evals.len()].fill(F::zero()); // FIXED: consider memset unsafe?
+        // hg_vals[0..vals.len()].fill(F::zero()); // FIXED: consider memset unsafe?
         unsafe {
-            std::ptr::write_bytes(hg_vals.as_mut_ptr(), 0, vals.evals.len());
+            std::ptr::write_bytes(hg_vals.as_mut_ptr(), 0, vals.len());
         }
-        // gate_exists[0..vals.evals.len()].fill(false); // FIXED: consider memset unsafe?
+        // gate_exists[0..vals.len()].fill(false); // FIXED: consider memset unsafe?
         unsafe {
-            std::ptr::write_bytes(gate_exists.as_mut_ptr(), 0, vals.evals.len());
+            std::ptr::write_bytes(gate_exists.as_mut_ptr(), 0, vals.len());
         }
         eq_eval_at(
             self.rz0,
@@ -284,22 +411,34 @@ where
             &mut self.sp.eq_evals_first_half,
             &mut self.sp.eq_evals_second_half,
         );
-        for i in 0..1 << self.rz0.len() {
-            eq_evals_at_rz0[i] += eq_evals_at_rz1[i];
-        }
+        eq_evals_at_rz0[..1 << self.rz0.len()]
+            .par_iter_mut()
+            .zip(eq_evals_at_rz1.par_iter())
+            .with_min_len(PAR_MIN_LEN)
+            .for_each(|(eq_0, eq_1)| *eq_0 += eq_1);
 
-        for g in mul.iter() {
-            hg_vals[g.i_ids[0]] +=
-                vals.evals[g.i_ids[1]].mul_base_elem(&(g.coef * eq_evals_at_rz0[g.o_id]));
-            gate_exists[g.i_ids[0]] = true;
-        }
-        for g in add.iter() {
-            hg_vals[g.i_ids[0]].add_assign_base_elem(&(g.coef * eq_evals_at_rz0[g.o_id]));
-            gate_exists[g.i_ids[0]] = true;
-        }
+        let eq_evals_at_rz0 = &*eq_evals_at_rz0;
+        let input_num = vals.len();
+        scatter_gates(
+            mul,
+            &mut hg_vals[..input_num],
+            &mut gate_exists[..input_num],
+            |g| g.i_ids[0],
+            |g, hg| {
+                *hg += eq_evals_at_rz0[g.o_id].mul_base_elem(&g.coef)
+                    * F::ChallengeField::from(vals[g.i_ids[1]])
+            },
+        );
+        scatter_gates(
+            add,
+            &mut hg_vals[..input_num],
+            &mut gate_exists[..input_num],
+            |g| g.i_ids[0],
+            |g, hg| *hg += eq_evals_at_rz0[g.o_id].mul_base_elem(&g.coef),
+        );
     }
 
-    pub fn prepare_h_y_vals(&mut self, v_rx: F) {
+    pub fn prepare_h_y_vals(&mut self, v_rx: F::ChallengeField) {
         let mul = &self.layer.mul;
         let eq_evals_at_rz0 = &mut self.sp.eq_evals_at_rz0;
         let eq_evals_at_rx = &mut self.sp.eq_evals_at_rx;
@@ -317,16 +456,22 @@ where
 
         eq_eval_at(
             &self.rx,
-            &F::BaseField::one(),
+            &F::ChallengeField::one(),
             eq_evals_at_rx,
             &mut self.sp.eq_evals_first_half,
             &mut self.sp.eq_evals_second_half,
         );
 
-        for g in mul.iter() {
-            hg_vals[g.i_ids[1]] += v_rx
-                .mul_base_elem(&(eq_evals_at_rz0[g.o_id] * eq_evals_at_rx[g.i_ids[0]] * g.coef));
-            gate_exists[g.i_ids[1]] = true;
-        }
+        let (eq_evals_at_rz0, eq_evals_at_rx) = (&*eq_evals_at_rz0, &*eq_evals_at_rx);
+        scatter_gates(
+            mul,
+            &mut hg_vals[..fill_len],
+            &mut gate_exists[..fill_len],
+            |g| g.i_ids[1],
+            |g, hg| {
+                *hg += v_rx
+                    * (eq_evals_at_rz0[g.o_id] * eq_evals_at_rx[g.i_ids[0]]).mul_base_elem(&g.coef)
+            },
+        );
     }
 }
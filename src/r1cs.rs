@@ -0,0 +1,342 @@
+//! Import of circom R1CS constraint systems (`.r1cs`) and witnesses (`.wtns`) over BN254.
+//!
+//! Every constraint `<A, w> * <B, w> = <C, w>` is compiled into a two-layer circuit: the first
+//! layer computes the three linear combinations with add gates, the second one outputs
+//! `<A, w> * <B, w> - <C, w>`. One more output is `w_0 - 1`, as wire 0 must hold the
+//! constant one. The witness is the circuit input, so the outputs are all zero iff the witness
+//! satisfies every constraint.
+
+use std::{fmt, fs, io};
+
+use arith::{Field, FieldSerde, MultiLinearPoly};
+use halo2curves::bn256::Fr;
+
+use crate::{Circuit, CircuitLayer, GateAdd, GateConst, GateMul, SENTINEL_BN254};
+
+const R1CS_MAGIC: &[u8; 4] = b"r1cs";
+const WTNS_MAGIC: &[u8; 4] = b"wtns";
+
+const R1CS_HEADER_SECTION: u32 = 1;
+const R1CS_CONSTRAINT_SECTION: u32 = 2;
+const WTNS_HEADER_SECTION: u32 = 1;
+const WTNS_DATA_SECTION: u32 = 2;
+
+/// Errors of the `.r1cs` and `.wtns` readers.
+#[derive(Debug)]
+pub enum R1csError {
+    Io(io::Error),
+    /// the file does not start with the magic of its format
+    BadMagic,
+    /// the file or one of its sections ends before all of it is read
+    Truncated,
+    /// bytes left in the file or in a section after all of it is read
+    TrailingBytes,
+    MissingSection(u32),
+    /// the file is not over the BN254 scalar field
+    UnsupportedField,
+    /// bytes that do not encode a field element
+    InvalidFieldElement,
+    /// a constraint refers to a wire beyond `num_wires`
+    WireOutOfRange(usize),
+}
+
+impl fmt::Display for R1csError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            R1csError::Io(err) => write!(f, "{}", err),
+            R1csError::BadMagic => write!(f, "bad magic"),
+            R1csError::Truncated => write!(f, "truncated file"),
+            R1csError::TrailingBytes => write!(f, "trailing bytes"),
+            R1csError::MissingSection(section) => write!(f, "missing section {}", section),
+            R1csError::UnsupportedField => write!(f, "only BN254 is supported"),
+            R1csError::InvalidFieldElement => write!(f, "invalid field element"),
+            R1csError::WireOutOfRange(wire_id) => write!(f, "wire {} out of range", wire_id),
+        }
+    }
+}
+
+impl std::error::Error for R1csError {}
+
+impl From<io::Error> for R1csError {
+    fn from(err: io::Error) -> Self {
+        R1csError::Io(err)
+    }
+}
+
+/// A linear combination of wires, as `(wire_id, coefficient)` pairs.
+pub type LinearCombination = Vec<(usize, Fr)>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct R1csConstraint {
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination,
+}
+
+/// Wire 0 is the constant 1, followed by the public outputs, public inputs and private inputs.
+#[derive(Debug, Clone, Default)]
+pub struct R1cs {
+    pub num_wires: usize,
+    pub num_pub_out: usize,
+    pub num_pub_in: usize,
+    pub num_prv_in: usize,
+    pub constraints: Vec<R1csConstraint>,
+}
+
+fn read_bytes<'a>(bytes: &'a [u8], cur: &mut usize, len: usize) -> Result<&'a [u8], R1csError> {
+    let end = cur.checked_add(len).ok_or(R1csError::Truncated)?;
+    let ret = bytes.get(*cur..end).ok_or(R1csError::Truncated)?;
+    *cur = end;
+    Ok(ret)
+}
+
+fn read_u32(bytes: &[u8], cur: &mut usize) -> Result<u32, R1csError> {
+    Ok(u32::from_le_bytes(
+        read_bytes(bytes, cur, 4)?.try_into().unwrap(),
+    ))
+}
+
+fn read_u64(bytes: &[u8], cur: &mut usize) -> Result<u64, R1csError> {
+    Ok(u64::from_le_bytes(
+        read_bytes(bytes, cur, 8)?.try_into().unwrap(),
+    ))
+}
+
+fn read_fr(bytes: &[u8], cur: &mut usize) -> Result<Fr, R1csError> {
+    Fr::try_deserialize_from(read_bytes(bytes, cur, Fr::SIZE)?)
+        .ok_or(R1csError::InvalidFieldElement)
+}
+
+/// Split an iden3 binary file into its `(section_type, content)` pairs.
+fn read_sections<'a>(bytes: &'a [u8], magic: &[u8; 4]) -> Result<Vec<(u32, &'a [u8])>, R1csError> {
+    let mut cur = 0;
+    if read_bytes(bytes, &mut cur, 4)? != magic {
+        return Err(R1csError::BadMagic);
+    }
+    let _version = read_u32(bytes, &mut cur)?;
+    let section_num = read_u32(bytes, &mut cur)?;
+    let mut sections = vec![];
+    for _ in 0..section_num {
+        let section_type = read_u32(bytes, &mut cur)?;
+        let section_size =
+            usize::try_from(read_u64(bytes, &mut cur)?).map_err(|_| R1csError::Truncated)?;
+        sections.push((section_type, read_bytes(bytes, &mut cur, section_size)?));
+    }
+    if cur != bytes.len() {
+        return Err(R1csError::TrailingBytes);
+    }
+    Ok(sections)
+}
+
+fn find_section<'a>(
+    sections: &[(u32, &'a [u8])],
+    section_type: u32,
+) -> Result<&'a [u8], R1csError> {
+    sections
+        .iter()
+        .find(|(t, _)| *t == section_type)
+        .map(|(_, content)| *content)
+        .ok_or(R1csError::MissingSection(section_type))
+}
+
+/// Check the field element size and prime of a header.
+fn read_prime(bytes: &[u8], cur: &mut usize) -> Result<(), R1csError> {
+    let field_size = read_u32(bytes, cur)? as usize;
+    if field_size != Fr::SIZE || read_bytes(bytes, cur, field_size)? != SENTINEL_BN254 {
+        return Err(R1csError::UnsupportedField);
+    }
+    Ok(())
+}
+
+fn read_linear_combination(
+    bytes: &[u8],
+    cur: &mut usize,
+    num_wires: usize,
+) -> Result<LinearCombination, R1csError> {
+    let term_num = read_u32(bytes, cur)?;
+    (0..term_num)
+        .map(|_| {
+            let wire_id = read_u32(bytes, cur)? as usize;
+            if wire_id >= num_wires {
+                return Err(R1csError::WireOutOfRange(wire_id));
+            }
+            Ok((wire_id, read_fr(bytes, cur)?))
+        })
+        .collect()
+}
+
+fn eval_linear_combination(lc: &LinearCombination, witness: &[Fr]) -> Fr {
+    lc.iter().fold(Fr::zero(), |acc, (wire_id, coef)| {
+        acc + witness[*wire_id] * coef
+    })
+}
+
+impl R1cs {
+    pub fn load(filename: &str) -> Result<Self, R1csError> {
+        let file_bytes = fs::read(filename)?;
+        Self::read(&file_bytes)
+    }
+
+    pub fn read(file_bytes: &[u8]) -> Result<Self, R1csError> {
+        let sections = read_sections(file_bytes, R1CS_MAGIC)?;
+
+        let header = find_section(&sections, R1CS_HEADER_SECTION)?;
+        let mut cur = 0;
+        read_prime(header, &mut cur)?;
+        let num_wires = read_u32(header, &mut cur)? as usize;
+        let num_pub_out = read_u32(header, &mut cur)? as usize;
+        let num_pub_in = read_u32(header, &mut cur)? as usize;
+        let num_prv_in = read_u32(header, &mut cur)? as usize;
+        let _num_labels = read_u64(header, &mut cur)?;
+        let num_constraints = read_u32(header, &mut cur)? as usize;
+
+        let body = find_section(&sections, R1CS_CONSTRAINT_SECTION)?;
+        let mut cur = 0;
+        let constraints = (0..num_constraints)
+            .map(|_| {
+                Ok(R1csConstraint {
+                    a: read_linear_combination(body, &mut cur, num_wires)?,
+                    b: read_linear_combination(body, &mut cur, num_wires)?,
+                    c: read_linear_combination(body, &mut cur, num_wires)?,
+                })
+            })
+            .collect::<Result<_, R1csError>>()?;
+        if cur != body.len() {
+            return Err(R1csError::TrailingBytes);
+        }
+
+        Ok(R1cs {
+            num_wires,
+            num_pub_out,
+            num_pub_in,
+            num_prv_in,
+            constraints,
+        })
+    }
+
+    pub fn load_witness(filename: &str) -> Result<Vec<Fr>, R1csError> {
+        let file_bytes = fs::read(filename)?;
+        Self::read_witness(&file_bytes)
+    }
+
+    pub fn read_witness(file_bytes: &[u8]) -> Result<Vec<Fr>, R1csError> {
+        let sections = read_sections(file_bytes, WTNS_MAGIC)?;
+
+        let header = find_section(&sections, WTNS_HEADER_SECTION)?;
+        let mut cur = 0;
+        read_prime(header, &mut cur)?;
+        let witness_num = read_u32(header, &mut cur)? as usize;
+
+        let data = find_section(&sections, WTNS_DATA_SECTION)?;
+        let mut cur = 0;
+        let witness = (0..witness_num)
+            .map(|_| read_fr(data, &mut cur))
+            .collect::<Result<_, _>>()?;
+        if cur != data.len() {
+            return Err(R1csError::TrailingBytes);
+        }
+        Ok(witness)
+    }
+
+    pub fn is_satisfied(&self, witness: &[Fr]) -> bool {
+        assert_eq!(witness.len(), self.num_wires);
+        self.constraints.iter().all(|constraint| {
+            eval_linear_combination(&constraint.a, witness)
+                * eval_linear_combination(&constraint.b, witness)
+                == eval_linear_combination(&constraint.c, witness)
+        })
+    }
+
+    /// Layer 0 maps the witness to `[A w | B w | C w | w_0 - 1, 0..]`, each block padded to a
+    /// power of two; layer 1 outputs `A w * B w - C w`, one wire per constraint, followed by
+    /// `w_0 - 1`.
+    pub fn to_circuit(&self) -> Circuit<Fr> {
+        let input_var_num = self.num_wires.next_power_of_two().trailing_zeros().max(1) as usize;
+        let block_size = (self.constraints.len() + 1).next_power_of_two().max(2);
+        let block_var_num = block_size.trailing_zeros() as usize;
+        // the wire of layer 0 holding w_0 - 1, and the output it is relayed to
+        let one_wire = 3 * block_size;
+        let one_output = self.constraints.len();
+
+        let mut linear = CircuitLayer::<Fr> {
+            input_var_num,
+            output_var_num: block_var_num + 2,
+            input_vals: MultiLinearPoly {
+                var_num: input_var_num,
+                evals: vec![],
+            },
+            ..Default::default()
+        };
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            for (block, lc) in [&constraint.a, &constraint.b, &constraint.c]
+                .into_iter()
+                .enumerate()
+            {
+                linear.add.extend(lc.iter().map(|(wire_id, coef)| GateAdd {
+                    i_ids: [*wire_id],
+                    o_id: block * block_size + i,
+                    coef: *coef,
+                }));
+            }
+        }
+        linear.add.push(GateAdd {
+            i_ids: [0],
+            o_id: one_wire,
+            coef: Fr::one(),
+        });
+        linear.const_.push(GateConst {
+            i_ids: [],
+            o_id: one_wire,
+            coef: -Fr::one(),
+        });
+
+        let mut product = CircuitLayer::<Fr> {
+            input_var_num: block_var_num + 2,
+            output_var_num: block_var_num,
+            input_vals: MultiLinearPoly {
+                var_num: block_var_num + 2,
+                evals: vec![],
+            },
+            ..Default::default()
+        };
+        for i in 0..self.constraints.len() {
+            product.mul.push(GateMul {
+                i_ids: [i, block_size + i],
+                o_id: i,
+                coef: Fr::one(),
+            });
+            product.add.push(GateAdd {
+                i_ids: [2 * block_size + i],
+                o_id: i,
+                coef: -Fr::one(),
+            });
+        }
+        product.add.push(GateAdd {
+            i_ids: [one_wire],
+            o_id: one_output,
+            coef: Fr::one(),
+        });
+
+        Circuit {
+            layers: vec![linear, product],
+            lookups: vec![],
+        }
+    }
+}
+
+impl Circuit<Fr> {
+    /// Load a `.wtns` witness of the R1CS this circuit was compiled from.
+    pub fn load_wtns_file(&mut self, filename: &str) -> Result<(), R1csError> {
+        let witness = R1cs::load_witness(filename)?;
+        self.set_r1cs_witness(&witness);
+        Ok(())
+    }
+
+    pub fn set_r1cs_witness(&mut self, witness: &[Fr]) {
+        let input_num = 1 << self.log_input_size();
+        assert!(witness.len() <= input_num);
+        let mut evals = witness.to_vec();
+        evals.resize(input_num, Fr::zero());
+        self.layers[0].input_vals.evals = evals;
+    }
+}
@@ -0,0 +1,911 @@
+//! The verifier compiled to a layered circuit over BN254, for recursive proof composition.
+//!
+//! `VerifierCircuit::new` emits a circuit checking the proofs for a target circuit. Its input
+//! layer holds the claimed outputs, the field elements of the proof and, as hints, the
+//! Fiat-Shamir challenges and the bits of the proof-of-work hash and of the query indices; its
+//! outputs are the checks of the grinding, of `gkr_verify`, of the merge of the evaluation
+//! claims and of the opening of the commitment, each of them zero iff it passes. Every
+//! challenge is checked against the MiMC7 hash of the transcript computed in the circuit, so
+//! the proofs must be made with `FiatShamirHashType::MIMC7`. `VerifierCircuit::witness` fills
+//! the input layer from a proof.
+//!
+//! The commitment is either raw, evaluated at the merged point in the circuit, or a
+//! `crate::mimc_deepfold` one, see `RecursiveCommitment`: the circuit checks the evaluations
+//! and the final value of the fold phase, then for each query the MiMC7 Merkle paths of its
+//! leaves and the consistency of their folds from one round to the next. The `crate::deepfold`
+//! proofs, whose Merkle trees are hashed with Blake3 and whose query phase depends on the
+//! indices, are read as raw ones and fail.
+//!
+//! The circuit covers a reduced set of configurations, `VerifierCircuit::with_commitment`
+//! rejects the others with `VerificationError::InvalidStatement`:
+//! - no lookups: the logUp sumcheck is not emitted;
+//! - no zero-knowledge proofs, whose masks are committed with a hiding DeepFold.
+
+use arith::{Field, FieldSerde, MultiLinearPoly};
+use halo2curves::bn256::Fr;
+
+use crate::{
+    bytes_to_fr_chunks, deepfold::DeepFoldParam, mimc7_round_constants, mimc_deepfold, Circuit,
+    CircuitLayer, Config, FiatShamirHashType, FieldType, GateAdd, GateConst, GateMul,
+    PolynomialCommitmentType, Proof, VerificationError, GRINDING_NONCE_BYTES, MIMC7_ROUNDS,
+};
+
+/// What the verification computes on: field elements when filling the witness, wires when
+/// building the circuit.
+trait Backend {
+    type V: Copy;
+
+    /// The claimed output of the repetition `j`.
+    fn claimed_output(&mut self, j: usize) -> Result<Self::V, VerificationError>;
+
+    /// The next field element of the proof, or the nonce of the proof of work if `nonce`.
+    fn read(&mut self, nonce: bool) -> Result<Self::V, VerificationError>;
+
+    /// An input holding `value`, so that it is available from the input layer on.
+    fn hint(&mut self, value: Self::V) -> Self::V;
+
+    /// Inputs holding the bits of the canonical encoding of `value`, from the least
+    /// significant one.
+    fn bits(&mut self, value: Self::V) -> Vec<Self::V>;
+
+    /// An input holding `a / b`, or zero if `b` is zero.
+    fn quotient(&mut self, a: Self::V, b: Self::V) -> Self::V;
+
+    /// `sum c * a` over `add` plus `sum c * a * b` over `mul` plus `constant`.
+    fn gate(
+        &mut self,
+        add: &[(Self::V, Fr)],
+        mul: &[(Self::V, Self::V, Fr)],
+        constant: Fr,
+    ) -> Self::V;
+
+    fn assert_zero(&mut self, v: Self::V);
+}
+
+/// The wire `id` of the circuit under construction is either an input, or a gate reading the
+/// wires of the layer below its own.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    layer: usize,
+    add: Vec<(usize, Fr)>,
+    mul: Vec<(usize, usize, Fr)>,
+    constant: Fr,
+}
+
+impl Node {
+    fn operands(&self) -> impl Iterator<Item = usize> + '_ {
+        self.add
+            .iter()
+            .map(|&(a, _)| a)
+            .chain(self.mul.iter().flat_map(|&(a, b, _)| [a, b]))
+    }
+}
+
+/// Places every node one layer above its deepest operand, the others being relayed by add
+/// gates up to the layer below.
+#[derive(Debug, Default)]
+struct CircuitBuilder {
+    nodes: Vec<Node>,
+    outputs: Vec<usize>,
+}
+
+fn layer_var_num(size: usize) -> usize {
+    size.next_power_of_two().trailing_zeros().max(1) as usize
+}
+
+impl CircuitBuilder {
+    fn input(&mut self) -> usize {
+        self.nodes.push(Node::default());
+        self.nodes.len() - 1
+    }
+
+    fn build(&self) -> Circuit<Fr> {
+        let depth = self
+            .outputs
+            .iter()
+            .map(|&o| self.nodes[o].layer)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        // the last layer below the outputs each node is read from
+        let mut last: Vec<_> = self.nodes.iter().map(|node| node.layer).collect();
+        for node in &self.nodes {
+            for operand in node.operands() {
+                last[operand] = last[operand].max(node.layer - 1);
+            }
+        }
+        for &o in &self.outputs {
+            last[o] = depth - 1;
+        }
+
+        // ids[k][l - layer] is the index of the node k in the layer l; only the outputs reach
+        // the last layer, in order
+        let mut sizes = vec![0; depth + 1];
+        let mut ids: Vec<Vec<usize>> = self
+            .nodes
+            .iter()
+            .zip(&last)
+            .map(|(node, &last)| {
+                (node.layer..=last.min(depth - 1))
+                    .map(|l| {
+                        sizes[l] += 1;
+                        sizes[l] - 1
+                    })
+                    .collect()
+            })
+            .collect();
+        for &o in &self.outputs {
+            if self.nodes[o].layer + ids[o].len() == depth {
+                ids[o].push(sizes[depth]);
+                sizes[depth] += 1;
+            }
+        }
+
+        let mut layers: Vec<_> = (0..depth)
+            .map(|l| {
+                let input_var_num = layer_var_num(sizes[l]);
+                CircuitLayer::<Fr> {
+                    input_var_num,
+                    output_var_num: layer_var_num(sizes[l + 1]),
+                    input_vals: MultiLinearPoly {
+                        var_num: input_var_num,
+                        evals: vec![],
+                    },
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let id_at = |k: usize, l: usize| ids[k][l - self.nodes[k].layer];
+        for (k, node) in self.nodes.iter().enumerate() {
+            for (l, &o_id) in (node.layer..).zip(&ids[k]) {
+                if l == 0 {
+                    continue;
+                }
+                let layer = &mut layers[l - 1];
+                if l > node.layer {
+                    layer.add.push(GateAdd {
+                        i_ids: [id_at(k, l - 1)],
+                        o_id,
+                        coef: Fr::one(),
+                    });
+                    continue;
+                }
+                layer.add.extend(node.add.iter().map(|&(a, coef)| GateAdd {
+                    i_ids: [id_at(a, l - 1)],
+                    o_id,
+                    coef,
+                }));
+                layer
+                    .mul
+                    .extend(node.mul.iter().map(|&(a, b, coef)| GateMul {
+                        i_ids: [id_at(a, l - 1), id_at(b, l - 1)],
+                        o_id,
+                        coef,
+                    }));
+                if node.constant != Fr::zero() {
+                    layer.const_.push(GateConst {
+                        i_ids: [],
+                        o_id,
+                        coef: node.constant,
+                    });
+                }
+            }
+        }
+        Circuit {
+            layers,
+            lookups: vec![],
+        }
+    }
+}
+
+impl Backend for CircuitBuilder {
+    type V = usize;
+
+    fn claimed_output(&mut self, _j: usize) -> Result<usize, VerificationError> {
+        Ok(self.input())
+    }
+
+    fn read(&mut self, _nonce: bool) -> Result<usize, VerificationError> {
+        Ok(self.input())
+    }
+
+    fn hint(&mut self, _value: usize) -> usize {
+        self.input()
+    }
+
+    fn bits(&mut self, _value: usize) -> Vec<usize> {
+        (0..256).map(|_| self.input()).collect()
+    }
+
+    fn quotient(&mut self, _a: usize, _b: usize) -> usize {
+        self.input()
+    }
+
+    fn gate(&mut self, add: &[(usize, Fr)], mul: &[(usize, usize, Fr)], constant: Fr) -> usize {
+        let node = Node {
+            layer: 0,
+            add: add.to_vec(),
+            mul: mul.to_vec(),
+            constant,
+        };
+        let layer = 1 + node
+            .operands()
+            .map(|operand| self.nodes[operand].layer)
+            .max()
+            .unwrap_or(0);
+        self.nodes.push(Node { layer, ..node });
+        self.nodes.len() - 1
+    }
+
+    fn assert_zero(&mut self, v: usize) {
+        self.outputs.push(v);
+    }
+}
+
+/// Reads the proof and records the inputs of the circuit, in order.
+struct WitnessBackend<'a> {
+    claimed_v: &'a [Fr],
+    proof: Proof,
+    inputs: Vec<Fr>,
+}
+
+impl Backend for WitnessBackend<'_> {
+    type V = Fr;
+
+    fn claimed_output(&mut self, j: usize) -> Result<Fr, VerificationError> {
+        let v = self.claimed_v[j];
+        self.inputs.push(v);
+        Ok(v)
+    }
+
+    fn read(&mut self, nonce: bool) -> Result<Fr, VerificationError> {
+        let v = if nonce {
+            // hashed as the bytes of the transcript are
+            bytes_to_fr_chunks(&self.proof.get_next_slice(GRINDING_NONCE_BYTES)?)[0]
+        } else {
+            self.proof.get_next_and_step()?
+        };
+        self.inputs.push(v);
+        Ok(v)
+    }
+
+    fn hint(&mut self, value: Fr) -> Fr {
+        self.inputs.push(value);
+        value
+    }
+
+    fn bits(&mut self, value: Fr) -> Vec<Fr> {
+        let mut bytes = [0u8; 256 / 8];
+        value.serialize_into(&mut bytes);
+        let bits: Vec<Fr> = (0..256)
+            .map(|i| Fr::from(((bytes[i / 8] >> (i % 8)) & 1) as u32))
+            .collect();
+        self.inputs.extend(&bits);
+        bits
+    }
+
+    fn quotient(&mut self, a: Fr, b: Fr) -> Fr {
+        let q = b.inv().map_or(Fr::zero(), |b_inv| a * b_inv);
+        self.inputs.push(q);
+        q
+    }
+
+    fn gate(&mut self, add: &[(Fr, Fr)], mul: &[(Fr, Fr, Fr)], constant: Fr) -> Fr {
+        add.iter().map(|&(a, c)| a * c).sum::<Fr>()
+            + mul.iter().map(|&(a, b, c)| a * b * c).sum::<Fr>()
+            + constant
+    }
+
+    fn assert_zero(&mut self, _v: Fr) {}
+}
+
+/// The verification of a proof for `target` on a backend, mirroring `Verifier::verify` with
+/// the transcript hashed with MiMC7.
+struct Verification<'a, B: Backend> {
+    backend: B,
+    target: &'a Circuit<Fr>,
+    config: &'a Config,
+    commitment: &'a RecursiveCommitment,
+    /// the elements appended to the transcript since the last challenge
+    pending: Vec<B::V>,
+    last_challenge: Option<B::V>,
+}
+
+impl<B: Backend> Verification<'_, B> {
+    fn constant(&mut self, c: Fr) -> B::V {
+        self.backend.gate(&[], &[], c)
+    }
+
+    fn sub(&mut self, a: B::V, b: B::V) -> B::V {
+        self.backend
+            .gate(&[(a, Fr::one()), (b, -Fr::one())], &[], Fr::zero())
+    }
+
+    fn mul(&mut self, a: B::V, b: B::V) -> B::V {
+        self.backend.gate(&[], &[(a, b, Fr::one())], Fr::zero())
+    }
+
+    fn read(&mut self) -> Result<B::V, VerificationError> {
+        let v = self.backend.read(false)?;
+        self.pending.push(v);
+        Ok(v)
+    }
+
+    /// The Miyaguchi-Preneel hash of `ms`, see `crate::mimc`.
+    fn hash(&mut self, ms: &[B::V]) -> B::V {
+        let constants = mimc7_round_constants();
+        let mut h = self.constant(Fr::zero());
+        for &m in ms {
+            // h + m + E_h(m), where E_h(m) = t^7 + h after the last round
+            let mut t = self
+                .backend
+                .gate(&[(m, Fr::one()), (h, Fr::one())], &[], Fr::zero());
+            for i in 0..MIMC7_ROUNDS {
+                let t2 = self.mul(t, t);
+                let t4 = self.mul(t2, t2);
+                let t6 = self.mul(t4, t2);
+                t = match constants.get(i + 1) {
+                    Some(&c) => self
+                        .backend
+                        .gate(&[(h, Fr::one())], &[(t6, t, Fr::one())], c),
+                    None => self.backend.gate(
+                        &[(h, Fr::from(2u32)), (m, Fr::one())],
+                        &[(t6, t, Fr::one())],
+                        Fr::zero(),
+                    ),
+                };
+            }
+            h = t;
+        }
+        h
+    }
+
+    /// The hash of the elements appended since the last challenge, or of the last challenge if
+    /// there are none, taken as a hint.
+    fn challenge(&mut self) -> B::V {
+        let ms = match self.pending.is_empty() {
+            true => vec![self.last_challenge.unwrap()],
+            false => std::mem::take(&mut self.pending),
+        };
+        let h = self.hash(&ms);
+        let c = self.backend.hint(h);
+        let diff = self.sub(h, c);
+        self.backend.assert_zero(diff);
+        self.last_challenge = Some(c);
+        c
+    }
+
+    /// The bits of the canonical encoding of `h`, from the least significant one.
+    fn canonical_bits(&mut self, h: B::V) -> Vec<B::V> {
+        let bits = self.backend.bits(h);
+        let mut recomposed = vec![(h, -Fr::one())];
+        let mut power = Fr::one();
+        for &b in &bits {
+            let boolean = self
+                .backend
+                .gate(&[(b, -Fr::one())], &[(b, b, Fr::one())], Fr::zero());
+            self.backend.assert_zero(boolean);
+            recomposed.push((b, power));
+            power = power.double();
+        }
+        let diff = self.backend.gate(&recomposed, &[], Fr::zero());
+        self.backend.assert_zero(diff);
+
+        // the bits are those of the canonical encoding iff they make at most p - 1: compare
+        // them to its bits by a tree of (greater, equal) pairs, from the low bits up
+        let mut max = [0u8; 256 / 8];
+        (-Fr::one()).serialize_into(&mut max);
+        let zero = self.constant(Fr::zero());
+        let one = self.constant(Fr::one());
+        let mut cmp = vec![];
+        for (i, &b) in bits.iter().enumerate() {
+            cmp.push(match (max[i / 8] >> (i % 8)) & 1 {
+                1 => (zero, b),
+                _ => (b, self.sub(one, b)),
+            });
+        }
+        while cmp.len() > 1 {
+            let mut next = vec![];
+            for pair in cmp.chunks(2) {
+                let ((gt_lo, eq_lo), (gt_hi, eq_hi)) = (pair[0], pair[1]);
+                let gt = self.backend.gate(
+                    &[(gt_hi, Fr::one())],
+                    &[(eq_hi, gt_lo, Fr::one())],
+                    Fr::zero(),
+                );
+                next.push((gt, self.mul(eq_hi, eq_lo)));
+            }
+            cmp = next;
+        }
+        self.backend.assert_zero(cmp[0].0);
+        bits
+    }
+
+    /// Assert that the canonical encoding of `h` starts with `zero_bits` zero bits, from the
+    /// most significant bit of each byte, as `check_nonce` counts them.
+    fn assert_leading_zeros(&mut self, h: B::V, zero_bits: usize) {
+        let bits = self.canonical_bits(h);
+        for i in 0..zero_bits {
+            self.backend.assert_zero(bits[8 * (i / 8) + 7 - i % 8]);
+        }
+    }
+
+    /// `factor * eq(point, i)` for all `i`.
+    fn eq_table(&mut self, point: &[B::V], factor: B::V) -> Vec<B::V> {
+        let mut table = vec![factor];
+        for &r in point {
+            let his: Vec<_> = table.iter().map(|&t| self.mul(t, r)).collect();
+            for (t, &hi) in table.iter_mut().zip(&his) {
+                *t = self.sub(*t, hi);
+            }
+            table.extend(his);
+        }
+        table
+    }
+
+    /// The polynomial of degree 2 taking the values `evals` at `0, 1, 2`, at `r`.
+    fn interpolate(&mut self, evals: [B::V; 3], r: B::V) -> B::V {
+        let [p0, p1, p2] = evals;
+        let r2 = self.mul(r, r);
+        let half = Fr::INV_2;
+        self.backend.gate(
+            &[(p0, Fr::one())],
+            &[
+                (p0, r2, half),
+                (p0, r, -half * Fr::from(3u32)),
+                (p1, r2, -Fr::one()),
+                (p1, r, Fr::from(2u32)),
+                (p2, r2, half),
+                (p2, r, -half),
+            ],
+            Fr::zero(),
+        )
+    }
+
+    fn sumcheck_round(&mut self, sum: B::V) -> Result<(B::V, B::V), VerificationError> {
+        let evals = [self.read()?, self.read()?, self.read()?];
+        let r = self.challenge();
+        let check = self.backend.gate(
+            &[
+                (evals[0], Fr::one()),
+                (evals[1], Fr::one()),
+                (sum, -Fr::one()),
+            ],
+            &[],
+            Fr::zero(),
+        );
+        self.backend.assert_zero(check);
+        Ok((r, self.interpolate(evals, r)))
+    }
+
+    /// `sumcheck_verify_gkr_layer`, where `outs[j]` holds `alpha eq(rz0[j], o) + beta
+    /// eq(rz1[j], o)` for every output `o`. Returns the points and claims of the input.
+    #[allow(clippy::type_complexity)]
+    fn verify_layer(
+        &mut self,
+        layer: &CircuitLayer<Fr>,
+        outs: &[Vec<B::V>],
+        claimed: &[B::V],
+    ) -> Result<(Vec<Vec<B::V>>, Vec<Vec<B::V>>, Vec<B::V>, Vec<B::V>), VerificationError> {
+        let reps = self.config.get_num_repetitions();
+        let var_num = layer.input_var_num;
+        let mut sum: Vec<_> = (0..reps)
+            .map(|j| {
+                let add: Vec<_> = layer
+                    .const_
+                    .iter()
+                    .map(|g| (outs[j][g.o_id], -g.coef))
+                    .chain([(claimed[j], Fr::one())])
+                    .collect();
+                self.backend.gate(&add, &[], Fr::zero())
+            })
+            .collect();
+        let mut rx = vec![vec![]; reps];
+        let mut ry = vec![vec![]; reps];
+        let mut eq_rx = vec![vec![]; reps];
+        let mut vx = vec![];
+        for i_var in 0..var_num * 2 {
+            for j in 0..reps {
+                let r;
+                (r, sum[j]) = self.sumcheck_round(sum[j])?;
+                match i_var < var_num {
+                    true => rx[j].push(r),
+                    false => ry[j].push(r),
+                }
+                if i_var == var_num - 1 {
+                    vx.push(self.read()?);
+                    let one = self.constant(Fr::one());
+                    eq_rx[j] = self.eq_table(&rx[j], one);
+                    let mul: Vec<_> = layer
+                        .add
+                        .iter()
+                        .map(|g| (outs[j][g.o_id], eq_rx[j][g.i_ids[0]], g.coef))
+                        .collect();
+                    let add_eval = self.backend.gate(&[], &mul, Fr::zero());
+                    sum[j] = self.backend.gate(
+                        &[(sum[j], Fr::one())],
+                        &[(vx[j], add_eval, -Fr::one())],
+                        Fr::zero(),
+                    );
+                }
+            }
+        }
+        let vy = (0..reps)
+            .map(|_| self.read())
+            .collect::<Result<Vec<_>, _>>()?;
+        for j in 0..reps {
+            let one = self.constant(Fr::one());
+            let eq_ry = self.eq_table(&ry[j], one);
+            let mul: Vec<_> = layer
+                .mul
+                .iter()
+                .map(|g| {
+                    let out_x = self.mul(outs[j][g.o_id], eq_rx[j][g.i_ids[0]]);
+                    (out_x, eq_ry[g.i_ids[1]], g.coef)
+                })
+                .collect();
+            let mul_eval = self.backend.gate(&[], &mul, Fr::zero());
+            let vxy = self.mul(vx[j], vy[j]);
+            let check = self.backend.gate(
+                &[(sum[j], Fr::one())],
+                &[(vxy, mul_eval, -Fr::one())],
+                Fr::zero(),
+            );
+            self.backend.assert_zero(check);
+        }
+        Ok((rx, ry, vx, vy))
+    }
+
+    /// `MiMC7DeepFoldVerifier::verify` of the opening at `point` of the codeword committed in
+    /// `root`, claimed to evaluate to `eval`.
+    fn verify_deepfold(
+        &mut self,
+        pp: &DeepFoldParam<Fr>,
+        root: B::V,
+        point: &[B::V],
+        mut eval: B::V,
+    ) -> Result<(), VerificationError> {
+        let mut challenges = vec![];
+        let mut roots = vec![root];
+        for (i, &z) in point.iter().enumerate() {
+            let next_eval = self.read()?;
+            let c = self.challenge();
+            let diff = self.sub(next_eval, eval);
+            eval = self.backend.gate(
+                &[(eval, Fr::one())],
+                &[(c, diff, Fr::one()), (z, diff, -Fr::one())],
+                Fr::zero(),
+            );
+            challenges.push(c);
+            if i < point.len() - 1 {
+                roots.push(self.read()?);
+            } else {
+                let final_value = self.read()?;
+                let check = self.sub(final_value, eval);
+                self.backend.assert_zero(check);
+            }
+        }
+
+        // the query indices are the first 8 bytes of the digests read as big-endian integers,
+        // reduced modulo powers of two: their low bits
+        let indices: Vec<Vec<B::V>> = (0..pp.query_num)
+            .map(|_| {
+                let c = self.challenge();
+                let bits = self.canonical_bits(c);
+                (0..64).map(|b| bits[8 * (7 - b / 8) + b % 8]).collect()
+            })
+            .collect();
+        // twice the value of each query in the codeword of the round, folded from the last one
+        let mut folded = vec![];
+        for (i, subgroup) in pp.mult_subgroups[..point.len()].iter().enumerate() {
+            let depth = (subgroup.size() / 2).ilog2() as usize;
+            for (k, index) in indices.iter().enumerate() {
+                let x = self.read()?;
+                let nx = self.read()?;
+                let path = (0..depth)
+                    .map(|_| self.read())
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut node = self.hash(&[x, nx]);
+                for (&sibling, &b) in path.iter().zip(index) {
+                    // the node is the right child iff b
+                    let diff = self.sub(sibling, node);
+                    let left = self.backend.gate(
+                        &[(node, Fr::one())],
+                        &[(b, diff, Fr::one())],
+                        Fr::zero(),
+                    );
+                    let right = self.backend.gate(
+                        &[(sibling, Fr::one())],
+                        &[(b, diff, -Fr::one())],
+                        Fr::zero(),
+                    );
+                    node = self.hash(&[left, right]);
+                }
+                let check = self.sub(node, roots[i]);
+                self.backend.assert_zero(check);
+
+                if i > 0 {
+                    // the value at the index in the codeword of the round, x or nx by its
+                    // bit above the leaf index
+                    let b = index[depth];
+                    let diff = self.sub(nx, x);
+                    let check = self.backend.gate(
+                        &[(folded[k], Fr::one()), (x, -Fr::from(2u32))],
+                        &[(b, diff, -Fr::from(2u32))],
+                        Fr::zero(),
+                    );
+                    self.backend.assert_zero(check);
+                }
+                // the inverse of the element of the subgroup at the leaf index
+                let mut inv = self.constant(Fr::one());
+                for (l, &b) in index[..depth].iter().enumerate() {
+                    let w = subgroup.element_inv_at(1 << l) - Fr::one();
+                    inv = self
+                        .backend
+                        .gate(&[(inv, Fr::one())], &[(inv, b, w)], Fr::zero());
+                }
+                let diff = self.sub(x, nx);
+                let t = self.mul(diff, inv);
+                let c = challenges[i];
+                let next = self.backend.gate(
+                    &[(x, Fr::one()), (nx, Fr::one())],
+                    &[(c, t, Fr::one()), (c, x, -Fr::one()), (c, nx, -Fr::one())],
+                    Fr::zero(),
+                );
+                match i {
+                    0 => folded.push(next),
+                    _ => folded[k] = next,
+                }
+            }
+        }
+        for v in folded {
+            let check = self
+                .backend
+                .gate(&[(v, Fr::INV_2), (eval, -Fr::one())], &[], Fr::zero());
+            self.backend.assert_zero(check);
+        }
+        Ok(())
+    }
+
+    fn verify(&mut self) -> Result<(), VerificationError> {
+        let reps = self.config.get_num_repetitions();
+        let var_num = self.target.log_input_size();
+        let mut claimed = (0..reps)
+            .map(|j| self.backend.claimed_output(j))
+            .collect::<Result<Vec<_>, _>>()?;
+        let commitment = match self.commitment {
+            RecursiveCommitment::Raw => (0..1 << var_num)
+                .map(|_| self.read())
+                .collect::<Result<Vec<_>, _>>()?,
+            // the root, hashed as the bytes of the transcript are
+            RecursiveCommitment::MiMC7DeepFold(_) => vec![self.read()?],
+        };
+        let seed: Vec<_> = (0..256 / Fr::FIELD_SIZE)
+            .map(|_| self.challenge())
+            .collect();
+        let nonce = self.backend.read(true)?;
+        if self.config.grinding_bits > 0 {
+            // the seed elements and the nonce fill one chunk of the hashed bytes each
+            let h = self.hash(&[seed, vec![nonce]].concat());
+            self.assert_leading_zeros(h, self.config.grinding_bits);
+        }
+        self.pending.push(nonce);
+
+        let target = self.target;
+        let layers = &target.layers;
+        let mut rz0 = vec![vec![]; reps];
+        for _ in 0..layers.last().unwrap().output_var_num {
+            for z in rz0.iter_mut() {
+                z.push(self.challenge());
+            }
+        }
+        // the output layer is only claimed at rz0
+        let one = self.constant(Fr::one());
+        let mut outs: Vec<_> = rz0.iter().map(|z| self.eq_table(z, one)).collect();
+        let mut zs = vec![];
+        let mut ys = vec![];
+        for layer in layers.iter().rev() {
+            let (rx, ry, vx, vy) = self.verify_layer(layer, &outs, &claimed)?;
+            let alpha = self.challenge();
+            let beta = self.challenge();
+            outs = (0..reps)
+                .map(|j| {
+                    let eq_rx = self.eq_table(&rx[j], alpha);
+                    let eq_ry = self.eq_table(&ry[j], beta);
+                    eq_rx
+                        .into_iter()
+                        .zip(eq_ry)
+                        .map(|(x, y)| {
+                            self.backend
+                                .gate(&[(x, Fr::one()), (y, Fr::one())], &[], Fr::zero())
+                        })
+                        .collect()
+                })
+                .collect();
+            claimed = (0..reps)
+                .map(|j| {
+                    self.backend.gate(
+                        &[],
+                        &[(vx[j], alpha, Fr::one()), (vy[j], beta, Fr::one())],
+                        Fr::zero(),
+                    )
+                })
+                .collect();
+            (zs, ys) = (
+                rx.into_iter().chain(ry).collect(),
+                vx.into_iter().chain(vy).collect(),
+            );
+        }
+
+        // merge_evals, without weights
+        let r = self.challenge();
+        let mut sum = self.constant(Fr::zero());
+        for &y in &ys {
+            sum = self
+                .backend
+                .gate(&[(y, Fr::one())], &[(sum, r, Fr::one())], Fr::zero());
+        }
+        let mut res = vec![];
+        for _ in 0..var_num {
+            let x;
+            (x, sum) = self.sumcheck_round(sum)?;
+            res.push(x);
+        }
+        let mut eq_prod = self.constant(Fr::zero());
+        for z in &zs {
+            let mut prod = self.constant(Fr::one());
+            for (&x, &z) in res.iter().zip(z) {
+                let eq = self.backend.gate(
+                    &[(x, -Fr::one()), (z, -Fr::one())],
+                    &[(x, z, Fr::from(2u32))],
+                    Fr::one(),
+                );
+                prod = self.mul(prod, eq);
+            }
+            eq_prod =
+                self.backend
+                    .gate(&[(prod, Fr::one())], &[(eq_prod, r, Fr::one())], Fr::zero());
+        }
+
+        if let RecursiveCommitment::MiMC7DeepFold(pp) = self.commitment {
+            // the evaluation claimed at the merged point is sum / eq_prod
+            let eval = self.backend.quotient(sum, eq_prod);
+            let check = self.backend.gate(
+                &[(sum, Fr::one())],
+                &[(eq_prod, eval, -Fr::one())],
+                Fr::zero(),
+            );
+            self.backend.assert_zero(check);
+            return self.verify_deepfold(pp, commitment[0], &res, eval);
+        }
+
+        // the raw commitment evaluated at the merged point, without inverting eq_prod
+        let one = self.constant(Fr::one());
+        let eq_res = self.eq_table(&res, one);
+        let mul: Vec<_> = commitment
+            .iter()
+            .zip(&eq_res)
+            .map(|(&c, &e)| (c, e, Fr::one()))
+            .collect();
+        let eval = self.backend.gate(&[], &mul, Fr::zero());
+        let check = self.backend.gate(
+            &[(sum, Fr::one())],
+            &[(eq_prod, eval, -Fr::one())],
+            Fr::zero(),
+        );
+        self.backend.assert_zero(check);
+        Ok(())
+    }
+}
+
+/// The polynomial commitment of the proofs checked by a verifier circuit.
+#[derive(Debug, Clone)]
+pub enum RecursiveCommitment {
+    /// proofs of a `RawCommitmentProver`
+    Raw,
+    /// proofs of a `MiMC7DeepFoldProver` with these parameters
+    MiMC7DeepFold(DeepFoldParam<Fr>),
+}
+
+/// A circuit verifying the proofs for a target circuit, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct VerifierCircuit {
+    /// its outputs are all zero iff the proof its input layer is filled from passes
+    pub circuit: Circuit<Fr>,
+    target: Circuit<Fr>,
+    config: Config,
+    commitment: RecursiveCommitment,
+}
+
+impl VerifierCircuit {
+    /// The verifier circuit for the proofs of `target` made with `config` and a raw
+    /// commitment, see `with_commitment`.
+    pub fn new(target: &Circuit<Fr>, config: &Config) -> Result<Self, VerificationError> {
+        Self::with_commitment(target, config, RecursiveCommitment::Raw)
+    }
+
+    /// The verifier circuit for the proofs of `target` made with `config` and `commitment`, or
+    /// `InvalidStatement` for a configuration out of the scope of the module documentation.
+    pub fn with_commitment(
+        target: &Circuit<Fr>,
+        config: &Config,
+        commitment: RecursiveCommitment,
+    ) -> Result<Self, VerificationError> {
+        let config = &config.for_challenge_field::<Fr>();
+        let unsupported = |reason| Err(VerificationError::InvalidStatement(reason));
+        if config.field_type != FieldType::BN254 {
+            return unsupported("the verifier circuit is over BN254");
+        }
+        if config.fs_hash != FiatShamirHashType::MIMC7 {
+            return unsupported("the verifier circuit hashes the transcript with MiMC7");
+        }
+        if config.polynomial_commitment_type != PolynomialCommitmentType::Raw {
+            return unsupported("the proofs are made with the raw commitment type of the config");
+        }
+        if let RecursiveCommitment::MiMC7DeepFold(pp) = &commitment {
+            mimc_deepfold::check_param(pp, target.log_input_size())?;
+        }
+        if !target.lookups.is_empty() {
+            return unsupported("the verifier circuit does not verify lookups");
+        }
+        if config.zero_knowledge {
+            return unsupported("the verifier circuit does not verify zero-knowledge proofs");
+        }
+        // the nonce must be hashed alone, the other elements fill whole chunks
+        if target.layers.last().unwrap().output_var_num == 0 {
+            return unsupported("the verifier circuit needs an output variable");
+        }
+
+        let mut target = target.clone();
+        for layer in target.layers.iter_mut() {
+            layer.input_vals.evals = vec![];
+            layer.output_vals.evals = vec![];
+        }
+        let mut verification = Verification {
+            backend: CircuitBuilder::default(),
+            target: &target,
+            config,
+            commitment: &commitment,
+            pending: vec![],
+            last_challenge: None,
+        };
+        verification.verify()?;
+        let circuit = verification.backend.build();
+        Ok(VerifierCircuit {
+            circuit,
+            target,
+            config: config.clone(),
+            commitment,
+        })
+    }
+
+    /// The input layer of `circuit` for the proof `proof` of the claimed outputs `claimed_v`.
+    pub fn witness(&self, claimed_v: &[Fr], proof: &Proof) -> Result<Vec<Fr>, VerificationError> {
+        if claimed_v.len() != self.config.get_num_repetitions() {
+            return Err(VerificationError::InvalidStatement(
+                "one claimed output is expected per repetition",
+            ));
+        }
+        let mut verification = Verification {
+            backend: WitnessBackend {
+                claimed_v,
+                proof: proof.clone(),
+                inputs: vec![],
+            },
+            target: &self.target,
+            config: &self.config,
+            commitment: &self.commitment,
+            pending: vec![],
+            last_challenge: None,
+        };
+        verification.verify()?;
+        let WitnessBackend {
+            proof, mut inputs, ..
+        } = verification.backend;
+        if proof.remaining() > 0 {
+            return Err(VerificationError::TrailingBytes(proof.remaining()));
+        }
+        inputs.resize(1 << self.circuit.log_input_size(), Fr::zero());
+        Ok(inputs)
+    }
+}
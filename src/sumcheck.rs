@@ -0,0 +1,175 @@
+//! Sumcheck for sums of products of multilinear polynomials.
+//!
+//! The prover sends, in each round, the round polynomial at `0, 1, ..., degree`, each
+//! appended to the transcript, then draws the challenge of the round from the transcript.
+//! Variables are bound from the least significant bit of the evaluation index. The GKR layer
+//! sumchecks follow the same format with degree 2, but are proved with the specialized
+//! `SumcheckGkrHelper`, which skips the inputs that no gate reads.
+
+use arith::{Field, FieldSerde};
+
+use crate::{Proof, Transcript, VerificationError};
+
+/// `sum_i coef_i * prod_{j in products_i} mles[j]`, over the hypercube.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VirtualPolynomial<F: Field> {
+    pub var_num: usize,
+    /// evaluations of the multilinear polynomials over the hypercube
+    pub mles: Vec<Vec<F>>,
+    /// the coefficient and the indices in `mles` of each product
+    pub products: Vec<(F, Vec<usize>)>,
+}
+
+impl<F: Field> VirtualPolynomial<F> {
+    pub fn new(var_num: usize) -> Self {
+        VirtualPolynomial {
+            var_num,
+            mles: vec![],
+            products: vec![],
+        }
+    }
+
+    /// Add a multilinear polynomial and return its index.
+    pub fn add_mle(&mut self, evals: Vec<F>) -> usize {
+        assert_eq!(evals.len(), 1 << self.var_num);
+        self.mles.push(evals);
+        self.mles.len() - 1
+    }
+
+    /// Add `coef` times the product of the multilinear polynomials at `indices`.
+    pub fn add_product(&mut self, coef: F, indices: Vec<usize>) {
+        assert!(indices.iter().all(|&i| i < self.mles.len()));
+        self.products.push((coef, indices));
+    }
+
+    /// Maximum number of factors in a product, i.e. the degree of the round polynomials,
+    /// which are sent with at least two evaluations.
+    pub fn degree(&self) -> usize {
+        self.products
+            .iter()
+            .map(|(_, p)| p.len())
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+
+    pub fn sum(&self) -> F {
+        (0..1 << self.var_num)
+            .map(|x| self.product_sum(|i| self.mles[i][x]))
+            .sum()
+    }
+
+    fn product_sum(&self, mle_at: impl Fn(usize) -> F) -> F {
+        self.products
+            .iter()
+            .map(|(coef, p)| p.iter().fold(*coef, |acc, &i| acc * mle_at(i)))
+            .sum()
+    }
+
+    /// The round polynomial at `0, 1, ..., degree`, the first unbound variable being set to
+    /// each of these and the others summed over the hypercube.
+    fn round_evals(&self, size: usize, degree: usize) -> Vec<F> {
+        let mut evals = vec![F::zero(); degree + 1];
+        let mut mle_evals = vec![vec![F::zero(); degree + 1]; self.mles.len()];
+        for x in (0..size).step_by(2) {
+            for (mle, e) in self.mles.iter().zip(mle_evals.iter_mut()) {
+                let (p_0, p_1) = (mle[x], mle[x + 1]);
+                let diff = p_1 - p_0;
+                e[0] = p_0;
+                for t in 1..=degree {
+                    e[t] = e[t - 1] + diff;
+                }
+            }
+            for (t, eval) in evals.iter_mut().enumerate() {
+                *eval += self.product_sum(|i| mle_evals[i][t]);
+            }
+        }
+        evals
+    }
+}
+
+/// Prove the sum of `poly` over the hypercube. Returns the point the sum is reduced to and the
+/// evaluations of `poly.mles` at it, which the caller proves or sends.
+pub fn sumcheck_prove<F: Field + FieldSerde>(
+    mut poly: VirtualPolynomial<F>,
+    transcript: &mut Transcript,
+) -> (Vec<F>, Vec<F>) {
+    let degree = poly.degree();
+    let mut point = vec![];
+    for i in 0..poly.var_num {
+        transcript.set_round(i);
+        let size = 1 << (poly.var_num - i);
+        for eval in poly.round_evals(size, degree) {
+            transcript.append_f(eval);
+        }
+        let r = transcript.challenge_fext::<F>();
+        point.push(r);
+        for mle in poly.mles.iter_mut() {
+            for j in 0..size / 2 {
+                mle[j] = mle[j * 2] + (mle[j * 2 + 1] - mle[j * 2]) * r;
+            }
+        }
+    }
+    let mle_evals = poly.mles.iter().map(|mle| mle[0]).collect();
+    (point, mle_evals)
+}
+
+/// Verify a sumcheck of `var_num` variables and `degree`, claimed to sum to `claimed_sum`.
+/// Returns the point and the claimed evaluation of the summed polynomial at it, which the
+/// caller must check, or the first inconsistent round.
+pub fn sumcheck_verify<F: Field + FieldSerde>(
+    claimed_sum: F,
+    var_num: usize,
+    degree: usize,
+    transcript: &mut Transcript,
+    proof: &mut Proof,
+) -> Result<(Vec<F>, F), VerificationError> {
+    let mut sum = claimed_sum;
+    let mut point = vec![];
+    for round in 0..var_num {
+        transcript.set_round(round);
+        let evals = (0..=degree)
+            .map(|_| {
+                let eval = proof.get_next_and_step::<F>()?;
+                transcript.append_f(eval);
+                Ok(eval)
+            })
+            .collect::<Result<Vec<F>, _>>()?;
+        if evals[0] + evals[1] != sum {
+            return Err(VerificationError::Sumcheck { round });
+        }
+        let r = transcript.challenge_fext::<F>();
+        point.push(r);
+        sum = interpolate_uni_poly(&evals, r);
+    }
+    Ok((point, sum))
+}
+
+/// Evaluate at `x` the polynomial of degree `evals.len() - 1` taking the values `evals` at
+/// `0, 1, ...`.
+pub fn interpolate_uni_poly<F: Field>(evals: &[F], x: F) -> F {
+    let degree = evals.len() - 1;
+    // the Lagrange basis polynomial of i is prod_{j != i} (x - j) / ((-1)^(d - i) i! (d - i)!)
+    let mut prefix = vec![F::one(); evals.len() + 1];
+    let mut suffix = vec![F::one(); evals.len() + 1];
+    for j in 0..evals.len() {
+        prefix[j + 1] = prefix[j] * (x - F::from(j as u32));
+        suffix[degree - j] = suffix[degree - j + 1] * (x - F::from((degree - j) as u32));
+    }
+    // 1 / k! for k in 0..=d, from a single inversion
+    let fact = (1..=degree).fold(F::one(), |acc, k| acc * F::from(k as u32));
+    let mut inv_fact = vec![fact.inv().unwrap(); evals.len()];
+    for k in (1..=degree).rev() {
+        inv_fact[k - 1] = inv_fact[k] * F::from(k as u32);
+    }
+    let mut res = F::zero();
+    for (i, eval) in evals.iter().enumerate() {
+        let term = *eval * prefix[i] * suffix[i + 1] * inv_fact[i] * inv_fact[degree - i];
+        if (degree - i).is_multiple_of(2) {
+            res += term;
+        } else {
+            res -= term;
+        }
+    }
+    res
+}
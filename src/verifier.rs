@@ -2,95 +2,75 @@ use std::vec;
 
 use arith::{Field, FieldSerde};
 use ark_std::{end_timer, start_timer};
+use rayon::prelude::*;
 
 use crate::{
-    eq_evals_at_primitive, grind, Circuit, CircuitLayer, CommitmentSerde, Config, Gate,
-    PolyCommitVerifier, Proof, Transcript,
+    interpolate_uni_poly, lookup_verify, pad_point, sumcheck_verify, verify_grind, BatchLayout,
+    Circuit, CommitmentSerde, Config, FiatShamirHashType, FiatShamirHasher, FieldType,
+    InnerProductClaim, LookupLayout, PolyCommitVerifier, Proof, Transcript, TranscriptPhase,
+    TranscriptRecording, VerificationError, VerifyingKey, Wiring, ZkLayout,
 };
 
-fn degree_2_eval<F: Field>(p0: F, p1: F, p2: F, x: F::BaseField) -> F {
-    let c0 = &p0;
-    let c2 = F::INV_2 * (p2 - p1 - p1 + p0);
-    let c1 = p1 - p0 - c2;
-    *c0 + (c2.mul_base_elem(&x) + c1).mul_base_elem(&x)
-}
-
-fn eval_sparse_circuit_connect_poly<F: Field, const INPUT_NUM: usize>(
-    gates: &[Gate<F, INPUT_NUM>],
-    rz0: &[F::BaseField],
-    rz1: &[F::BaseField],
-    alpha: F::BaseField,
-    beta: F::BaseField,
-    ris: &[Vec<F::BaseField>],
-) -> F::BaseField {
-    let mut eq_evals_at_rz0 = vec![F::BaseField::zero(); 1 << rz0.len()];
-    let mut eq_evals_at_rz1 = vec![F::BaseField::zero(); 1 << rz1.len()];
-
-    eq_evals_at_primitive(rz0, &alpha, &mut eq_evals_at_rz0);
-    eq_evals_at_primitive(rz1, &beta, &mut eq_evals_at_rz1);
-
-    let mut eq_evals_at_ris = vec![vec![]; INPUT_NUM];
-    for i in 0..INPUT_NUM {
-        eq_evals_at_ris[i] = vec![F::BaseField::zero(); 1 << ris[i].len()];
-        eq_evals_at_primitive(&ris[i], &F::BaseField::one(), &mut eq_evals_at_ris[i])
-    }
-
-    let mut v = F::BaseField::zero();
-    for g in gates {
-        let mut prod = eq_evals_at_rz0[g.o_id] + eq_evals_at_rz1[g.o_id];
-
-        for (i, eq_evals_at_ri) in eq_evals_at_ris.iter().enumerate().take(INPUT_NUM) {
-            prod *= eq_evals_at_ri[g.i_ids[i]];
-        }
-        v += prod * g.coef;
-    }
-    v
+/// `eq(index, point)`, with the bits of `index` from the least significant one.
+pub(crate) fn eq_at_index<C: Field>(index: usize, point: &[C]) -> C {
+    point
+        .iter()
+        .enumerate()
+        .map(|(b, r)| {
+            if (index >> b) & 1 == 1 {
+                *r
+            } else {
+                C::one() - r
+            }
+        })
+        .product()
 }
 
+/// Returns the sumcheck points and the vx and vy claims.
 // todo: FIXME
 #[allow(clippy::too_many_arguments)]
 #[allow(clippy::type_complexity)]
 fn sumcheck_verify_gkr_layer<F: Field + FieldSerde>(
-    layer: &CircuitLayer<F>,
-    rz0: &[Vec<F::BaseField>],
-    rz1: &[Vec<F::BaseField>],
-    claimed_v0: &[F],
-    claimed_v1: &[F],
-    alpha: F::BaseField,
-    beta: F::BaseField,
+    layer: Wiring<F>,
+    layer_id: usize,
+    rz0: &[Vec<F::ChallengeField>],
+    rz1: &[Vec<F::ChallengeField>],
+    claimed_v0: &[F::ChallengeField],
+    claimed_v1: &[F::ChallengeField],
+    alpha: F::ChallengeField,
+    beta: F::ChallengeField,
     proof: &mut Proof,
     transcript: &mut Transcript,
     config: &Config,
-) -> (
-    bool,
-    Vec<Vec<F::BaseField>>,
-    Vec<Vec<F::BaseField>>,
-    Vec<F>,
-    Vec<F>,
-) {
-    let var_num = layer.input_var_num;
+) -> Result<
+    (
+        Vec<Vec<F::ChallengeField>>,
+        Vec<Vec<F::ChallengeField>>,
+        Vec<F::ChallengeField>,
+        Vec<F::ChallengeField>,
+    ),
+    VerificationError,
+> {
+    let var_num = layer.input_var_num();
+    let failed = |round| VerificationError::LayerSumcheck {
+        layer: layer_id,
+        round,
+    };
     let mut sum = (0..config.get_num_repetitions())
         .map(|i| {
-            claimed_v0[i].mul_base_elem(&alpha) + claimed_v1[i].mul_base_elem(&beta)
-                - F::one().mul_base_elem(&eval_sparse_circuit_connect_poly(
-                    &layer.const_,
-                    &rz0[i],
-                    &rz1[i],
-                    alpha,
-                    beta,
-                    &[],
-                ))
+            claimed_v0[i] * alpha + claimed_v1[i] * beta
+                - layer.eval_const(&rz0[i], &rz1[i], alpha, beta)
         })
         .collect::<Vec<_>>();
     let mut rx = vec![vec![]; config.get_num_repetitions()];
     let mut ry = vec![vec![]; config.get_num_repetitions()];
-    let mut vx_claim = vec![F::zero(); config.get_num_repetitions()];
-    let mut verified = true;
+    let mut vx_claim = vec![F::ChallengeField::zero(); config.get_num_repetitions()];
     for i_var in 0..var_num * 2 {
+        transcript.set_round(i_var);
         for j in 0..config.get_num_repetitions() {
-            let p0 = proof.get_next_and_step();
-            let p1 = proof.get_next_and_step();
-            let p2 = proof.get_next_and_step();
+            let p0 = proof.get_next_and_step::<F::ChallengeField>()?;
+            let p1 = proof.get_next_and_step::<F::ChallengeField>()?;
+            let p2 = proof.get_next_and_step::<F::ChallengeField>()?;
             transcript.append_f(p0);
             transcript.append_f(p1);
             transcript.append_f(p2);
@@ -104,97 +84,258 @@ fn sumcheck_verify_gkr_layer<F: Field + FieldSerde>(
                     p2
                 );
             }
-            let r = transcript.challenge_f::<F>();
+            let r = transcript.challenge_fext::<F::ChallengeField>();
 
             if i_var < var_num {
                 rx[j].push(r);
             } else {
                 ry[j].push(r);
             }
-            verified &= (p0 + p1) == sum[j];
+            if p0 + p1 != sum[j] {
+                return Err(failed(i_var));
+            }
 
-            sum[j] = degree_2_eval(p0, p1, p2, r);
+            sum[j] = interpolate_uni_poly(&[p0, p1, p2], r);
 
             if i_var == var_num - 1 {
-                vx_claim[j] = proof.get_next_and_step();
-                sum[j] -= vx_claim[j].mul_base_elem(&eval_sparse_circuit_connect_poly(
-                    &layer.add,
-                    &rz0[j],
-                    &rz1[j],
-                    alpha,
-                    beta,
-                    &[rx[j].clone()],
-                ));
+                vx_claim[j] = proof.get_next_and_step()?;
+                sum[j] -= vx_claim[j] * layer.eval_add(&rz0[j], &rz1[j], alpha, beta, &rx[j]);
                 transcript.append_f(vx_claim[j]);
             }
         }
     }
-    let mut vy_claim: Vec<F> = vec![];
+    transcript.set_round(var_num * 2);
+    let mut vy_claim: Vec<F::ChallengeField> = vec![];
+    for _ in 0..config.get_num_repetitions() {
+        vy_claim.push(proof.get_next_and_step()?);
+        transcript.append_f(*vy_claim.last().unwrap());
+    }
     for j in 0..config.get_num_repetitions() {
-        vy_claim.push(proof.get_next_and_step());
-        verified &= sum[j]
-            == vx_claim[j]
-                * vy_claim[j].mul_base_elem(&eval_sparse_circuit_connect_poly(
-                    &layer.mul,
-                    &rz0[j],
-                    &rz1[j],
-                    alpha,
-                    beta,
-                    &[rx[j].clone(), ry[j].clone()],
-                ));
-        transcript.append_f(vy_claim[j]);
+        let expected = vx_claim[j]
+            * vy_claim[j]
+            * layer.eval_mul(&rz0[j], &rz1[j], alpha, beta, &rx[j], &ry[j]);
+        if sum[j] != expected {
+            return Err(failed(var_num * 2));
+        }
     }
-    (verified, rx, ry, vx_claim, vy_claim)
+    Ok((rx, ry, vx_claim, vy_claim))
+}
+
+/// `sumcheck_verify_gkr_layer` in zero-knowledge mode, with the masks laid out by `layout`;
+/// see `crate::zk`. Also returns the claims the masks reduce to, one per repetition.
+// todo: FIXME
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+fn sumcheck_verify_gkr_layer_zk<F: Field + FieldSerde>(
+    layer: Wiring<F>,
+    layer_id: usize,
+    rz0: &[Vec<F::ChallengeField>],
+    rz1: &[Vec<F::ChallengeField>],
+    claimed_v0: &[F::ChallengeField],
+    claimed_v1: &[F::ChallengeField],
+    alpha: F::ChallengeField,
+    beta: F::ChallengeField,
+    proof: &mut Proof,
+    transcript: &mut Transcript,
+    config: &Config,
+    layout: &ZkLayout,
+) -> Result<
+    (
+        Vec<Vec<F::ChallengeField>>,
+        Vec<Vec<F::ChallengeField>>,
+        Vec<F::ChallengeField>,
+        Vec<F::ChallengeField>,
+        Vec<InnerProductClaim<F::ChallengeField>>,
+    ),
+    VerificationError,
+> {
+    let var_num = layer.input_var_num();
+    let reps = config.get_num_repetitions();
+    let failed = |round| VerificationError::LayerSumcheck {
+        layer: layer_id,
+        round,
+    };
+    transcript.set_round(0);
+    let mut mask_sums = vec![];
+    for _ in 0..reps {
+        mask_sums.push(proof.get_next_and_step::<F::ChallengeField>()?);
+        transcript.append_f(*mask_sums.last().unwrap());
+    }
+    let rho = transcript.challenge_fext::<F::ChallengeField>();
+    let mut sum = (0..reps)
+        .map(|i| {
+            claimed_v0[i] * alpha + claimed_v1[i] * beta
+                - layer.eval_const(&rz0[i], &rz1[i], alpha, beta)
+                + rho * mask_sums[i]
+        })
+        .collect::<Vec<_>>();
+    let mut rx = vec![vec![]; reps];
+    let mut ry = vec![vec![]; reps];
+    let mut vx_claim = vec![F::ChallengeField::zero(); reps];
+    let mut vy_claim = vec![F::ChallengeField::zero(); reps];
+    let mut c = vec![F::ChallengeField::zero(); reps];
+    // the rounds over x, y, then w
+    for i_var in 0..var_num * 2 + 1 {
+        transcript.set_round(i_var);
+        for j in 0..reps {
+            let evals = (0..4)
+                .map(|_| proof.get_next_and_step::<F::ChallengeField>())
+                .collect::<Result<Vec<_>, _>>()?;
+            for eval in &evals {
+                transcript.append_f(*eval);
+            }
+            let r = transcript.challenge_fext::<F::ChallengeField>();
+            if evals[0] + evals[1] != sum[j] {
+                return Err(failed(i_var));
+            }
+            sum[j] = interpolate_uni_poly(&evals, r);
+
+            if i_var < var_num {
+                rx[j].push(r);
+            } else if i_var < var_num * 2 {
+                ry[j].push(r);
+            } else {
+                c[j] = r;
+            }
+            if i_var == var_num - 1 {
+                vx_claim[j] = proof.get_next_and_step()?;
+                sum[j] -= vx_claim[j] * layer.eval_add(&rz0[j], &rz1[j], alpha, beta, &rx[j]);
+                transcript.append_f(vx_claim[j]);
+            }
+            if i_var == var_num * 2 - 1 {
+                vy_claim[j] = proof.get_next_and_step()?;
+                transcript.append_f(vy_claim[j]);
+            }
+        }
+    }
+    transcript.set_round(var_num * 2 + 1);
+    let mut claims = vec![];
+    for j in 0..reps {
+        let value = proof.get_next_and_step::<F::ChallengeField>()?;
+        transcript.append_f(value);
+        let expected = (F::ChallengeField::one() - c[j])
+            * vx_claim[j]
+            * vy_claim[j]
+            * layer.eval_mul(&rz0[j], &rz1[j], alpha, beta, &rx[j], &ry[j])
+            + value;
+        if sum[j] != expected {
+            return Err(failed(var_num * 2 + 1));
+        }
+        let mut point = rx[j].clone();
+        point.extend(&ry[j]);
+        point.push(c[j]);
+        let weights =
+            layout.layer_claim_weights(layer_id, j, &rz0[j], &rz1[j], alpha, beta, &point, rho);
+        claims.push(InnerProductClaim { weights, value });
+    }
+    Ok((rx, ry, vx_claim, vy_claim, claims))
 }
 
 // todo: FIXME
 #[allow(clippy::type_complexity)]
 pub fn gkr_verify<F: Field + FieldSerde>(
     circuit: &Circuit<F>,
-    claimed_v: &[F],
+    claimed_v: &[F::ChallengeField],
     transcript: &mut Transcript,
     proof: &mut Proof,
     config: &Config,
-) -> (
-    bool,
-    Vec<Vec<F::BaseField>>,
-    Vec<Vec<F::BaseField>>,
-    Vec<F>,
-    Vec<F>,
-) {
+) -> Result<
+    (
+        Vec<Vec<F::ChallengeField>>,
+        Vec<Vec<F::ChallengeField>>,
+        Vec<F::ChallengeField>,
+        Vec<F::ChallengeField>,
+    ),
+    VerificationError,
+> {
+    let layers: Vec<_> = circuit.layers.iter().map(Wiring::Gates).collect();
+    let (rz0, rz1, claimed_v0, claimed_v1, _) =
+        gkr_verify_wiring(&layers, claimed_v, transcript, proof, config, None)?;
+    Ok((rz0, rz1, claimed_v0, claimed_v1))
+}
+
+/// `gkr_verify` for the circuit with the wiring `layers`. In zero-knowledge mode, `zk` is the
+/// layout of the committed polynomial, and the claims the masks of each layer reduce to are
+/// returned too.
+#[allow(clippy::type_complexity)]
+pub fn gkr_verify_wiring<F: Field + FieldSerde>(
+    layers: &[Wiring<F>],
+    claimed_v: &[F::ChallengeField],
+    transcript: &mut Transcript,
+    proof: &mut Proof,
+    config: &Config,
+    zk: Option<&ZkLayout>,
+) -> Result<
+    (
+        Vec<Vec<F::ChallengeField>>,
+        Vec<Vec<F::ChallengeField>>,
+        Vec<F::ChallengeField>,
+        Vec<F::ChallengeField>,
+        Vec<InnerProductClaim<F::ChallengeField>>,
+    ),
+    VerificationError,
+> {
+    if claimed_v.len() != config.get_num_repetitions() {
+        return Err(VerificationError::InvalidStatement(
+            "one claimed output is expected per repetition",
+        ));
+    }
     let timer = start_timer!(|| "gkr verify");
-    let layer_num = circuit.layers.len();
+    let layer_num = layers.len();
     let mut rz0 = vec![vec![]; config.get_num_repetitions()];
     let mut rz1 = vec![vec![]; config.get_num_repetitions()];
-    for _ in 0..circuit.layers.last().unwrap().output_var_num {
+    transcript.set_phase(TranscriptPhase::Layer(layer_num - 1));
+    for _ in 0..layers.last().unwrap().output_var_num() {
         for j in 0..config.get_num_repetitions() {
-            rz0[j].push(transcript.challenge_f::<F>());
-            rz1[j].push(F::BaseField::zero());
+            rz0[j].push(transcript.challenge_fext::<F::ChallengeField>());
+            rz1[j].push(F::ChallengeField::zero());
         }
     }
-    let mut alpha = F::BaseField::one();
-    let mut beta = F::BaseField::zero();
+    let mut alpha = F::ChallengeField::one();
+    let mut beta = F::ChallengeField::zero();
     let mut claimed_v0 = claimed_v.to_vec();
-    let mut claimed_v1 = vec![F::zero(); claimed_v.len()];
+    let mut claimed_v1 = vec![F::ChallengeField::zero(); claimed_v.len()];
+    let mut mask_claims = vec![];
 
-    let mut verified = true;
     for i in (0..layer_num).rev() {
-        let cur_verified;
-        (cur_verified, rz0, rz1, claimed_v0, claimed_v1) = sumcheck_verify_gkr_layer(
-            &circuit.layers[i],
-            &rz0,
-            &rz1,
-            &claimed_v0,
-            &claimed_v1,
-            alpha,
-            beta,
-            proof,
-            transcript,
-            config,
-        );
-        verified &= cur_verified;
-        alpha = transcript.challenge_f::<F>();
-        beta = transcript.challenge_f::<F>();
+        transcript.set_phase(TranscriptPhase::Layer(i));
+        match zk {
+            Some(layout) => {
+                let claims;
+                (rz0, rz1, claimed_v0, claimed_v1, claims) = sumcheck_verify_gkr_layer_zk(
+                    layers[i],
+                    i,
+                    &rz0,
+                    &rz1,
+                    &claimed_v0,
+                    &claimed_v1,
+                    alpha,
+                    beta,
+                    proof,
+                    transcript,
+                    config,
+                    layout,
+                )?;
+                mask_claims.extend(claims);
+            }
+            None => {
+                (rz0, rz1, claimed_v0, claimed_v1) = sumcheck_verify_gkr_layer(
+                    layers[i],
+                    i,
+                    &rz0,
+                    &rz1,
+                    &claimed_v0,
+                    &claimed_v1,
+                    alpha,
+                    beta,
+                    proof,
+                    transcript,
+                    config,
+                )?
+            }
+        }
+        alpha = transcript.challenge_fext::<F::ChallengeField>();
+        beta = transcript.challenge_fext::<F::ChallengeField>();
         log::trace!(
             "Layer {} verified with alpha={:?} and beta={:?}, claimed_v0={:?}, claimed_v1={:?}",
             i,
@@ -205,7 +346,230 @@ pub fn gkr_verify<F: Field + FieldSerde>(
         );
     }
     end_timer!(timer);
-    (verified, rz0, rz1, claimed_v0, claimed_v1)
+    Ok((rz0, rz1, claimed_v0, claimed_v1, mask_claims))
+}
+
+/// `sumcheck_verify_gkr_layer` for the layers at the same depth of several circuits, proved by
+/// `sumcheck_prove_gkr_batch_layer`. The claims, the points and the returned values are indexed
+/// by the layer, then by the repetition.
+// todo: FIXME
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+fn sumcheck_verify_gkr_batch_layer<F: Field + FieldSerde>(
+    layers: &[Wiring<F>],
+    layer_id: usize,
+    rz0: &[Vec<Vec<F::ChallengeField>>],
+    rz1: &[Vec<Vec<F::ChallengeField>>],
+    claimed_v0: &[Vec<F::ChallengeField>],
+    claimed_v1: &[Vec<F::ChallengeField>],
+    alpha: F::ChallengeField,
+    beta: F::ChallengeField,
+    proof: &mut Proof,
+    transcript: &mut Transcript,
+    config: &Config,
+) -> Result<
+    (
+        Vec<Vec<Vec<F::ChallengeField>>>,
+        Vec<Vec<Vec<F::ChallengeField>>>,
+        Vec<Vec<F::ChallengeField>>,
+        Vec<Vec<F::ChallengeField>>,
+    ),
+    VerificationError,
+> {
+    let reps = config.get_num_repetitions();
+    let failed = |round| VerificationError::LayerSumcheck {
+        layer: layer_id,
+        round,
+    };
+    let lambda = if layers.len() > 1 {
+        transcript.challenge_fext::<F::ChallengeField>()
+    } else {
+        F::ChallengeField::one()
+    };
+    let mut weights = vec![F::ChallengeField::one()];
+    for _ in 1..layers.len() {
+        weights.push(*weights.last().unwrap() * lambda);
+    }
+    let var_nums: Vec<usize> = layers.iter().map(|layer| layer.input_var_num()).collect();
+    let var_num = *var_nums.iter().max().unwrap();
+
+    let mut sum = (0..reps)
+        .map(|j| {
+            (0..layers.len())
+                .map(|k| {
+                    weights[k]
+                        * (claimed_v0[k][j] * alpha + claimed_v1[k][j] * beta
+                            - layers[k].eval_const(&rz0[k][j], &rz1[k][j], alpha, beta))
+                })
+                .sum()
+        })
+        .collect::<Vec<F::ChallengeField>>();
+    let mut rx = vec![vec![vec![]; reps]; layers.len()];
+    let mut ry = vec![vec![vec![]; reps]; layers.len()];
+    // `eq(0, .)` at the challenges past the variables of each layer
+    let mut eq = vec![vec![F::ChallengeField::one(); reps]; layers.len()];
+    let mut vx_claim = vec![vec![F::ChallengeField::zero(); reps]; layers.len()];
+    for i_var in 0..var_num * 2 {
+        transcript.set_round(i_var);
+        for j in 0..reps {
+            let p0 = proof.get_next_and_step::<F::ChallengeField>()?;
+            let p1 = proof.get_next_and_step::<F::ChallengeField>()?;
+            let p2 = proof.get_next_and_step::<F::ChallengeField>()?;
+            transcript.append_f(p0);
+            transcript.append_f(p1);
+            transcript.append_f(p2);
+            let r = transcript.challenge_fext::<F::ChallengeField>();
+            if p0 + p1 != sum[j] {
+                return Err(failed(i_var));
+            }
+            sum[j] = interpolate_uni_poly(&[p0, p1, p2], r);
+
+            for k in 0..layers.len() {
+                let (half, i) = if i_var < var_num {
+                    (&mut rx[k][j], i_var)
+                } else {
+                    (&mut ry[k][j], i_var - var_num)
+                };
+                if i < var_nums[k] {
+                    half.push(r);
+                } else {
+                    eq[k][j] *= F::ChallengeField::one() - r;
+                }
+                if i_var + 1 == var_nums[k] {
+                    vx_claim[k][j] = proof.get_next_and_step()?;
+                    sum[j] -= weights[k]
+                        * vx_claim[k][j]
+                        * layers[k].eval_add(&rz0[k][j], &rz1[k][j], alpha, beta, &rx[k][j]);
+                    transcript.append_f(vx_claim[k][j]);
+                }
+            }
+        }
+    }
+    transcript.set_round(var_num * 2);
+    let mut vy_claim = vec![vec![]; layers.len()];
+    for _ in 0..reps {
+        for vy_claim in vy_claim.iter_mut() {
+            vy_claim.push(proof.get_next_and_step()?);
+            transcript.append_f(*vy_claim.last().unwrap());
+        }
+    }
+    for j in 0..reps {
+        let expected: F::ChallengeField = (0..layers.len())
+            .map(|k| {
+                weights[k]
+                    * eq[k][j]
+                    * vx_claim[k][j]
+                    * vy_claim[k][j]
+                    * layers[k].eval_mul(&rz0[k][j], &rz1[k][j], alpha, beta, &rx[k][j], &ry[k][j])
+            })
+            .sum();
+        if sum[j] != expected {
+            return Err(failed(var_num * 2));
+        }
+    }
+    Ok((rx, ry, vx_claim, vy_claim))
+}
+
+/// `gkr_verify` for a proof of `gkr_prove_batch`, where `claimed_v[k]` are the claimed outputs
+/// of `circuits[k]`. Returns the claims on each input layer.
+#[allow(clippy::type_complexity)]
+pub fn gkr_verify_batch<F: Field + FieldSerde>(
+    circuits: &[&Circuit<F>],
+    claimed_v: &[Vec<F::ChallengeField>],
+    transcript: &mut Transcript,
+    proof: &mut Proof,
+    config: &Config,
+) -> Result<
+    (
+        Vec<Vec<Vec<F::ChallengeField>>>,
+        Vec<Vec<Vec<F::ChallengeField>>>,
+        Vec<Vec<F::ChallengeField>>,
+        Vec<Vec<F::ChallengeField>>,
+    ),
+    VerificationError,
+> {
+    let reps = config.get_num_repetitions();
+    if claimed_v.iter().any(|claimed_v| claimed_v.len() != reps) {
+        return Err(VerificationError::InvalidStatement(
+            "one claimed output is expected per repetition",
+        ));
+    }
+    let timer = start_timer!(|| "gkr verify batch");
+    let depth = circuits.iter().map(|c| c.layers.len()).max().unwrap();
+    transcript.set_phase(TranscriptPhase::Layer(depth - 1));
+    let mut rz0 = vec![];
+    let mut rz1 = vec![];
+    for c in circuits {
+        let mut rz0_k = vec![vec![]; reps];
+        let mut rz1_k = vec![vec![]; reps];
+        for _ in 0..c.layers.last().unwrap().output_var_num {
+            for j in 0..reps {
+                rz0_k[j].push(transcript.challenge_fext::<F::ChallengeField>());
+                rz1_k[j].push(F::ChallengeField::zero());
+            }
+        }
+        rz0.push(rz0_k);
+        rz1.push(rz1_k);
+    }
+    let mut alpha = F::ChallengeField::one();
+    let mut beta = F::ChallengeField::zero();
+    let mut claimed_v0 = claimed_v.to_vec();
+    let mut claimed_v1 = vec![vec![F::ChallengeField::zero(); reps]; circuits.len()];
+
+    for d in 0..depth {
+        transcript.set_phase(TranscriptPhase::Layer(depth - 1 - d));
+        let active: Vec<usize> = (0..circuits.len())
+            .filter(|&k| circuits[k].layers.len() > d)
+            .collect();
+        let layers: Vec<_> = active
+            .iter()
+            .map(|&k| Wiring::Gates(&circuits[k].layers[circuits[k].layers.len() - 1 - d]))
+            .collect();
+        fn take<T: Default>(v: &mut [T], active: &[usize]) -> Vec<T> {
+            active.iter().map(|&k| std::mem::take(&mut v[k])).collect()
+        }
+        let (active_rz0, active_rz1) = (take(&mut rz0, &active), take(&mut rz1, &active));
+        let (active_v0, active_v1) = (
+            take(&mut claimed_v0, &active),
+            take(&mut claimed_v1, &active),
+        );
+        let (new_rz0, new_rz1, new_v0, new_v1) = sumcheck_verify_gkr_batch_layer(
+            &layers,
+            depth - 1 - d,
+            &active_rz0,
+            &active_rz1,
+            &active_v0,
+            &active_v1,
+            alpha,
+            beta,
+            proof,
+            transcript,
+            config,
+        )?;
+        let claims = new_rz0
+            .into_iter()
+            .zip(new_rz1)
+            .zip(new_v0.into_iter().zip(new_v1));
+        for (&k, ((new_rz0, new_rz1), (new_v0, new_v1))) in active.iter().zip(claims) {
+            (rz0[k], rz1[k]) = (new_rz0, new_rz1);
+            (claimed_v0[k], claimed_v1[k]) = (new_v0, new_v1);
+        }
+        alpha = transcript.challenge_fext::<F::ChallengeField>();
+        beta = transcript.challenge_fext::<F::ChallengeField>();
+    }
+    end_timer!(timer);
+    Ok((rz0, rz1, claimed_v0, claimed_v1))
+}
+
+/// What `Verifier::prepare` derives from a circuit, shared by the proofs for that circuit.
+struct PreparedCircuit<'a, F: Field> {
+    /// the layer sizes and the lookup columns; the gates are read through `wiring` only
+    circuit: &'a Circuit<F>,
+    wiring: Vec<Wiring<'a, F>>,
+    lookup_layout: Option<LookupLayout>,
+    zk_layout: Option<ZkLayout>,
+    /// variables of the committed polynomial
+    var_num: usize,
 }
 
 pub struct Verifier<F: Field + FieldSerde, PC: PolyCommitVerifier<F>> {
@@ -215,85 +579,365 @@ pub struct Verifier<F: Field + FieldSerde, PC: PolyCommitVerifier<F>> {
 
 impl<F: Field + FieldSerde, PC: PolyCommitVerifier<F>> Verifier<F, PC> {
     pub fn new(config: &Config, pp: PC::Param) -> Self {
+        // panics on the hashes not implemented yet
+        FiatShamirHasher::new(&config.fs_hash);
         Verifier {
-            config: config.clone(),
+            config: config.for_challenge_field::<F::ChallengeField>(),
             pp,
         }
     }
 
+    /// `ys` holds the claimed evaluations at `zs`, plus the inner products with the sparse
+    /// `z_weights[k]` for the claim at `zs[k]` if any, followed by the claimed inner products
+    /// with the sparse `weights`. Returns the point and the claimed evaluation the merge
+    /// sumcheck reduces to.
     fn merge_evals(
-        zs: Vec<Vec<F::BaseField>>,
-        ys: &Vec<F>,
+        zs: Vec<Vec<F::ChallengeField>>,
+        z_weights: Vec<Vec<(usize, F::ChallengeField)>>,
+        ys: &[F::ChallengeField],
+        weights: Vec<Vec<(usize, F::ChallengeField)>>,
         transcript: &mut Transcript,
         proof: &mut Proof,
-    ) -> (Vec<F>, F) {
-        let r = transcript.challenge_fext::<F>();
-        let mut sum = ys.iter().fold(F::zero(), |acc, &x| acc * r + x);
+    ) -> Result<(Vec<F::ChallengeField>, F::ChallengeField), VerificationError> {
+        transcript.set_phase(TranscriptPhase::Merge);
+        let one = F::ChallengeField::one();
+        let r = transcript.challenge_fext::<F::ChallengeField>();
+        let claimed_sum = ys
+            .iter()
+            .fold(F::ChallengeField::zero(), |acc, &x| acc * r + x);
         let var_num = zs[0].len();
-        let mut res = vec![];
-        for _ in 0..var_num {
-            let x_0 = proof.get_next_and_step();
-            transcript.append_f::<F>(x_0);
-            let x_1 = proof.get_next_and_step();
-            transcript.append_f::<F>(x_1);
-            let x_2 = proof.get_next_and_step();
-            transcript.append_f::<F>(x_2);
-            assert_eq!(sum, x_0 + x_1);
-            let challenge = transcript.challenge_fext::<F>();
-            res.push(challenge);
-            sum = x_0 * (F::one() - challenge) * (F::from(2) - challenge) * F::INV_2
-                + x_1 * challenge * (F::from(2) - challenge)
-                + x_2 * challenge * (challenge - F::one()) * F::INV_2;
+        let (res, sum) = sumcheck_verify(claimed_sum, var_num, 2, transcript, proof)?;
+        let eq_prod = zs
+            .iter()
+            .enumerate()
+            .fold(F::ChallengeField::zero(), |acc, (k, x)| {
+                let mut prod = one;
+                for i in 0..var_num {
+                    let res_x = res[i] * x[i];
+                    prod *= res_x + res_x + one - x[i] - res[i];
+                }
+                if let Some(w) = z_weights.get(k) {
+                    prod += w
+                        .iter()
+                        .map(|&(i, w_i)| w_i * eq_at_index(i, &res))
+                        .sum::<F::ChallengeField>();
+                }
+                acc * r + prod
+            });
+        let eq_prod = weights.iter().fold(eq_prod, |acc, w| {
+            acc * r
+                + w.iter()
+                    .map(|&(i, w_i)| w_i * eq_at_index(i, &res))
+                    .sum::<F::ChallengeField>()
+        });
+        // zero with negligible probability, the evaluation can't be recovered then
+        let eq_prod_inv = eq_prod
+            .inv()
+            .ok_or(VerificationError::Sumcheck { round: var_num })?;
+        Ok((res, sum * eq_prod_inv))
+    }
+
+    /// Read the commitment to a polynomial with `var_num` variables at the start of `proof`.
+    fn read_commitment(
+        &self,
+        var_num: usize,
+        proof: &Proof,
+    ) -> Result<PC::Commitment, VerificationError> {
+        if proof.bytes.len() < PC::commitment_size(&self.pp, var_num) {
+            return Err(VerificationError::TruncatedProof);
+        }
+        PC::Commitment::deserialize_from(&proof.bytes, 1 << var_num)
+            .ok_or(VerificationError::MalformedProof)
+    }
+
+    /// Reject the configurations no proof can be verified with.
+    fn check_config(&self) -> Result<(), VerificationError> {
+        if self.config.fs_hash == FiatShamirHashType::Poseidon
+            && self.config.field_type != FieldType::BN254
+        {
+            return Err(VerificationError::InvalidStatement(
+                "the Poseidon transcript is for BN254 proofs only",
+            ));
+        }
+        if self.config.zero_knowledge && !PC::HIDING {
+            return Err(VerificationError::InvalidStatement(
+                "the zero-knowledge mode needs a hiding commitment",
+            ));
         }
-        let eq_prod = zs.iter().fold(F::zero(), |acc, x| {
-            let mut prod = F::one();
-            for i in 0..var_num {
-                let res_x = res[i].mul_base_elem(&x[i]);
-                prod *= res_x + res_x + F::one().add_base_elem(&-x[i]) - res[i];
+        Ok(())
+    }
+
+    /// Derive what the verification of any proof for `circuit` needs from the circuit alone.
+    fn prepare<'a>(
+        &self,
+        circuit: &'a Circuit<F>,
+        wiring: Vec<Wiring<'a, F>>,
+    ) -> Result<PreparedCircuit<'a, F>, VerificationError> {
+        self.check_config()?;
+        // with lookups the input is committed along with the multiplicities, in
+        // zero-knowledge mode along with the masks
+        let lookup_layout = (!circuit.lookups.is_empty()).then(|| LookupLayout::new(circuit));
+        let zk_layout = self
+            .config
+            .zero_knowledge
+            .then(|| ZkLayout::new(circuit, self.config.get_num_repetitions()));
+        let var_num = match (&lookup_layout, &zk_layout) {
+            (Some(_), Some(_)) => {
+                return Err(VerificationError::InvalidStatement(
+                    "lookups are not supported in zero-knowledge mode",
+                ))
             }
-            acc * r + prod
-        });
-        (res, sum * eq_prod.inv().unwrap())
+            (Some(layout), None) => layout.var_num,
+            (None, Some(layout)) => layout.var_num,
+            (None, None) => circuit.log_input_size(),
+        };
+        Ok(PreparedCircuit {
+            circuit,
+            wiring,
+            lookup_layout,
+            zk_layout,
+            var_num,
+        })
     }
 
-    pub fn verify(&self, circuit: &Circuit<F>, claimed_v: &[F], proof: &Proof) -> bool {
-        let timer = start_timer!(|| "verify");
+    pub fn verify(
+        &self,
+        circuit: &Circuit<F>,
+        claimed_v: &[F::ChallengeField],
+        proof: &Proof,
+    ) -> Result<(), VerificationError> {
+        let prepared = self.prepare(circuit, circuit.layers.iter().map(Wiring::Gates).collect())?;
+        self.verify_prepared(&prepared, claimed_v, proof, &mut self.transcript())
+    }
 
-        let poly_size = circuit.layers.first().unwrap().input_vals.evals.len();
-        let commitment = PC::Commitment::deserialize_from(&proof.bytes, poly_size);
+    /// Same as `verify`, also returning the recording of the transcript up to the error if
+    /// any, to be compared with the one of `Prover::prove_recorded` by `first_divergence`.
+    pub fn verify_recorded(
+        &self,
+        circuit: &Circuit<F>,
+        claimed_v: &[F::ChallengeField],
+        proof: &Proof,
+    ) -> (Result<(), VerificationError>, TranscriptRecording) {
+        let mut transcript = self.transcript();
+        transcript.start_recording();
+        let result = self
+            .prepare(circuit, circuit.layers.iter().map(Wiring::Gates).collect())
+            .and_then(|prepared| {
+                self.verify_prepared(&prepared, claimed_v, proof, &mut transcript)
+            });
+        (result, transcript.take_recording().unwrap())
+    }
+
+    fn transcript(&self) -> Transcript {
+        Transcript::with_hash(&self.config.fs_hash)
+    }
+
+    /// Verify many proofs for the same circuit, each given with its claimed outputs, in
+    /// parallel on the current rayon thread pool. The wiring of the circuit is sorted once
+    /// into a `VerifyingKey` and shared by all of them. Returns the result of each proof, in
+    /// order, so that the failed ones are the indices holding an error.
+    pub fn verify_many(
+        &self,
+        circuit: &Circuit<F>,
+        proofs: &[(Vec<F::ChallengeField>, Proof)],
+    ) -> Vec<Result<(), VerificationError>>
+    where
+        PC::Param: Sync,
+    {
+        self.verify_many_with_key(&VerifyingKey::new(circuit), proofs)
+    }
+
+    /// `verify_many` for the circuit of `vk`.
+    pub fn verify_many_with_key(
+        &self,
+        vk: &VerifyingKey<F>,
+        proofs: &[(Vec<F::ChallengeField>, Proof)],
+    ) -> Vec<Result<(), VerificationError>>
+    where
+        PC::Param: Sync,
+    {
+        self.verify_many_prepared(self.prepare(vk.shape(), vk.wiring()), proofs)
+    }
+
+    fn verify_many_prepared(
+        &self,
+        prepared: Result<PreparedCircuit<F>, VerificationError>,
+        proofs: &[(Vec<F::ChallengeField>, Proof)],
+    ) -> Vec<Result<(), VerificationError>>
+    where
+        PC::Param: Sync,
+    {
+        let timer = start_timer!(|| format!("verify {} proofs", proofs.len()));
+        let results = match prepared {
+            Ok(prepared) => proofs
+                .par_iter()
+                .map(|(claimed_v, proof)| {
+                    self.verify_prepared(&prepared, claimed_v, proof, &mut self.transcript())
+                })
+                .collect(),
+            Err(err) => vec![Err(err); proofs.len()],
+        };
+        end_timer!(timer);
+        results
+    }
+
+    fn verify_prepared(
+        &self,
+        prepared: &PreparedCircuit<F>,
+        claimed_v: &[F::ChallengeField],
+        proof: &Proof,
+        transcript: &mut Transcript,
+    ) -> Result<(), VerificationError> {
+        let timer = start_timer!(|| "verify");
+        let PreparedCircuit {
+            circuit,
+            wiring,
+            lookup_layout,
+            zk_layout,
+            var_num,
+        } = prepared;
+        let (circuit, var_num) = (*circuit, *var_num);
+        let commitment = self.read_commitment(var_num, proof)?;
         let pc_verifier = PC::new(self.pp.clone(), commitment.clone());
 
-        let mut transcript = Transcript::new();
         transcript.append_u8_slice(&proof.bytes, commitment.size());
 
-        // ZZ: shall we use probabilistic grinding so the verifier can avoid this cost?
-        // (and also be recursion friendly)
-        grind::<F>(&mut transcript, &self.config);
         let mut proof = proof.clone(); // FIXME: consider separating pointers to make proof always immutable?
-        proof.step(commitment.size() + 256 / 8);
+        proof.step(commitment.size());
+        verify_grind::<F>(transcript, &self.config, &mut proof)?;
 
-        let (verified, rz0, rz1, claimed_v0, claimed_v1) = gkr_verify(
-            circuit,
+        let (rz0, rz1, claimed_v0, claimed_v1, mask_claims) = gkr_verify_wiring(
+            wiring,
             claimed_v,
-            &mut transcript,
+            transcript,
             &mut proof,
             &self.config,
-        );
+            zk_layout.as_ref(),
+        )?;
 
-        log::info!("GKR verification: {}", verified);
-        let (new_point, claimed_v) = Self::merge_evals(
-            rz0.into_iter().chain(rz1.into_iter()).collect(),
-            &claimed_v0
-                .into_iter()
-                .chain(claimed_v1.into_iter())
-                .collect(),
+        let mut ys: Vec<_> = claimed_v0.into_iter().chain(claimed_v1).collect();
+        let mut claims = mask_claims;
+        if let Some(layout) = lookup_layout {
+            claims = lookup_verify(circuit, layout, transcript, &mut proof)?;
+        }
+
+        // the input is the first block of the committed polynomial; in zero-knowledge mode
+        // its claims are on the masked values
+        let mut z_weights = vec![];
+        if let Some(layout) = zk_layout {
+            for (side, zs) in [&rz0, &rz1].into_iter().enumerate() {
+                for (j, z) in zs.iter().enumerate() {
+                    z_weights.push(layout.input_claim_weights(j, side, z));
+                }
+            }
+        }
+        let mut zs: Vec<_> = rz0.into_iter().chain(rz1).collect();
+        for z in zs.iter_mut() {
+            z.resize(var_num, F::ChallengeField::zero());
+        }
+        if zk_layout.is_some() {
+            // a claim in the random half masks the merge sumcheck
+            transcript.set_phase(TranscriptPhase::Merge);
+            zs.push(pad_point(
+                (1..var_num)
+                    .map(|_| transcript.challenge_fext::<F::ChallengeField>())
+                    .collect(),
+            ));
+            ys.push(proof.get_next_and_step()?);
+            transcript.append_f(*ys.last().unwrap());
+        }
+        ys.extend(claims.iter().map(|claim| claim.value));
+        let weights = claims.into_iter().map(|claim| claim.weights).collect();
+        let (new_point, claimed_v) =
+            Self::merge_evals(zs, z_weights, &ys, weights, transcript, &mut proof)?;
+        transcript.set_phase(TranscriptPhase::Opening);
+        pc_verifier.verify(&self.pp, &new_point, claimed_v, transcript, &mut proof)?;
+
+        end_timer!(timer);
+
+        match proof.remaining() {
+            0 => Ok(()),
+            n => Err(VerificationError::TrailingBytes(n)),
+        }
+    }
+
+    /// Verify a proof for the circuit of `vk`, evaluating the wiring predicates from its
+    /// preprocessed gates.
+    pub fn verify_with_key(
+        &self,
+        vk: &VerifyingKey<F>,
+        claimed_v: &[F::ChallengeField],
+        proof: &Proof,
+    ) -> Result<(), VerificationError> {
+        let prepared = self.prepare(vk.shape(), vk.wiring())?;
+        self.verify_prepared(&prepared, claimed_v, proof, &mut self.transcript())
+    }
+
+    /// Verify a proof of `prove_batch`, where `claimed_v[k]` are the claimed outputs of
+    /// `circuits[k]`. The commitment, the layer sumchecks at each depth and the opening are
+    /// checked once for the batch.
+    pub fn verify_batch(
+        &self,
+        circuits: &[&Circuit<F>],
+        claimed_v: &[Vec<F::ChallengeField>],
+        proof: &Proof,
+    ) -> Result<(), VerificationError> {
+        let timer = start_timer!(|| format!("verify batch of {}", circuits.len()));
+        self.check_config()?;
+        if circuits.is_empty() {
+            return Err(VerificationError::InvalidStatement("the batch is empty"));
+        }
+        if circuits.iter().any(|c| !c.lookups.is_empty()) {
+            return Err(VerificationError::InvalidStatement(
+                "batches do not support lookups",
+            ));
+        }
+        if self.config.zero_knowledge {
+            return Err(VerificationError::InvalidStatement(
+                "batches do not support the zero-knowledge mode",
+            ));
+        }
+        if circuits.len() != claimed_v.len() {
+            return Err(VerificationError::InvalidStatement(
+                "one set of claimed outputs is expected per circuit",
+            ));
+        }
+
+        let layout = BatchLayout::new(circuits);
+        let commitment = self.read_commitment(layout.var_num, proof)?;
+        let pc_verifier = PC::new(self.pp.clone(), commitment.clone());
+
+        let mut transcript = self.transcript();
+        transcript.append_u8_slice(&proof.bytes, commitment.size());
+        let mut proof = proof.clone();
+        proof.step(commitment.size());
+        verify_grind::<F>(&mut transcript, &self.config, &mut proof)?;
+        let (rz0s, rz1s, claimed_v0s, claimed_v1s) = gkr_verify_batch(
+            circuits,
+            claimed_v,
             &mut transcript,
             &mut proof,
-        );
-        let v = pc_verifier.verify(&self.pp, &new_point, claimed_v, &mut transcript, &mut proof);
+            &self.config,
+        )?;
+        let mut zs = vec![];
+        for (k, (rz0, rz1)) in rz0s.iter().zip(&rz1s).enumerate() {
+            zs.extend(rz0.iter().chain(rz1).map(|z| layout.point(k, z)));
+        }
+        let ys: Vec<_> = claimed_v0s
+            .into_iter()
+            .zip(claimed_v1s)
+            .flat_map(|(v0, v1)| v0.into_iter().chain(v1))
+            .collect();
+        let (new_point, claimed_v) =
+            Self::merge_evals(zs, vec![], &ys, vec![], &mut transcript, &mut proof)?;
+        transcript.set_phase(TranscriptPhase::Opening);
+        pc_verifier.verify(&self.pp, &new_point, claimed_v, &mut transcript, &mut proof)?;
 
         end_timer!(timer);
 
-        verified & v
+        match proof.remaining() {
+            0 => Ok(()),
+            n => Err(VerificationError::TrailingBytes(n)),
+        }
     }
 }
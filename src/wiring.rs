@@ -0,0 +1,687 @@
+//! Wiring predicates of the layers, as evaluated by the verifier.
+//!
+//! The predicate of a gate is its coefficient times eq polynomials at its output and input ids.
+//! `LayerWiring` keeps the gates of a layer sorted by output, with each id split into the low
+//! and the high half of its bits: `eq(r, id)` is then the product of an entry of a table over
+//! the low half of the variables of `r` and one over the high half, so the verifier tabulates
+//! `2^(n/2)` values per point instead of `2^n`, and the gates with the same output share the
+//! evaluation at their output.
+//!
+//! A layer of a `RecursiveCircuit` is made of a few leaf segments copied at many allocations.
+//! When the offsets of a copy are multiples of the sizes of the segment, the low bits of its ids
+//! are the ids in the segment and the high bits are those of the offsets, so `eq(r, id)` is
+//! `eq` at the low variables of `r` times `eq` at the high ones. The wiring predicate of the
+//! segment is then evaluated once at the low variables, and multiplied by the sum over its
+//! copies of the `eq` terms of their offsets; for copies at regular strides, that sum is a
+//! product over the variables, see `AllocationRun`.
+
+use arith::{Field, FieldSerde};
+
+use crate::{eq_evals_at_primitive, Allocation, CircuitLayer, Gate, RecursiveCircuit, SegmentId};
+
+fn eval_sparse_circuit_connect_poly<F: Field, const INPUT_NUM: usize>(
+    gates: &[Gate<F, INPUT_NUM>],
+    rz0: &[F::ChallengeField],
+    rz1: &[F::ChallengeField],
+    alpha: F::ChallengeField,
+    beta: F::ChallengeField,
+    ris: &[Vec<F::ChallengeField>],
+) -> F::ChallengeField {
+    let mut eq_evals_at_rz0 = vec![F::ChallengeField::zero(); 1 << rz0.len()];
+    let mut eq_evals_at_rz1 = vec![F::ChallengeField::zero(); 1 << rz1.len()];
+
+    eq_evals_at_primitive(rz0, &alpha, &mut eq_evals_at_rz0);
+    eq_evals_at_primitive(rz1, &beta, &mut eq_evals_at_rz1);
+
+    let mut eq_evals_at_ris = vec![vec![]; INPUT_NUM];
+    for i in 0..INPUT_NUM {
+        eq_evals_at_ris[i] = vec![F::ChallengeField::zero(); 1 << ris[i].len()];
+        eq_evals_at_primitive(&ris[i], &F::ChallengeField::one(), &mut eq_evals_at_ris[i])
+    }
+
+    let mut v = F::ChallengeField::zero();
+    for g in gates {
+        let mut prod = eq_evals_at_rz0[g.o_id] + eq_evals_at_rz1[g.o_id];
+
+        for (i, eq_evals_at_ri) in eq_evals_at_ris.iter().enumerate().take(INPUT_NUM) {
+            prod *= eq_evals_at_ri[g.i_ids[i]];
+        }
+        v += prod.mul_base_elem(&g.coef);
+    }
+    v
+}
+
+/// An id split into its low and high half of bits, see `split_id`.
+type SplitId = [u32; 2];
+
+/// Split `id`, the index of a point of the hypercube of `var_num` variables, into
+/// `[id & mask, id >> low_var_num]` with `low_var_num = var_num / 2`.
+fn split_id(id: usize, var_num: usize) -> SplitId {
+    let low_var_num = var_num / 2;
+    [
+        (id & ((1 << low_var_num) - 1)) as u32,
+        (id >> low_var_num) as u32,
+    ]
+}
+
+fn join_id([low, high]: SplitId, var_num: usize) -> usize {
+    low as usize | (high as usize) << (var_num / 2)
+}
+
+/// `mul_factor * eq(r, ·)` over the hypercube, as the product of a table over the low half of
+/// the variables of `r` and one over the high half.
+struct SplitEqTable<F> {
+    low: Vec<F>,
+    high: Vec<F>,
+}
+
+impl<F: Field> SplitEqTable<F> {
+    fn new(r: &[F], mul_factor: F) -> Self {
+        let low_var_num = r.len() / 2;
+        let mut low = vec![F::zero(); 1 << low_var_num];
+        let mut high = vec![F::zero(); 1 << (r.len() - low_var_num)];
+        eq_evals_at_primitive(&r[..low_var_num], &mul_factor, &mut low);
+        eq_evals_at_primitive(&r[low_var_num..], &F::one(), &mut high);
+        SplitEqTable { low, high }
+    }
+
+    #[inline(always)]
+    fn at(&self, [low, high]: SplitId) -> F {
+        self.low[low as usize] * self.high[high as usize]
+    }
+}
+
+/// The gates of one kind of a layer, sorted by output id then input ids.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SortedGates<F: Field, const INPUT_NUM: usize> {
+    /// output id of each run of gates with the same output, by increasing id
+    o_ids: Vec<SplitId>,
+    /// end of each run in `i_ids` and `coefs`
+    run_ends: Vec<u32>,
+    i_ids: Vec<[SplitId; INPUT_NUM]>,
+    coefs: Vec<F::BaseField>,
+}
+
+impl<F: Field, const INPUT_NUM: usize> SortedGates<F, INPUT_NUM> {
+    fn new(gates: &[Gate<F, INPUT_NUM>], output_var_num: usize, input_var_num: usize) -> Self {
+        let mut sorted: Vec<_> = gates.iter().collect();
+        sorted.sort_by_key(|g| (g.o_id, g.i_ids));
+        let mut ret = Self::default();
+        for g in sorted {
+            let o_id = split_id(g.o_id, output_var_num);
+            if ret.o_ids.last() != Some(&o_id) {
+                ret.o_ids.push(o_id);
+                ret.run_ends.push(0);
+            }
+            ret.i_ids
+                .push(g.i_ids.map(|i_id| split_id(i_id, input_var_num)));
+            ret.coefs.push(g.coef);
+            *ret.run_ends.last_mut().unwrap() = ret.coefs.len() as u32;
+        }
+        ret
+    }
+
+    fn len(&self) -> usize {
+        self.coefs.len()
+    }
+
+    /// The gates back with their original ids, in order.
+    fn gates(
+        &self,
+        output_var_num: usize,
+        input_var_num: usize,
+    ) -> impl Iterator<Item = Gate<F, INPUT_NUM>> + '_ {
+        let run_starts = std::iter::once(0).chain(self.run_ends.iter().copied());
+        self.o_ids
+            .iter()
+            .zip(run_starts.zip(&self.run_ends))
+            .flat_map(move |(&o_id, (start, &end))| {
+                (start as usize..end as usize).map(move |k| Gate {
+                    i_ids: self.i_ids[k].map(|i_id| join_id(i_id, input_var_num)),
+                    o_id: join_id(o_id, output_var_num),
+                    coef: self.coefs[k],
+                })
+            })
+    }
+
+    /// The sum over the gates of `coef * out(o) * ris[0](i_0) * ...`.
+    fn eval(
+        &self,
+        out: impl Fn(SplitId) -> F::ChallengeField,
+        ris: &[SplitEqTable<F::ChallengeField>; INPUT_NUM],
+    ) -> F::ChallengeField {
+        let mut v = F::ChallengeField::zero();
+        let mut start = 0;
+        for (&o_id, &end) in self.o_ids.iter().zip(&self.run_ends) {
+            let end = end as usize;
+            let mut run = F::ChallengeField::zero();
+            for (i_ids, coef) in self.i_ids[start..end].iter().zip(&self.coefs[start..end]) {
+                let mut prod = F::ChallengeField::one();
+                for (&i_id, ri) in i_ids.iter().zip(ris) {
+                    prod *= ri.at(i_id);
+                }
+                run += prod.mul_base_elem(coef);
+            }
+            v += out(o_id) * run;
+            start = end;
+        }
+        v
+    }
+
+    fn write_to(&self, buffer: &mut Vec<u8>, output_var_num: usize, input_var_num: usize) {
+        write_u64(buffer, self.len());
+        let mut coef_bytes = vec![0u8; F::BaseField::SIZE];
+        for g in self.gates(output_var_num, input_var_num) {
+            for i_id in g.i_ids {
+                write_u64(buffer, i_id);
+            }
+            write_u64(buffer, g.o_id);
+            g.coef.serialize_into(&mut coef_bytes);
+            buffer.extend_from_slice(&coef_bytes);
+        }
+    }
+
+    /// `None` if the bytes are not written by `write_to`, or an id is out of range.
+    fn read(
+        bytes: &[u8],
+        cur: &mut usize,
+        output_var_num: usize,
+        input_var_num: usize,
+    ) -> Option<Self> {
+        let gate_num = read_u64(bytes, cur)?;
+        let mut gates = vec![];
+        for _ in 0..gate_num {
+            let mut i_ids = [0; INPUT_NUM];
+            for i_id in i_ids.iter_mut() {
+                *i_id = read_id(bytes, cur, input_var_num)?;
+            }
+            let o_id = read_id(bytes, cur, output_var_num)?;
+            let coef =
+                F::BaseField::try_deserialize_from(bytes.get(*cur..*cur + F::BaseField::SIZE)?)?;
+            *cur += F::BaseField::SIZE;
+            gates.push(Gate { i_ids, o_id, coef });
+        }
+        Some(Self::new(&gates, output_var_num, input_var_num))
+    }
+}
+
+pub(crate) fn write_u64(buffer: &mut Vec<u8>, v: usize) {
+    buffer.extend_from_slice(&(v as u64).to_le_bytes());
+}
+
+/// Read the little-endian `u64` at `cur`, as in the circuit files, and move past it.
+pub(crate) fn read_u64(bytes: &[u8], cur: &mut usize) -> Option<usize> {
+    let v = u64::from_le_bytes(bytes.get(*cur..*cur + 8)?.try_into().ok()?);
+    *cur += 8;
+    usize::try_from(v).ok()
+}
+
+/// Read an id of the hypercube of `var_num` variables.
+pub(crate) fn read_id(bytes: &[u8], cur: &mut usize, var_num: usize) -> Option<usize> {
+    let id = read_u64(bytes, cur)?;
+    (id >> var_num == 0).then_some(id)
+}
+
+/// Copies of a segment, the `k`-th one at the output offset `o_offset + (k << o_stride_bits)`
+/// and the input offset `i_offset + (k << i_stride_bits)`, for `k < 2^count_bits`. The offsets
+/// are multiples of the sizes of the segment, with no bits set in the range of `k`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationRun {
+    pub o_offset: usize,
+    pub i_offset: usize,
+    pub o_stride_bits: usize,
+    pub i_stride_bits: usize,
+    pub count_bits: usize,
+}
+
+impl AllocationRun {
+    /// Group the allocations of a segment with `o_var_num` output and `i_var_num` input
+    /// variables, whose offsets are multiples of its sizes, into runs of consecutive ones.
+    pub fn group(allocations: &[Allocation], o_var_num: usize, i_var_num: usize) -> Vec<Self> {
+        let mut runs = vec![];
+        let mut rest = allocations;
+        while let Some(first) = rest.first() {
+            let mut run = AllocationRun {
+                o_offset: first.o_offset,
+                i_offset: first.i_offset,
+                o_stride_bits: o_var_num,
+                i_stride_bits: i_var_num,
+                count_bits: 0,
+            };
+            if let Some(second) = rest.get(1) {
+                let o_stride = second.o_offset.wrapping_sub(first.o_offset);
+                let i_stride = second.i_offset.wrapping_sub(first.i_offset);
+                // strides of at least the size of the segment, so that the copies do not overlap
+                if o_stride.is_power_of_two()
+                    && i_stride.is_power_of_two()
+                    && o_stride >> o_var_num > 0
+                    && i_stride >> i_var_num > 0
+                {
+                    let len = 1 + rest
+                        .windows(2)
+                        .take_while(|w| {
+                            w[1].o_offset.wrapping_sub(w[0].o_offset) == o_stride
+                                && w[1].i_offset.wrapping_sub(w[0].i_offset) == i_stride
+                        })
+                        .count();
+                    run.o_stride_bits = o_stride.trailing_zeros() as usize;
+                    run.i_stride_bits = i_stride.trailing_zeros() as usize;
+                    run.count_bits = len.ilog2() as usize;
+                    while !run.is_aligned() {
+                        run.count_bits -= 1;
+                    }
+                }
+            }
+            rest = &rest[1 << run.count_bits..];
+            runs.push(run);
+        }
+        runs
+    }
+
+    /// Whether the offsets have no bits set in the range of the copy index.
+    fn is_aligned(&self) -> bool {
+        let mask = (1 << self.count_bits) - 1;
+        (self.o_offset >> self.o_stride_bits) & mask == 0
+            && (self.i_offset >> self.i_stride_bits) & mask == 0
+    }
+
+    /// Whether the copies of a segment with `segment` sizes fit in a layer with `layer` sizes,
+    /// both as `(output, input)` numbers of variables.
+    fn fits(&self, segment: (usize, usize), layer: (usize, usize)) -> bool {
+        let fits_in = |offset: usize, stride_bits: usize, segment: usize, layer: usize| {
+            segment <= layer
+                && stride_bits >= segment
+                && stride_bits
+                    .checked_add(self.count_bits)
+                    .is_some_and(|end| end <= layer)
+                && offset & ((1 << segment) - 1) == 0
+                && offset >> layer == 0
+        };
+        fits_in(self.o_offset, self.o_stride_bits, segment.0, layer.0)
+            && fits_in(self.i_offset, self.i_stride_bits, segment.1, layer.1)
+            && self.is_aligned()
+    }
+
+    pub fn allocations(&self) -> impl Iterator<Item = Allocation> + '_ {
+        (0..1usize << self.count_bits).map(|k| Allocation {
+            i_offset: self.i_offset + (k << self.i_stride_bits),
+            o_offset: self.o_offset + (k << self.o_stride_bits),
+        })
+    }
+
+    /// The sum over the copies of `eq(rz, o_offset_k) * eq(ri, i_offset_k)` for each `ri` in
+    /// `ris`, at the high variables only: those from `o_low` on for `rz`, from `i_low` on for
+    /// the `ris`. The bits of `k` pair the variables of the points at the same position in
+    /// their strides and add up independently, so the sum is a product over the variables.
+    fn eval<C: Field>(&self, rz: &[C], ris: &[&[C]], o_low: usize, i_low: usize) -> C {
+        let eq_bit = |r: C, bit: usize| if bit & 1 == 1 { r } else { C::one() - r };
+        let window = |c: usize, stride_bits: usize| {
+            (stride_bits..stride_bits + self.count_bits).contains(&c)
+        };
+        let mut v = C::one();
+        for (c, &r) in rz.iter().enumerate().skip(o_low) {
+            if !window(c, self.o_stride_bits) {
+                v *= eq_bit(r, self.o_offset >> c);
+            }
+        }
+        for ri in ris {
+            for (c, &r) in ri.iter().enumerate().skip(i_low) {
+                if !window(c, self.i_stride_bits) {
+                    v *= eq_bit(r, self.i_offset >> c);
+                }
+            }
+        }
+        for j in 0..self.count_bits {
+            let z = rz[self.o_stride_bits + j];
+            let (mut one, mut zero) = (z, C::one() - z);
+            for ri in ris {
+                let x = ri[self.i_stride_bits + j];
+                one *= x;
+                zero *= C::one() - x;
+            }
+            v *= one + zero;
+        }
+        v
+    }
+}
+
+/// A leaf segment of a layer with its copies.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SegmentWiring<F: Field> {
+    /// the gates of the segment, with its own sizes
+    gates: LayerWiring<F>,
+    runs: Vec<AllocationRun>,
+}
+
+/// The wiring of a layer preprocessed for the verifier, see the module documentation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayerWiring<F: Field> {
+    pub input_var_num: usize,
+    pub output_var_num: usize,
+    mul: SortedGates<F, 2>,
+    add: SortedGates<F, 1>,
+    const_: SortedGates<F, 0>,
+    /// leaf segments evaluated once for all their copies, along with the gates above
+    segments: Vec<SegmentWiring<F>>,
+}
+
+/// Number of variables of the largest layer a serialized `LayerWiring` may have.
+const MAX_VAR_NUM: usize = 48;
+
+impl<F: Field> LayerWiring<F> {
+    pub fn new(layer: &CircuitLayer<F>) -> Self {
+        let (o, i) = (layer.output_var_num, layer.input_var_num);
+        LayerWiring {
+            input_var_num: i,
+            output_var_num: o,
+            mul: SortedGates::new(&layer.mul, o, i),
+            add: SortedGates::new(&layer.add, o, i),
+            const_: SortedGates::new(&layer.const_, o, i),
+            segments: vec![],
+        }
+    }
+
+    /// The wiring of the layer `layer_id` of `rc`, per leaf segment. The copies of a segment at
+    /// offsets that are not multiples of its sizes are flattened.
+    pub fn from_segments(rc: &RecursiveCircuit<F>, layer_id: SegmentId) -> Self {
+        let layer_seg = &rc.segments[layer_id];
+        let mut flat = CircuitLayer {
+            input_var_num: layer_seg.i_var_num,
+            output_var_num: layer_seg.o_var_num,
+            ..Default::default()
+        };
+        let mut segments = vec![];
+        for (leaf_seg_id, leaf_allocs) in rc.layer_leaves(layer_id) {
+            let leaf_seg = &rc.segments[leaf_seg_id];
+            let aligned = leaf_allocs.iter().all(|alloc| {
+                alloc.o_offset.trailing_zeros() as usize >= leaf_seg.o_var_num
+                    && alloc.i_offset.trailing_zeros() as usize >= leaf_seg.i_var_num
+            });
+            if !aligned {
+                for alloc in &leaf_allocs {
+                    leaf_seg.place(alloc, &mut flat);
+                }
+                continue;
+            }
+            let mut leaf = CircuitLayer {
+                input_var_num: leaf_seg.i_var_num,
+                output_var_num: leaf_seg.o_var_num,
+                ..Default::default()
+            };
+            leaf_seg.place(&Allocation::default(), &mut leaf);
+            segments.push(SegmentWiring {
+                gates: LayerWiring::new(&leaf),
+                runs: AllocationRun::group(&leaf_allocs, leaf_seg.o_var_num, leaf_seg.i_var_num),
+            });
+        }
+        LayerWiring {
+            segments,
+            ..LayerWiring::new(&flat)
+        }
+    }
+
+    /// The layer with its gates and no values.
+    pub fn to_layer(&self) -> CircuitLayer<F> {
+        let (o, i) = (self.output_var_num, self.input_var_num);
+        let mut layer = CircuitLayer {
+            input_var_num: i,
+            output_var_num: o,
+            mul: self.mul.gates(o, i).collect(),
+            add: self.add.gates(o, i).collect(),
+            const_: self.const_.gates(o, i).collect(),
+            ..Default::default()
+        };
+        for segment in &self.segments {
+            let leaf = segment.gates.to_layer();
+            for alloc in segment.runs.iter().flat_map(AllocationRun::allocations) {
+                for gate in &leaf.mul {
+                    layer.mul.push(Gate {
+                        i_ids: gate.i_ids.map(|i_id| i_id + alloc.i_offset),
+                        o_id: gate.o_id + alloc.o_offset,
+                        coef: gate.coef,
+                    });
+                }
+                for gate in &leaf.add {
+                    layer.add.push(Gate {
+                        i_ids: gate.i_ids.map(|i_id| i_id + alloc.i_offset),
+                        o_id: gate.o_id + alloc.o_offset,
+                        coef: gate.coef,
+                    });
+                }
+                for gate in &leaf.const_ {
+                    layer.const_.push(Gate {
+                        i_ids: [],
+                        o_id: gate.o_id + alloc.o_offset,
+                        coef: gate.coef,
+                    });
+                }
+            }
+        }
+        layer
+    }
+
+    /// Number of gates of the layer, with every copy of the segments.
+    pub fn gate_num(&self) -> usize {
+        let copies = |segment: &SegmentWiring<F>| -> usize {
+            segment.runs.iter().map(|run| 1 << run.count_bits).sum()
+        };
+        self.mul.len()
+            + self.add.len()
+            + self.const_.len()
+            + self
+                .segments
+                .iter()
+                .map(|segment| segment.gates.gate_num() * copies(segment))
+                .sum::<usize>()
+    }
+
+    /// Number of gates the verifier reads: those of the layer and of its leaf segments, once,
+    /// plus one per run of copies.
+    pub fn unique_gate_num(&self) -> usize {
+        self.mul.len()
+            + self.add.len()
+            + self.const_.len()
+            + self
+                .segments
+                .iter()
+                .map(|segment| segment.gates.gate_num() + segment.runs.len())
+                .sum::<usize>()
+    }
+
+    fn eval<const INPUT_NUM: usize>(
+        &self,
+        select: fn(&Self) -> &SortedGates<F, INPUT_NUM>,
+        rz0: &[F::ChallengeField],
+        rz1: &[F::ChallengeField],
+        alpha: F::ChallengeField,
+        beta: F::ChallengeField,
+        ris: [&[F::ChallengeField]; INPUT_NUM],
+    ) -> F::ChallengeField {
+        let one = F::ChallengeField::one();
+        let mut v = F::ChallengeField::zero();
+        let gates = select(self);
+        if gates.len() > 0 {
+            let rz0 = SplitEqTable::new(rz0, alpha);
+            let rz1 = SplitEqTable::new(rz1, beta);
+            let ris = ris.map(|ri| SplitEqTable::new(ri, one));
+            v += gates.eval(|o_id| rz0.at(o_id) + rz1.at(o_id), &ris);
+        }
+        for segment in &self.segments {
+            let gates = select(&segment.gates);
+            if gates.len() == 0 {
+                continue;
+            }
+            let (o_low, i_low) = (segment.gates.output_var_num, segment.gates.input_var_num);
+            let ris_low = ris.map(|ri| SplitEqTable::new(&ri[..i_low], one));
+            for (rz, scale) in [(rz0, alpha), (rz1, beta)] {
+                if scale.is_zero() {
+                    continue;
+                }
+                let copies: F::ChallengeField = segment
+                    .runs
+                    .iter()
+                    .map(|run| run.eval(rz, &ris, o_low, i_low))
+                    .sum();
+                if copies.is_zero() {
+                    continue;
+                }
+                let rz_low = SplitEqTable::new(&rz[..o_low], scale);
+                v += gates.eval(|o_id| rz_low.at(o_id), &ris_low) * copies;
+            }
+        }
+        v
+    }
+
+    pub fn write_to(&self, buffer: &mut Vec<u8>) {
+        let (o, i) = (self.output_var_num, self.input_var_num);
+        write_u64(buffer, i);
+        write_u64(buffer, o);
+        self.mul.write_to(buffer, o, i);
+        self.add.write_to(buffer, o, i);
+        self.const_.write_to(buffer, o, i);
+        write_u64(buffer, self.segments.len());
+        for segment in &self.segments {
+            segment.gates.write_to(buffer);
+            write_u64(buffer, segment.runs.len());
+            for run in &segment.runs {
+                for v in [
+                    run.o_offset,
+                    run.i_offset,
+                    run.o_stride_bits,
+                    run.i_stride_bits,
+                    run.count_bits,
+                ] {
+                    write_u64(buffer, v);
+                }
+            }
+        }
+    }
+
+    /// Read a layer written by `write_to` at `cur` and move past it; `None` if the bytes are
+    /// malformed.
+    pub fn read(bytes: &[u8], cur: &mut usize) -> Option<Self> {
+        let mut layer = Self::read_gates(bytes, cur)?;
+        for _ in 0..read_u64(bytes, cur)? {
+            // the leaf segments have no segments of their own
+            let gates = Self::read_gates(bytes, cur)?;
+            if read_u64(bytes, cur)? != 0 {
+                return None;
+            }
+            let mut runs = vec![];
+            for _ in 0..read_u64(bytes, cur)? {
+                let run = AllocationRun {
+                    o_offset: read_u64(bytes, cur)?,
+                    i_offset: read_u64(bytes, cur)?,
+                    o_stride_bits: read_u64(bytes, cur)?,
+                    i_stride_bits: read_u64(bytes, cur)?,
+                    count_bits: read_u64(bytes, cur)?,
+                };
+                let fits = run.fits(
+                    (gates.output_var_num, gates.input_var_num),
+                    (layer.output_var_num, layer.input_var_num),
+                );
+                if !fits {
+                    return None;
+                }
+                runs.push(run);
+            }
+            layer.segments.push(SegmentWiring { gates, runs });
+        }
+        Some(layer)
+    }
+
+    fn read_gates(bytes: &[u8], cur: &mut usize) -> Option<Self> {
+        let i = read_u64(bytes, cur)?;
+        let o = read_u64(bytes, cur)?;
+        if i > MAX_VAR_NUM || o > MAX_VAR_NUM {
+            return None;
+        }
+        Some(LayerWiring {
+            input_var_num: i,
+            output_var_num: o,
+            mul: SortedGates::read(bytes, cur, o, i)?,
+            add: SortedGates::read(bytes, cur, o, i)?,
+            const_: SortedGates::read(bytes, cur, o, i)?,
+            segments: vec![],
+        })
+    }
+}
+
+/// The wiring of a layer, either the gates of the layer as they are or preprocessed.
+#[derive(Debug, Clone, Copy)]
+pub enum Wiring<'a, F: Field> {
+    Gates(&'a CircuitLayer<F>),
+    Sorted(&'a LayerWiring<F>),
+}
+
+impl<'a, F: Field> Wiring<'a, F> {
+    pub fn input_var_num(&self) -> usize {
+        match self {
+            Wiring::Gates(layer) => layer.input_var_num,
+            Wiring::Sorted(wiring) => wiring.input_var_num,
+        }
+    }
+
+    pub fn output_var_num(&self) -> usize {
+        match self {
+            Wiring::Gates(layer) => layer.output_var_num,
+            Wiring::Sorted(wiring) => wiring.output_var_num,
+        }
+    }
+
+    /// The sum over the constant gates of `coef * (alpha * eq(rz0, o) + beta * eq(rz1, o))`.
+    pub fn eval_const(
+        &self,
+        rz0: &[F::ChallengeField],
+        rz1: &[F::ChallengeField],
+        alpha: F::ChallengeField,
+        beta: F::ChallengeField,
+    ) -> F::ChallengeField {
+        match self {
+            Wiring::Gates(layer) => {
+                eval_sparse_circuit_connect_poly(&layer.const_, rz0, rz1, alpha, beta, &[])
+            }
+            Wiring::Sorted(wiring) => wiring.eval(|w| &w.const_, rz0, rz1, alpha, beta, []),
+        }
+    }
+
+    /// Like `eval_const` over the addition gates, times `eq(rx, i)`.
+    pub fn eval_add(
+        &self,
+        rz0: &[F::ChallengeField],
+        rz1: &[F::ChallengeField],
+        alpha: F::ChallengeField,
+        beta: F::ChallengeField,
+        rx: &[F::ChallengeField],
+    ) -> F::ChallengeField {
+        match self {
+            Wiring::Gates(layer) => {
+                eval_sparse_circuit_connect_poly(&layer.add, rz0, rz1, alpha, beta, &[rx.to_vec()])
+            }
+            Wiring::Sorted(wiring) => wiring.eval(|w| &w.add, rz0, rz1, alpha, beta, [rx]),
+        }
+    }
+
+    /// Like `eval_const` over the multiplication gates, times `eq(rx, i_0) * eq(ry, i_1)`.
+    pub fn eval_mul(
+        &self,
+        rz0: &[F::ChallengeField],
+        rz1: &[F::ChallengeField],
+        alpha: F::ChallengeField,
+        beta: F::ChallengeField,
+        rx: &[F::ChallengeField],
+        ry: &[F::ChallengeField],
+    ) -> F::ChallengeField {
+        match self {
+            Wiring::Gates(layer) => eval_sparse_circuit_connect_poly(
+                &layer.mul,
+                rz0,
+                rz1,
+                alpha,
+                beta,
+                &[rx.to_vec(), ry.to_vec()],
+            ),
+            Wiring::Sorted(wiring) => wiring.eval(|w| &w.mul, rz0, rz1, alpha, beta, [rx, ry]),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! A circuit loaded once and shared by the proofs of many witnesses.
+//!
+//! `ProvingKey` holds the wiring and the lookup columns of a circuit behind an `Arc`, with no
+//! values. The values of one proof live in a `Witness`, so that several provers, each with its
+//! own `Prover` and scratchpads, can prove different witnesses of the same circuit concurrently.
+//! `VerifyingKey` holds the wiring preprocessed for the verifier, see `crate::wiring`, and can
+//! be serialized for verifiers that do not load the circuit file.
+
+use std::sync::Arc;
+
+use arith::{Field, FieldSerde};
+
+use crate::{
+    read_id, read_u64, write_u64, Circuit, CircuitLayer, LayerWiring, LookupColumn,
+    RecursiveCircuit, Wiring,
+};
+
+#[derive(Debug, Clone)]
+pub struct ProvingKey<F: Field> {
+    circuit: Arc<Circuit<F>>,
+}
+
+impl<F: Field> ProvingKey<F> {
+    /// Takes the wiring and the lookup columns of `circuit`; the values it holds are dropped.
+    pub fn new(mut circuit: Circuit<F>) -> Self {
+        for layer in circuit.layers.iter_mut() {
+            layer.input_vals.evals = vec![];
+            layer.output_vals.evals = vec![];
+        }
+        ProvingKey {
+            circuit: Arc::new(circuit),
+        }
+    }
+
+    pub fn circuit(&self) -> &Circuit<F> {
+        &self.circuit
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey<F> {
+        VerifyingKey::new(&self.circuit)
+    }
+
+    /// Evaluate the circuit on `input`.
+    pub fn evaluate(&self, input: Vec<F>) -> Witness<F> {
+        self.evaluate_checkpointed(input, 1)
+    }
+
+    /// Evaluate the circuit on `input`, keeping the input values of one layer in `interval`
+    /// only, like `Circuit::evaluate_checkpointed`.
+    pub fn evaluate_checkpointed(&self, input: Vec<F>, interval: usize) -> Witness<F> {
+        assert!(interval > 0);
+        assert_eq!(input.len(), 1 << self.circuit.log_input_size());
+        let mut layer_inputs = Vec::with_capacity(self.circuit.layers.len());
+        let mut vals = input;
+        for (i, layer) in self.circuit.layers.iter().enumerate() {
+            let next = layer.evaluate_on(&vals);
+            layer_inputs.push(if i.is_multiple_of(interval) {
+                vals
+            } else {
+                vec![]
+            });
+            vals = next;
+        }
+        Witness {
+            layer_inputs,
+            output: vals,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyingKey<F: Field> {
+    /// the layer sizes and the lookup columns of the circuit, with no gates
+    shape: Circuit<F>,
+    layers: Vec<LayerWiring<F>>,
+}
+
+impl<F: Field> VerifyingKey<F> {
+    pub fn new(circuit: &Circuit<F>) -> Self {
+        let layers: Vec<_> = circuit.layers.iter().map(LayerWiring::new).collect();
+        Self::from_layers(layers, circuit.lookups.clone())
+    }
+
+    /// The key of the flattened `rc`, with the wiring evaluated per leaf segment, see
+    /// `LayerWiring::from_segments`.
+    pub fn from_recursive_circuit(rc: &RecursiveCircuit<F>) -> Self {
+        let layers = rc
+            .layers
+            .iter()
+            .map(|&layer_id| LayerWiring::from_segments(rc, layer_id))
+            .collect();
+        Self::from_layers(layers, vec![])
+    }
+
+    fn from_layers(layers: Vec<LayerWiring<F>>, lookups: Vec<LookupColumn<F>>) -> Self {
+        let shape = Circuit {
+            layers: layers
+                .iter()
+                .map(|layer| CircuitLayer {
+                    input_var_num: layer.input_var_num,
+                    output_var_num: layer.output_var_num,
+                    ..Default::default()
+                })
+                .collect(),
+            lookups,
+        };
+        VerifyingKey { shape, layers }
+    }
+
+    pub fn layers(&self) -> &[LayerWiring<F>] {
+        &self.layers
+    }
+
+    pub fn lookups(&self) -> &[LookupColumn<F>] {
+        &self.shape.lookups
+    }
+
+    pub fn log_input_size(&self) -> usize {
+        self.shape.log_input_size()
+    }
+
+    /// The circuit with its gates and no values.
+    pub fn to_circuit(&self) -> Circuit<F> {
+        Circuit {
+            layers: self.layers.iter().map(LayerWiring::to_layer).collect(),
+            lookups: self.shape.lookups.clone(),
+        }
+    }
+
+    pub(crate) fn shape(&self) -> &Circuit<F> {
+        &self.shape
+    }
+
+    pub(crate) fn wiring(&self) -> Vec<Wiring<'_, F>> {
+        self.layers.iter().map(Wiring::Sorted).collect()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = vec![];
+        write_u64(&mut buffer, self.layers.len());
+        for layer in &self.layers {
+            layer.write_to(&mut buffer);
+        }
+        write_u64(&mut buffer, self.shape.lookups.len());
+        let mut entry_bytes = vec![0u8; F::BaseField::SIZE];
+        for column in &self.shape.lookups {
+            write_u64(&mut buffer, column.input_ids.len());
+            for &i in &column.input_ids {
+                write_u64(&mut buffer, i);
+            }
+            write_u64(&mut buffer, column.table.len());
+            for entry in &column.table {
+                entry.serialize_into(&mut entry_bytes);
+                buffer.extend_from_slice(&entry_bytes);
+            }
+        }
+        buffer
+    }
+
+    /// `None` if `bytes` are not written by `to_bytes`, or do not describe a valid circuit.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut cur = 0;
+        let layer_num = read_u64(bytes, &mut cur)?;
+        let mut layers = vec![];
+        for _ in 0..layer_num {
+            layers.push(LayerWiring::<F>::read(bytes, &mut cur)?);
+        }
+        // each layer reads the output of the previous one
+        let chained = layers
+            .windows(2)
+            .all(|w| w[0].output_var_num == w[1].input_var_num);
+        if layers.is_empty() || !chained {
+            return None;
+        }
+
+        let input_var_num = layers[0].input_var_num;
+        let column_num = read_u64(bytes, &mut cur)?;
+        let mut lookups = vec![];
+        for _ in 0..column_num {
+            let mut column = LookupColumn::default();
+            for _ in 0..read_u64(bytes, &mut cur)? {
+                column
+                    .input_ids
+                    .push(read_id(bytes, &mut cur, input_var_num)?);
+            }
+            for _ in 0..read_u64(bytes, &mut cur)? {
+                let entry = bytes.get(cur..cur + F::BaseField::SIZE)?;
+                column
+                    .table
+                    .push(F::BaseField::try_deserialize_from(entry)?);
+                cur += F::BaseField::SIZE;
+            }
+            lookups.push(column);
+        }
+        if cur != bytes.len() {
+            return None;
+        }
+        Some(Self::from_layers(layers, lookups))
+    }
+}
+
+/// The values of every layer of a circuit for one input.
+#[derive(Debug, Clone, Default)]
+pub struct Witness<F: Field> {
+    /// input values of each layer, indexed like `circuit.layers`; empty for the layers
+    /// dropped by `ProvingKey::evaluate_checkpointed`
+    pub layer_inputs: Vec<Vec<F>>,
+    /// output values of the last layer
+    pub output: Vec<F>,
+}
+
+impl<F: Field> Witness<F> {
+    pub fn input(&self) -> &[F] {
+        &self.layer_inputs[0]
+    }
+}
+
+/// The values read by the prover, either held by the circuit, as set by `Circuit::evaluate`,
+/// or by a separate `Witness`.
+#[derive(Debug, Clone, Copy)]
+pub enum WitnessRef<'a, F: Field> {
+    Circuit(&'a Circuit<F>),
+    Witness(&'a Witness<F>),
+}
+
+impl<'a, F: Field> WitnessRef<'a, F> {
+    /// Input values of layer `i`, empty if they were dropped by checkpointing.
+    pub fn layer_input(&self, i: usize) -> &'a [F] {
+        match self {
+            WitnessRef::Circuit(c) => &c.layers[i].input_vals.evals,
+            WitnessRef::Witness(w) => &w.layer_inputs[i],
+        }
+    }
+
+    pub fn output(&self) -> &'a [F] {
+        match self {
+            WitnessRef::Circuit(c) => &c.layers.last().unwrap().output_vals.evals,
+            WitnessRef::Witness(w) => &w.output,
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Zero-knowledge mode, selected by `Config::zero_knowledge`, in the style of Libra.
+//!
+//! The claims and the round messages of the GKR sumchecks are masked by random polynomials
+//! committed along with the input layer, so that everything the verifier sees is uniformly
+//! random up to the relations it checks:
+//! - for each layer and repetition, the input values `V` of the layer are replaced in the
+//!   sumcheck by `V^x(z) = V(z) + sum_l z_l (1 - z_l) s^x_l` on the `x` side, and by `V^y` on
+//!   the `y` side. They agree with `V` on the hypercube, so the sums are unchanged, but the
+//!   claims `V^x(rx)` and `V^y(ry)` are masked. The `s_l = R(.., 0) + R(.., 1)` come from the
+//!   committed `R(z, w) = sum_l z_l (1 - z_l) (p_l + q_l w)`;
+//! - the sumcheck of the layer above, whose claims are on `V^x` and `V^y`, gets an extra
+//!   variable `w` and the extra term `eq(0, x) eq(0, y) (alpha R^x(rz0, w) + beta R^y(rz1, w))`,
+//!   so that only the evaluation of `R` at the random `w` is revealed;
+//! - each layer sumcheck also sums `rho g(x, y, w)`, for a committed
+//!   `g = a_0 + sum_v a_{v,1} X_v + a_{v,2} X_v^2 + a_{v,3} X_v^3` whose sum is sent before
+//!   `rho` is drawn. The round messages have degree 3.
+//!
+//! At the end of the sumcheck of a layer, the terms in `R` and `g` are a single inner product
+//! of the committed polynomial with a public sparse vector, merged with the input claims into
+//! the opening. The upper half of the committed polynomial is random, and the merge adds a
+//! claim at a random point of it, which masks the merge sumcheck; the PCS must be hiding for
+//! the opening itself, see `PolyCommitProver::HIDING`.
+//!
+//! The masks are drawn with `Field::random_unsafe`, uniform for BN254, the field of the hiding
+//! DeepFold commitment. Lookups, batches and the verifier circuit are not supported.
+
+use arith::Field;
+use rand::RngCore;
+
+use crate::Circuit;
+
+/// Coefficients of the sumcheck mask `g` over `var_num` variables.
+fn sumcheck_mask_len(var_num: usize) -> usize {
+    1 + 3 * var_num
+}
+
+/// Position of the masks in the committed polynomial, laid out as
+/// `[input | masks of each layer and repetition | zero padding | random half]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ZkLayout {
+    pub input_var_num: usize,
+    /// number of variables of the committed polynomial, which the PCS parameters must match
+    pub var_num: usize,
+    /// `layer_var_nums[i]` is the number of input variables of layer `i`
+    pub layer_var_nums: Vec<usize>,
+    /// `mask_offsets[i][j]` is the offset of the masks of layer `i` and repetition `j`: the
+    /// coefficients of `g`, then the `p` and `q` of `R^x`, then those of `R^y`
+    pub mask_offsets: Vec<Vec<usize>>,
+}
+
+impl ZkLayout {
+    pub fn new<F: Field>(circuit: &Circuit<F>, num_repetitions: usize) -> Self {
+        let input_var_num = circuit.log_input_size();
+        let layer_var_nums: Vec<usize> = circuit.layers.iter().map(|l| l.input_var_num).collect();
+        let mut offset = 1usize << input_var_num;
+        let mut mask_offsets = vec![];
+        for &m in &layer_var_nums {
+            let mut offsets = vec![];
+            for _ in 0..num_repetitions {
+                offsets.push(offset);
+                offset += sumcheck_mask_len(2 * m + 1) + 4 * m;
+            }
+            mask_offsets.push(offsets);
+        }
+        // the random half masks the merge sumcheck: two new evaluations per round, the claim
+        // at a random point of it and the opened evaluation
+        let mut var_num = offset.next_power_of_two().trailing_zeros() as usize + 1;
+        while (1 << (var_num - 1)) < 2 * var_num + 2 {
+            var_num += 1;
+        }
+        ZkLayout {
+            input_var_num,
+            var_num,
+            layer_var_nums,
+            mask_offsets,
+        }
+    }
+
+    /// The polynomial to commit to: `input` followed by random masks, then a random half.
+    pub(crate) fn committed_poly<F: Field>(&self, input: &[F], mut rng: impl RngCore) -> Vec<F> {
+        let half = 1 << (self.var_num - 1);
+        let masks_end = match self.mask_offsets.last().and_then(|o| o.last()) {
+            Some(&offset) => {
+                let m = *self.layer_var_nums.last().unwrap();
+                offset + sumcheck_mask_len(2 * m + 1) + 4 * m
+            }
+            None => input.len(),
+        };
+        let mut evals = input.to_vec();
+        evals.extend((input.len()..masks_end).map(|_| F::random_unsafe(&mut rng)));
+        evals.resize(half, F::zero());
+        evals.extend((0..half).map(|_| F::random_unsafe(&mut rng)));
+        evals
+    }
+
+    /// Offset of the `p` of `R^x` (`side` 0) or `R^y` (`side` 1) of layer `i`, repetition `j`.
+    fn value_mask_offset(&self, i: usize, j: usize, side: usize) -> usize {
+        let m = self.layer_var_nums[i];
+        self.mask_offsets[i][j] + sumcheck_mask_len(2 * m + 1) + side * 2 * m
+    }
+
+    /// Push the weights of
+    /// `coef * sum_l z_l (1 - z_l) (w[0] p_l + w[1] q_l)` for the value mask `side` of layer
+    /// `i`, repetition `j`: `w = [1, c]` for `R(z, c)`, `[2, 1]` for `s` at `z`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn value_mask_weights<C: Field>(
+        &self,
+        i: usize,
+        j: usize,
+        side: usize,
+        z: &[C],
+        w: [C; 2],
+        coef: C,
+        weights: &mut Vec<(usize, C)>,
+    ) {
+        let m = self.layer_var_nums[i];
+        let offset = self.value_mask_offset(i, j, side);
+        for (l, z_l) in z.iter().enumerate() {
+            let a = coef * *z_l * (C::one() - z_l);
+            weights.push((offset + l, a * w[0]));
+            weights.push((offset + m + l, a * w[1]));
+        }
+    }
+
+    /// The weights of the claim the masks of layer `i`, repetition `j` reduce to at the end of
+    /// its sumcheck, at `point = rx || ry || c`: `rho g(point)`, plus
+    /// `eq(0, rx) eq(0, ry) (alpha R^x(rz0, c) + beta R^y(rz1, c))` for the masks of the
+    /// layer above.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn layer_claim_weights<C: Field>(
+        &self,
+        i: usize,
+        j: usize,
+        rz0: &[C],
+        rz1: &[C],
+        alpha: C,
+        beta: C,
+        point: &[C],
+        rho: C,
+    ) -> Vec<(usize, C)> {
+        let offset = self.mask_offsets[i][j];
+        let mut weights = vec![(offset, rho)];
+        for (v, x) in point.iter().enumerate() {
+            let x2 = x.square();
+            weights.push((offset + 1 + 3 * v, rho * x));
+            weights.push((offset + 2 + 3 * v, rho * x2));
+            weights.push((offset + 3 + 3 * v, rho * x2 * x));
+        }
+        if i + 1 < self.layer_var_nums.len() {
+            let (xy, c) = point.split_at(point.len() - 1);
+            let eq0: C = xy.iter().map(|r| C::one() - r).product();
+            let w = [C::one(), c[0]];
+            self.value_mask_weights(i + 1, j, 0, rz0, w, alpha * eq0, &mut weights);
+            self.value_mask_weights(i + 1, j, 1, rz1, w, beta * eq0, &mut weights);
+        }
+        weights
+    }
+
+    /// The weights to add to the claim on the input at `z`, `side` 0 for `rz0` and 1 for
+    /// `rz1` of repetition `j`, for the value mask of the input layer.
+    pub(crate) fn input_claim_weights<C: Field>(
+        &self,
+        j: usize,
+        side: usize,
+        z: &[C],
+    ) -> Vec<(usize, C)> {
+        let mut weights = vec![];
+        let w = [C::from(2u32), C::one()];
+        self.value_mask_weights(0, j, side, z, w, C::one(), &mut weights);
+        weights
+    }
+}
+
+/// The point of the random half of the committed polynomial at which the merge adds a claim:
+/// `challenges` followed by 1.
+pub(crate) fn pad_point<C: Field>(mut challenges: Vec<C>) -> Vec<C> {
+    challenges.push(C::one());
+    challenges
+}
+
+/// The masks of one layer and repetition on the prover side, and their contribution to the
+/// round messages of the layer sumcheck over `x`, `y` and `w`.
+pub(crate) struct LayerMask<C: Field> {
+    /// input variables of the layer
+    var_num: usize,
+    /// coefficients of `g`, as laid out in the committed polynomial
+    g: Vec<C>,
+    /// `g_rest[v]` is the sum of the coefficients of the variables from `v` on
+    g_rest: Vec<C>,
+    /// the `s_l` of `V^x` and `V^y`
+    sigma: [Vec<C>; 2],
+    /// `alpha R^x(rz0, w) + beta R^y(rz1, w)` of the masks of the layer above, as the value
+    /// at 0 and the slope; zero for the output layer
+    above: [C; 2],
+    pub(crate) rho: C,
+
+    round: usize,
+    /// `a_0` plus the terms of `g` in the bound variables
+    g_bound: C,
+    /// `sum_l r_l (1 - r_l) s_l` over the bound variables of each side
+    masked: [C; 2],
+    /// `eq(0, bound variables)`, over `x` and `y`
+    eq0: C,
+}
+
+impl<C: Field> LayerMask<C> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new<F: Field<ChallengeField = C>>(
+        layout: &ZkLayout,
+        committed: &[F],
+        i: usize,
+        j: usize,
+        rz0: &[C],
+        rz1: &[C],
+        alpha: C,
+        beta: C,
+    ) -> Self {
+        let m = layout.layer_var_nums[i];
+        let offset = layout.mask_offsets[i][j];
+        let g: Vec<C> = committed[offset..offset + sumcheck_mask_len(2 * m + 1)]
+            .iter()
+            .map(|&a| lift(a))
+            .collect();
+        let mut g_rest = vec![C::zero(); 2 * m + 2];
+        for v in (0..2 * m + 1).rev() {
+            g_rest[v] = g_rest[v + 1] + g[1 + 3 * v] + g[2 + 3 * v] + g[3 + 3 * v];
+        }
+        let sigma = [0, 1].map(|side| {
+            let offset = layout.value_mask_offset(i, j, side);
+            (0..m)
+                .map(|l| {
+                    let p = lift(committed[offset + l]);
+                    p.double() + lift(committed[offset + m + l])
+                })
+                .collect()
+        });
+        let mut above = [C::zero(); 2];
+        if i + 1 < layout.layer_var_nums.len() {
+            let mut weights = vec![];
+            let (one, zero) = (C::one(), C::zero());
+            layout.value_mask_weights(i + 1, j, 0, rz0, [one, zero], alpha, &mut weights);
+            layout.value_mask_weights(i + 1, j, 1, rz1, [one, zero], beta, &mut weights);
+            above[0] = inner_product(committed, &weights);
+            weights.clear();
+            layout.value_mask_weights(i + 1, j, 0, rz0, [zero, one], alpha, &mut weights);
+            layout.value_mask_weights(i + 1, j, 1, rz1, [zero, one], beta, &mut weights);
+            above[1] = inner_product(committed, &weights);
+        }
+        LayerMask {
+            var_num: m,
+            g_bound: g[0],
+            g,
+            g_rest,
+            sigma,
+            above,
+            rho: C::zero(),
+            round: 0,
+            masked: [C::zero(); 2],
+            eq0: C::one(),
+        }
+    }
+
+    /// The sum of `g` over the hypercube.
+    pub(crate) fn sum(&self) -> C {
+        C::from(2u32).exp(2 * self.var_num + 1) * (self.g[0] + self.g_rest[0] * C::INV_2)
+    }
+
+    /// `sum_l r_l (1 - r_l) s_l` at the challenges of `side`, 0 for `x` and 1 for `y`: the
+    /// mask of the claim on that side.
+    pub(crate) fn masked_value(&self, side: usize) -> C {
+        self.masked[side]
+    }
+
+    /// The sum of `g` over the free variables after the current one, set to `t`.
+    fn g_at(&self, t: C) -> C {
+        let v = self.round;
+        let free = 2 * self.var_num - v;
+        let t2 = t.square();
+        let term = self.g[1 + 3 * v] * t + self.g[2 + 3 * v] * t2 + self.g[3 + 3 * v] * t2 * t;
+        C::from(2u32).exp(free) * (self.g_bound + term + self.g_rest[v + 1] * C::INV_2)
+    }
+
+    /// The message of a round over `x` or `y`, at 0, 1, 2 and 3, from the evaluations `p` of
+    /// the unmasked message at 0, 1 and 2, and the sums `h` of the `hg` table at 0 and 1.
+    pub(crate) fn round_evals(&self, p: [C; 3], h: [C; 2]) -> [C; 4] {
+        let side = self.round / self.var_num;
+        let s = self.sigma[side][self.round % self.var_num];
+        let p3 = p[0] - (p[1] - p[2]) * C::from(3u32);
+        let k = self.above[0].double() + self.above[1];
+        let mut evals = [p[0], p[1], p[2], p3];
+        for (t, eval) in evals.iter_mut().enumerate() {
+            let t = C::from(t as u32);
+            let mask = self.masked[side] + t * (C::one() - t) * s;
+            *eval += mask * (h[0] + t * (h[1] - h[0]))
+                + k * self.eq0 * (C::one() - t)
+                + self.rho * self.g_at(t);
+        }
+        evals
+    }
+
+    /// The message of the round over `w`, at 0, 1, 2 and 3, where `wiring` is
+    /// `vx vy mul(rx, ry)` with the masked claims.
+    pub(crate) fn w_round_evals(&self, wiring: C) -> [C; 4] {
+        [0u32, 1, 2, 3].map(|t| {
+            let t = C::from(t);
+            (C::one() - t) * wiring
+                + self.eq0 * (self.above[0] + t * self.above[1])
+                + self.rho * self.g_at(t)
+        })
+    }
+
+    pub(crate) fn receive_challenge(&mut self, r: C) {
+        let v = self.round;
+        let r2 = r.square();
+        self.g_bound += self.g[1 + 3 * v] * r + self.g[2 + 3 * v] * r2 + self.g[3 + 3 * v] * r2 * r;
+        if v < 2 * self.var_num {
+            let side = v / self.var_num;
+            self.masked[side] += r * (C::one() - r) * self.sigma[side][v % self.var_num];
+            self.eq0 *= C::one() - r;
+        }
+        self.round += 1;
+    }
+}
+
+fn lift<F: Field>(a: F) -> F::ChallengeField {
+    F::ChallengeField::from(a)
+}
+
+/// The inner product of `evals` with the sparse `weights`.
+pub(crate) fn inner_product<F: Field>(
+    evals: &[F],
+    weights: &[(usize, F::ChallengeField)],
+) -> F::ChallengeField {
+    weights
+        .iter()
+        .map(|&(i, w)| w * F::ChallengeField::from(evals[i]))
+        .sum()
+}
@@ -0,0 +1,231 @@
+use arith::{mul_group::Radix2Group, Field, FieldSerde, M31Ext3, M31};
+use expander_rs::{
+    deepfold::{DeepFoldParam, DeepFoldProver, DeepFoldVerifier, HidingDeepFoldProver},
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    BatchLayout, BristolCircuit, Circuit, Config, Prover, ProverError, VerificationError, Verifier,
+    VerifyingKey,
+};
+use halo2curves::bn256::Fr;
+
+const FILENAME_MUL: &str = "data/ExtractedCircuitMul.txt";
+const FILENAME_ADD: &str = "data/ExtractedCircuitAdd.txt";
+
+/// 2-bit adder: inputs a (wires 0..2) and b (wires 2..4), outputs the carry then the sum bits.
+const ADDER_2: &str = "9 13
+2 2 2
+1 3
+
+2 1 0 2 4 XOR
+2 1 0 2 5 AND
+2 1 1 3 6 XOR
+2 1 6 5 7 XOR
+2 1 1 3 8 AND
+2 1 5 6 9 AND
+2 1 8 9 10 XOR
+1 1 4 11 EQW
+1 1 7 12 EQW
+";
+
+fn load_circuits<F: Field + FieldSerde>() -> Vec<Circuit<F>> {
+    let mut extracted = Circuit::<F>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    extracted.set_random_bool_input_for_test();
+    extracted.evaluate();
+
+    let mut adder = BristolCircuit::parse(ADDER_2).unwrap().to_circuit::<F>();
+    adder.set_bool_input(&[true, false, true, true]);
+    adder.evaluate();
+
+    let mut other_extracted = Circuit::<F>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    other_extracted.set_random_bool_input_for_test();
+    other_extracted.evaluate();
+
+    vec![extracted, adder, other_extracted]
+}
+
+#[test]
+fn test_batch_layout() {
+    let circuits = load_circuits::<M31>();
+    let circuits: Vec<&Circuit<M31>> = circuits.iter().collect();
+    let layout = BatchLayout::new(&circuits);
+    let big = circuits[0].log_input_size();
+    assert!(circuits[1].log_input_size() < big);
+    // the two large inputs first, then the small one
+    assert_eq!(layout.offsets, vec![0, 2 << big, 1 << big]);
+    assert_eq!(layout.var_num, big + 2);
+}
+
+#[test]
+fn test_batch_proof_raw() {
+    let config = Config::m31_config();
+    let circuits = load_circuits::<M31>();
+    let circuits: Vec<&Circuit<M31>> = circuits.iter().collect();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem_batch(&circuits);
+    let (claimed_v, proof) = prover.prove_batch(&circuits).unwrap();
+    assert_eq!(claimed_v.len(), circuits.len());
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify_batch(&circuits, &claimed_v, &proof).is_ok());
+
+    for k in 0..circuits.len() {
+        let mut bad_claimed_v = claimed_v.clone();
+        bad_claimed_v[k][0] += M31Ext3::one();
+        assert!(verifier
+            .verify_batch(&circuits, &bad_claimed_v, &proof)
+            .is_err());
+    }
+
+    // a batch of one circuit is proved like the circuit alone
+    let (single_claimed_v, single_proof) = prover.prove_batch(&circuits[..1]).unwrap();
+    assert!(verifier
+        .verify(circuits[0], &single_claimed_v[0], &single_proof)
+        .is_ok());
+}
+
+#[test]
+fn test_batch_proof_deepfold() {
+    let config = Config::bn254_config();
+    let circuits = load_circuits::<Fr>();
+    let circuits: Vec<&Circuit<Fr>> = circuits.iter().collect();
+
+    let variable_num = BatchLayout::new(&circuits).var_num;
+    let mut mult_subgroups = vec![Radix2Group::<Fr>::new(variable_num as u32 + 3)];
+    for i in 1..variable_num {
+        mult_subgroups.push(mult_subgroups[i - 1].exp(2));
+    }
+    let pp = DeepFoldParam {
+        mult_subgroups,
+        variable_num,
+        query_num: 30,
+    };
+
+    let mut prover = Prover::<_, DeepFoldProver<_>>::new(&config, pp.clone());
+    prover.prepare_mem_batch(&circuits);
+    let (claimed_v, proof) = prover.prove_batch(&circuits).unwrap();
+
+    let verifier = Verifier::<_, DeepFoldVerifier<_>>::new(&config, pp);
+    assert!(verifier.verify_batch(&circuits, &claimed_v, &proof).is_ok());
+
+    let mut bad_claimed_v = claimed_v.clone();
+    bad_claimed_v[1][0] += Fr::one();
+    assert!(verifier
+        .verify_batch(&circuits, &bad_claimed_v, &proof)
+        .is_err());
+}
+
+#[test]
+fn test_batch_sumchecks_are_shared() {
+    let config = Config::m31_config();
+    let circuits = load_circuits::<M31>();
+    let circuits: Vec<&Circuit<M31>> = circuits.iter().collect();
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem_batch(&circuits);
+    let (claimed_v, proof) = prover.prove_batch(&circuits).unwrap();
+
+    // without the raw commitments, the batch of the two large circuits and the small one
+    // costs little more than the largest circuit alone: the sumchecks are shared, only the
+    // claims on each layer and the merge grow
+    let largest = [circuits[0]];
+    let (_, single_proof) = prover.prove_batch(&largest).unwrap();
+    let commitment_bytes =
+        |circuits: &[&Circuit<M31>]| (1 << BatchLayout::new(circuits).var_num) * M31::SIZE;
+    let batch_bytes = proof.bytes.len() - commitment_bytes(&circuits);
+    let single_bytes = single_proof.bytes.len() - commitment_bytes(&largest);
+    assert!(batch_bytes < single_bytes * 3 / 2);
+
+    // tampering with any part of the shared sumchecks is caught
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    for at in [proof.bytes.len() / 2, proof.bytes.len() * 3 / 4] {
+        let mut bad_proof = proof.clone();
+        bad_proof.bytes[at] ^= 1;
+        assert!(verifier
+            .verify_batch(&circuits, &claimed_v, &bad_proof)
+            .is_err());
+    }
+}
+
+#[test]
+fn test_invalid_batch() {
+    let config = Config::m31_config();
+    assert_eq!(BatchLayout::new::<M31>(&[]).var_num, 0);
+    let mut prover = Prover::<M31, RawCommitmentProver<_>>::new(&config, ());
+    assert_eq!(
+        prover.prove_batch(&[]).unwrap_err(),
+        ProverError::InvalidStatement("the batch is empty")
+    );
+    let verifier = Verifier::<M31, RawCommitmentVerifier<_>>::new(&config, ());
+    assert_eq!(
+        verifier.verify_batch(&[], &[], &Default::default()),
+        Err(VerificationError::InvalidStatement("the batch is empty"))
+    );
+
+    let mut zk_config = Config::bn254_config();
+    zk_config.zero_knowledge = true;
+    let circuits = load_circuits::<Fr>();
+    let circuits: Vec<&Circuit<Fr>> = circuits.iter().collect();
+    let pp = DeepFoldParam {
+        mult_subgroups: vec![],
+        variable_num: 0,
+        query_num: 0,
+    };
+    let mut prover = Prover::<Fr, HidingDeepFoldProver<_>>::new(&zk_config, pp);
+    assert!(matches!(
+        prover.prove_batch(&circuits),
+        Err(ProverError::InvalidStatement(_))
+    ));
+}
+
+#[test]
+fn test_batch_allocates_scratchpads() {
+    let config = Config::m31_config();
+    let circuits = load_circuits::<M31>();
+    let circuits: Vec<&Circuit<M31>> = circuits.iter().collect();
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    // prepared for the small circuit only, or not at all
+    prover.prepare_mem_batch(&circuits[1..2]);
+    let (claimed_v, proof) = prover.prove_batch(&circuits).unwrap();
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify_batch(&circuits, &claimed_v, &proof).is_ok());
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    let (other_claimed_v, other_proof) = prover.prove_batch(&circuits).unwrap();
+    assert_eq!(other_claimed_v, claimed_v);
+    assert_eq!(other_proof.bytes, proof.bytes);
+}
+
+#[test]
+fn test_verify_many() {
+    let config = Config::m31_config();
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let mut proofs: Vec<_> = (0..6)
+        .map(|_| {
+            circuit.set_random_bool_input_for_test();
+            circuit.evaluate();
+            prover.prove(&circuit)
+        })
+        .collect();
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier
+        .verify_many(&circuit, &proofs)
+        .iter()
+        .all(|result| result.is_ok()));
+
+    // only the tampered proofs are reported
+    proofs[1].0[0] += M31Ext3::one();
+    proofs[4].1.bytes.pop();
+    let results = verifier.verify_many(&circuit, &proofs);
+    let failed: Vec<_> = results
+        .iter()
+        .enumerate()
+        .filter_map(|(k, result)| result.is_err().then_some(k))
+        .collect();
+    assert_eq!(failed, vec![1, 4]);
+    for ((claimed_v, proof), result) in proofs.iter().zip(&results) {
+        assert_eq!(&verifier.verify(&circuit, claimed_v, proof), result);
+    }
+    let vk = VerifyingKey::new(&circuit);
+    assert_eq!(verifier.verify_many_with_key(&vk, &proofs), results);
+}
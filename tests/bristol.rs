@@ -0,0 +1,177 @@
+use arith::{Field, M31};
+use expander_rs::{
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    BristolCircuit, BristolError, BristolGate, Config, Prover, Verifier,
+};
+use halo2curves::bn256::Fr;
+use rand::Rng;
+
+/// 4-bit ripple carry adder: inputs a (wires 0..4) and b (wires 4..8), 5 output bits, all
+/// least significant bit first.
+const ADDER_4: &str = "22 30
+2 4 4
+1 5
+
+2 1 0 4 8 XOR
+2 1 0 4 9 AND
+2 1 1 5 10 XOR
+2 1 10 9 11 XOR
+2 1 1 5 12 AND
+2 1 9 10 13 AND
+2 1 12 13 14 XOR
+2 1 2 6 15 XOR
+2 1 15 14 16 XOR
+2 1 2 6 17 AND
+2 1 14 15 18 AND
+2 1 17 18 19 XOR
+2 1 3 7 20 XOR
+2 1 20 19 21 XOR
+2 1 3 7 22 AND
+2 1 19 20 23 AND
+2 1 22 23 24 XOR
+1 1 8 25 EQW
+1 1 11 26 EQW
+1 1 16 27 EQW
+1 1 21 28 EQW
+1 1 24 29 EQW
+";
+
+fn to_bits(x: usize, len: usize) -> Vec<bool> {
+    (0..len).map(|i| (x >> i) & 1 == 1).collect()
+}
+
+fn assert_outputs_match<F: Field>(bristol: &BristolCircuit, inputs: &[bool]) {
+    let mut circuit = bristol.to_circuit::<F>();
+    circuit.set_bool_input(inputs);
+    circuit.evaluate();
+    let expected: Vec<F> = bristol
+        .evaluate(inputs)
+        .into_iter()
+        .map(|b| F::from(b as u32))
+        .collect();
+    let outputs = &circuit.layers.last().unwrap().output_vals.evals;
+    assert_eq!(outputs[..expected.len()], expected);
+    assert!(outputs[expected.len()..].iter().all(|o| o.is_zero()));
+}
+
+fn test_bristol_adder_helper<F: Field>() {
+    let bristol = BristolCircuit::parse(ADDER_4).unwrap();
+    assert_eq!(bristol.num_inputs(), 8);
+    assert_eq!(bristol.num_outputs(), 5);
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = [to_bits(a, 4), to_bits(b, 4)].concat();
+            assert_eq!(bristol.evaluate(&inputs), to_bits(a + b, 5));
+            assert_outputs_match::<F>(&bristol, &inputs);
+        }
+    }
+}
+
+#[test]
+fn test_bristol_adder() {
+    test_bristol_adder_helper::<M31>();
+    test_bristol_adder_helper::<Fr>();
+}
+
+fn gen_random_bristol(num_inputs: usize, num_gates: usize, num_outputs: usize) -> BristolCircuit {
+    let mut rng = rand::thread_rng();
+    let mut gates = vec![];
+    for out in num_inputs..num_inputs + num_gates {
+        let a = rng.gen_range(0..out);
+        let b = rng.gen_range(0..out);
+        gates.push(match rng.gen_range(0..5) {
+            0 => BristolGate::And { a, b, out },
+            1 => BristolGate::Xor { a, b, out },
+            2 => BristolGate::Inv { a, out },
+            3 => BristolGate::Eqw { a, out },
+            _ => BristolGate::Eq {
+                value: rng.gen(),
+                out,
+            },
+        });
+    }
+    // copy random wires to the output positions
+    let num_wires = num_inputs + num_gates + num_outputs;
+    for out in num_inputs + num_gates..num_wires {
+        gates.push(BristolGate::Eqw {
+            a: rng.gen_range(0..num_inputs + num_gates),
+            out,
+        });
+    }
+    BristolCircuit {
+        num_wires,
+        input_sizes: vec![num_inputs],
+        output_sizes: vec![num_outputs],
+        gates,
+    }
+}
+
+#[test]
+fn test_bristol_random() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..10 {
+        let bristol = gen_random_bristol(16, 200, 16);
+        let inputs: Vec<bool> = (0..16).map(|_| rng.gen()).collect();
+        assert_outputs_match::<M31>(&bristol, &inputs);
+        assert_outputs_match::<Fr>(&bristol, &inputs);
+    }
+}
+
+#[test]
+fn test_bristol_gkr() {
+    let bristol = BristolCircuit::parse(ADDER_4).unwrap();
+    let mut circuit = bristol.to_circuit::<Fr>();
+    circuit.set_bool_input(&[to_bits(11, 4), to_bits(6, 4)].concat());
+    circuit.evaluate();
+
+    let config = Config::bn254_config();
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+}
+
+#[test]
+fn test_bristol_malformed_files() {
+    let replace = |from: &str, to: &str| BristolCircuit::parse(&ADDER_4.replacen(from, to, 1));
+    assert!(matches!(
+        BristolCircuit::parse(""),
+        Err(BristolError::Truncated)
+    ));
+    assert!(matches!(
+        BristolCircuit::parse("22 30\n2 4 4\n"),
+        Err(BristolError::Truncated)
+    ));
+    assert!(matches!(
+        replace("22 30", "22 x"),
+        Err(BristolError::BadHeader)
+    ));
+    assert!(matches!(
+        replace("2 4 4", "2 4"),
+        Err(BristolError::Truncated)
+    ));
+    assert!(matches!(
+        replace("2 1 0 4 8 XOR", "2 1 0 4 8 OR"),
+        Err(BristolError::BadGate(_))
+    ));
+    assert!(matches!(
+        replace("2 1 0 4 8 XOR", "2 1 0 8 XOR"),
+        Err(BristolError::BadGate(_))
+    ));
+    assert!(matches!(
+        replace("2 1 0 4 8 XOR", "2 1 0 30 8 XOR"),
+        Err(BristolError::WireOutOfRange(30))
+    ));
+    assert!(matches!(
+        replace("22 30", "23 30"),
+        Err(BristolError::GateCountMismatch {
+            expected: 23,
+            found: 22
+        })
+    ));
+    assert!(matches!(
+        BristolCircuit::load("data/missing.txt"),
+        Err(BristolError::Io(_))
+    ));
+}
@@ -0,0 +1,105 @@
+use arith::{Field, MultiLinearPoly, M31};
+use expander_rs::{Circuit, CircuitLayer, GateAdd, GateConst, GateMul};
+use halo2curves::bn256::Fr;
+use rand::Rng;
+
+fn gen_random_circuit<F: Field>(layer_num: usize, var_num: usize) -> Circuit<F> {
+    let mut rng = rand::thread_rng();
+    let size = 1 << var_num;
+    let mut circuit = Circuit::default();
+    for _ in 0..layer_num {
+        let mut layer = CircuitLayer::<F> {
+            input_var_num: var_num,
+            output_var_num: var_num,
+            input_vals: MultiLinearPoly {
+                var_num,
+                evals: vec![],
+            },
+            ..Default::default()
+        };
+        for _ in 0..size * 2 {
+            layer.mul.push(GateMul {
+                i_ids: [rng.gen_range(0..size), rng.gen_range(0..size)],
+                o_id: rng.gen_range(0..size),
+                coef: F::BaseField::from(rng.gen_range(1..16)),
+            });
+            layer.add.push(GateAdd {
+                i_ids: [rng.gen_range(0..size)],
+                o_id: rng.gen_range(0..size),
+                coef: F::BaseField::from(rng.gen_range(1..16)),
+            });
+        }
+        for _ in 0..size / 4 {
+            layer.const_.push(GateConst {
+                i_ids: [],
+                o_id: rng.gen_range(0..size),
+                coef: F::BaseField::from(rng.gen_range(1..16)),
+            });
+        }
+        circuit.layers.push(layer);
+    }
+    circuit
+}
+
+fn test_parallel_evaluation_helper<F: Field>() {
+    let mut circuit = gen_random_circuit::<F>(4, 12);
+    circuit.set_random_bool_input_for_test();
+    let mut circuit_parallel = circuit.clone();
+
+    circuit.evaluate();
+    circuit_parallel.evaluate_parallel();
+    for (layer, layer_parallel) in circuit.layers.iter().zip(circuit_parallel.layers.iter()) {
+        assert_eq!(layer.input_vals.evals, layer_parallel.input_vals.evals);
+    }
+    assert_eq!(
+        circuit.layers.last().unwrap().output_vals.evals,
+        circuit_parallel.layers.last().unwrap().output_vals.evals
+    );
+}
+
+#[test]
+fn test_parallel_evaluation() {
+    test_parallel_evaluation_helper::<M31>();
+    test_parallel_evaluation_helper::<Fr>();
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[test]
+fn test_packed_evaluation() {
+    use arith::PackedM31;
+
+    const LANES: usize = PackedM31::PACK_SIZE;
+
+    let circuit = gen_random_circuit::<M31>(3, 10);
+    let mut rng = rand::thread_rng();
+    let mut circuits = vec![circuit; LANES];
+    for circuit in circuits.iter_mut() {
+        circuit.layers[0].input_vals.evals = (0..1 << circuit.log_input_size())
+            .map(|_| M31::random_bool_unsafe(&mut rng))
+            .collect();
+    }
+
+    // lane j of the packed input holds the input of circuits[j]
+    let mut packed = circuits[0].to_field::<PackedM31>();
+    packed.layers[0].input_vals.evals = (0..1 << packed.log_input_size())
+        .map(|i| {
+            PackedM31::pack(&std::array::from_fn(|j| {
+                circuits[j].layers[0].input_vals.evals[i]
+            }))
+        })
+        .collect();
+
+    packed.evaluate_parallel();
+    circuits.iter_mut().for_each(|c| c.evaluate());
+
+    let packed_outputs = &packed.layers.last().unwrap().output_vals.evals;
+    for (i, packed_output) in packed_outputs.iter().enumerate() {
+        let lanes = packed_output.unpack();
+        for (j, circuit) in circuits.iter().enumerate() {
+            assert_eq!(
+                lanes[j],
+                circuit.layers.last().unwrap().output_vals.evals[i]
+            );
+        }
+    }
+}
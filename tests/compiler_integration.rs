@@ -1,8 +1,10 @@
 use std::fs;
 
-use arith::{Field, M31};
+use arith::{Field, M31Ext3, M31};
 use expander_rs::{
-    raw::RawCommitmentProver, raw::RawCommitmentVerifier, Circuit, Config, Prover, Verifier,
+    raw::RawCommitmentProver, raw::RawCommitmentVerifier, Allocation, Circuit, CircuitDebugger,
+    Config, GateAdd, GateConst, GateMul, GateType, Prover, RecursiveCircuit, Segment, Verifier,
+    VerifyingKey, Wiring,
 };
 use rand::Rng;
 
@@ -36,13 +38,197 @@ fn test_compiler_format_integration() {
 
     let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
     println!("Verifier created.");
-    assert!(verifier.verify(&circuit, &claimed_v, &proof));
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
     println!("Correct proof verified.");
     let mut bad_proof = proof.clone();
     let rng = &mut rand::thread_rng();
     let random_idx = rng.gen_range(0..bad_proof.bytes.len());
     let random_change = rng.gen_range(1..256) as u8;
     bad_proof.bytes[random_idx] ^= random_change;
-    assert!(!verifier.verify(&circuit, &claimed_v, &bad_proof));
+    assert!(verifier.verify(&circuit, &claimed_v, &bad_proof).is_err());
     println!("Bad proof rejected.");
 }
+
+#[test]
+fn test_compiler_format_debugger() {
+    let rc = RecursiveCircuit::<F>::load(FILENAME_CIRCUIT);
+    let mut circuit = rc.flatten();
+    circuit.load_witness_file(FILENAME_WITNESS);
+    // corrupt the witness
+    circuit.layers[0]
+        .input_vals
+        .evals
+        .iter_mut()
+        .for_each(|v| *v += F::one());
+    circuit.evaluate();
+
+    let debugger = CircuitDebugger::with_recursive_circuit(&circuit, &rc);
+    let diagnosis = debugger.diagnose(2);
+    let output_vals = &circuit.layers.last().unwrap().output_vals.evals;
+    let nonzero_outputs = output_vals.iter().filter(|v| !v.is_zero()).count();
+    assert!(nonzero_outputs > 0);
+    assert_eq!(diagnosis.len(), nonzero_outputs);
+
+    for output in diagnosis.iter() {
+        assert!(!output.value.is_zero());
+        let wire = &output.trace[0];
+        assert_eq!(wire.wire, output.o_id);
+        assert_eq!(wire.layer, circuit.layers.len() - 1);
+        assert_eq!(
+            wire.gates.iter().map(|g| g.contribution).sum::<F>(),
+            output.value
+        );
+        for gate in output.trace.iter().flat_map(|w| &w.gates) {
+            // the reported origin must reproduce the flattened gate
+            let origin = gate.origin.unwrap();
+            let segment = &rc.segments[origin.segment_id];
+            match gate.gate_type {
+                GateType::Mul => assert_eq!(
+                    segment.gate_muls[origin.local_idx].i_ids[0] + origin.allocation.i_offset,
+                    gate.i_ids[0]
+                ),
+                GateType::Add => assert_eq!(
+                    segment.gate_adds[origin.local_idx].i_ids[0] + origin.allocation.i_offset,
+                    gate.i_ids[0]
+                ),
+                GateType::Const => assert!(gate.i_ids.is_empty()),
+            }
+        }
+    }
+}
+
+/// The wiring of every layer of `vk` evaluates like the gates of the flattened circuit.
+fn check_segment_wiring(circuit: &Circuit<F>, vk: &VerifyingKey<F>) {
+    let mut rng = rand::thread_rng();
+    let mut random_point =
+        |n: usize| -> Vec<M31Ext3> { (0..n).map(|_| M31Ext3::random_unsafe(&mut rng)).collect() };
+    for (layer, wiring) in circuit.layers.iter().zip(vk.layers()) {
+        assert_eq!(
+            wiring.gate_num(),
+            layer.mul.len() + layer.add.len() + layer.const_.len()
+        );
+        let (gates, segments) = (Wiring::Gates(layer), Wiring::Sorted(wiring));
+        let rz0 = random_point(layer.output_var_num);
+        let rz1 = random_point(layer.output_var_num);
+        let rx = random_point(layer.input_var_num);
+        let ry = random_point(layer.input_var_num);
+        let (alpha, beta) = (M31Ext3::from(3), M31Ext3::from(5));
+        assert_eq!(
+            gates.eval_const(&rz0, &rz1, alpha, beta),
+            segments.eval_const(&rz0, &rz1, alpha, beta)
+        );
+        assert_eq!(
+            gates.eval_add(&rz0, &rz1, alpha, beta, &rx),
+            segments.eval_add(&rz0, &rz1, alpha, beta, &rx)
+        );
+        assert_eq!(
+            gates.eval_mul(&rz0, &rz1, alpha, beta, &rx, &ry),
+            segments.eval_mul(&rz0, &rz1, alpha, beta, &rx, &ry)
+        );
+    }
+}
+
+#[test]
+fn test_compiler_format_segment_wiring() {
+    let config = Config::bn254_config();
+    let rc = RecursiveCircuit::<F>::load(FILENAME_CIRCUIT);
+    let vk = VerifyingKey::from_recursive_circuit(&rc);
+    let mut circuit = rc.flatten();
+    check_segment_wiring(&circuit, &vk);
+    for wiring in vk.layers() {
+        assert!(wiring.unique_gate_num() < wiring.gate_num());
+    }
+    let vk = VerifyingKey::<F>::from_bytes(&vk.to_bytes()).unwrap();
+
+    circuit.load_witness_file(FILENAME_WITNESS);
+    circuit.evaluate();
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify_with_key(&vk, &claimed_v, &proof).is_ok());
+}
+
+#[test]
+fn test_data_parallel_segment_wiring() {
+    let leaf = Segment::<F> {
+        i_var_num: 2,
+        o_var_num: 1,
+        child_segs: vec![],
+        gate_muls: vec![GateMul {
+            i_ids: [0, 1],
+            o_id: 0,
+            coef: F::from(2),
+        }],
+        gate_adds: vec![GateAdd {
+            i_ids: [3],
+            o_id: 1,
+            coef: F::from(3),
+        }],
+        gate_consts: vec![GateConst {
+            i_ids: [],
+            o_id: 1,
+            coef: F::from(5),
+        }],
+    };
+    let unaligned_leaf = Segment::<F> {
+        i_var_num: 1,
+        o_var_num: 0,
+        child_segs: vec![],
+        gate_muls: vec![],
+        gate_adds: vec![GateAdd {
+            i_ids: [1],
+            o_id: 0,
+            coef: F::from(7),
+        }],
+        gate_consts: vec![],
+    };
+    // 16 data-parallel copies, two more at other strides, and a copy at odd offsets
+    let mut allocs: Vec<_> = (0..16)
+        .map(|k| Allocation {
+            i_offset: 64 + 4 * k,
+            o_offset: 32 + 2 * k,
+        })
+        .collect();
+    allocs.push(Allocation {
+        i_offset: 0,
+        o_offset: 0,
+    });
+    allocs.push(Allocation {
+        i_offset: 16,
+        o_offset: 4,
+    });
+    let layer = Segment::<F> {
+        i_var_num: 7,
+        o_var_num: 6,
+        child_segs: vec![
+            (0, allocs),
+            (
+                1,
+                vec![Allocation {
+                    i_offset: 3,
+                    o_offset: 1,
+                }],
+            ),
+        ],
+        gate_muls: vec![],
+        gate_adds: vec![],
+        gate_consts: vec![],
+    };
+    let rc = RecursiveCircuit {
+        segments: vec![leaf, unaligned_leaf, layer],
+        layers: vec![2],
+    };
+
+    let vk = VerifyingKey::from_recursive_circuit(&rc);
+    check_segment_wiring(&rc.flatten(), &vk);
+    // the 16 copies are read as one run, and so are the two others
+    let wiring = &vk.layers()[0];
+    assert_eq!(wiring.gate_num(), 18 * 3 + 1);
+    assert_eq!(wiring.unique_gate_num(), 1 + 3 + 2);
+    let bytes = vk.to_bytes();
+    assert_eq!(
+        VerifyingKey::<F>::from_bytes(&bytes).unwrap().to_bytes(),
+        bytes
+    );
+}
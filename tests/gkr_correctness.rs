@@ -1,6 +1,7 @@
-use arith::{mul_group::Radix2Group, Field, FieldSerde, TwoAdicField};
+use arith::{mul_group::Radix2Group, Field, FieldSerde, M31Ext3, TwoAdicField, M31};
 use expander_rs::{
     deepfold::{DeepFoldParam, DeepFoldProver, DeepFoldVerifier},
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
     Circuit, CircuitLayer, Config, GateAdd, GateMul, Prover, Verifier,
 };
 use halo2curves::bn256::Fr;
@@ -52,7 +53,7 @@ fn test_gkr_correctness() {
 
 fn test_gkr_correctness_helper<F>(config: &Config)
 where
-    F: TwoAdicField + FieldSerde,
+    F: TwoAdicField<ChallengeField = F> + FieldSerde,
 {
     println!("Config created.");
     let mut circuit = Circuit::<F>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
@@ -113,13 +114,153 @@ where
     // Verify
     let verifier = Verifier::<_, DeepFoldVerifier<_>>::new(&config, pp);
     println!("Verifier created.");
-    assert!(verifier.verify(&circuit, &claimed_v, &proof));
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
     println!("Correct proof verified.");
     let mut bad_proof = proof.clone();
     let rng = &mut rand::thread_rng();
     let random_idx = rng.gen_range(0..bad_proof.bytes.len());
     let random_change = rng.gen_range(1..256) as u8;
     bad_proof.bytes[random_idx] ^= random_change;
-    assert!(!verifier.verify(&circuit, &claimed_v, &bad_proof));
+    assert!(verifier.verify(&circuit, &claimed_v, &bad_proof).is_err());
     println!("Bad proof rejected.");
 }
+
+fn test_multi_threaded_proof_helper<F: Field + FieldSerde>(config: &Config) {
+    let mut circuit = Circuit::<F>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+
+    let mut proofs = vec![];
+    for num_threads in [1, 4] {
+        let mut config = config.clone();
+        config.num_threads = num_threads;
+        let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+        prover.prepare_mem(&circuit);
+        proofs.push(prover.prove(&circuit));
+    }
+    assert_eq!(proofs[0].0, proofs[1].0);
+    assert_eq!(proofs[0].1.bytes, proofs[1].1.bytes);
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(config, ());
+    assert!(verifier
+        .verify(&circuit, &proofs[1].0, &proofs[1].1)
+        .is_ok());
+}
+
+#[test]
+fn test_multi_threaded_proof() {
+    test_multi_threaded_proof_helper::<M31>(&Config::m31_config());
+    test_multi_threaded_proof_helper::<Fr>(&Config::bn254_config());
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[test]
+fn test_gkr_correctness_packed() {
+    use arith::PackedM31;
+
+    // every lane holds an independent witness of the same circuit
+    let config = Config::m31_config();
+    let mut circuit = Circuit::<PackedM31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    // challenges are in M31 for packed circuits, hence three repetitions
+    assert_eq!(claimed_v.len(), 3);
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+
+    // a wrong claim in a single lane is rejected
+    let mut lanes = claimed_v[0].unpack();
+    lanes[1] += M31::one();
+    let mut bad_claimed_v = claimed_v.clone();
+    bad_claimed_v[0] = PackedM31::pack(&lanes);
+    assert!(verifier.verify(&circuit, &bad_claimed_v, &proof).is_err());
+}
+
+#[test]
+fn test_gkr_correctness_m31_ext3_challenges() {
+    // a single repetition is enough with challenges in the degree 3 extension
+    let config = Config::m31_ext3_config();
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    assert_eq!(claimed_v.len(), 1);
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+
+    let mut bad_claimed_v = claimed_v.clone();
+    bad_claimed_v[0] += M31Ext3::one();
+    assert!(verifier.verify(&circuit, &bad_claimed_v, &proof).is_err());
+}
+
+#[test]
+fn test_checkpointed_proof() {
+    let config = Config::m31_config();
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    let mut full = circuit.clone();
+    full.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&full);
+    let (claimed_v, proof) = prover.prove(&full);
+    // challenges are in M31Ext3 for M31 circuits, hence a single repetition
+    assert_eq!(claimed_v.len(), 1);
+
+    // the same proof whatever the layers kept
+    for interval in [1, 2, 3, circuit.layers.len()] {
+        let mut lean = circuit.clone();
+        lean.evaluate_checkpointed(interval);
+        for (i, layer) in lean.layers.iter().enumerate() {
+            assert_eq!(layer.input_vals.evals.is_empty(), i % interval != 0);
+        }
+        assert_eq!(
+            lean.layers.last().unwrap().output_vals.evals,
+            full.layers.last().unwrap().output_vals.evals
+        );
+        let (lean_claimed_v, lean_proof) = prover.prove(&lean);
+        assert_eq!(lean_claimed_v, claimed_v);
+        assert_eq!(lean_proof.bytes, proof.bytes);
+    }
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify(&full, &claimed_v, &proof).is_ok());
+}
+
+#[test]
+fn test_grinding_nonce() {
+    let config = Config::m31_config();
+    assert!(config.grinding_bits > 0);
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof, stats) = prover.prove_with_stats(&circuit);
+    assert_eq!(stats.grind.bytes, 8);
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+
+    // the prover sends the smallest valid nonce, so any smaller one is rejected
+    let nonce_bytes = stats.commit.bytes..stats.commit.bytes + 8;
+    let nonce = u64::from_le_bytes(proof.bytes[nonce_bytes.clone()].try_into().unwrap());
+    for bad_nonce in [0, nonce / 2, nonce.saturating_sub(1)] {
+        if bad_nonce == nonce {
+            continue;
+        }
+        let mut bad_proof = proof.clone();
+        bad_proof.bytes[nonce_bytes.clone()].copy_from_slice(&bad_nonce.to_le_bytes());
+        assert!(verifier.verify(&circuit, &claimed_v, &bad_proof).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use arith::{mul_group::Radix2Group, Field, FieldSerde, M31};
+use expander_rs::{
+    deepfold::{
+        DeepFoldParam, DeepFoldProver, DeepFoldVerifier, HidingDeepFoldProver,
+        HidingDeepFoldVerifier,
+    },
+    estimate_proof_cost,
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    Circuit, Config, LookupColumn, LookupLayout, Prover, ProverError, VerificationError, Verifier,
+};
+use halo2curves::bn256::Fr;
+
+const FILENAME_MUL: &str = "data/ExtractedCircuitMul.txt";
+const FILENAME_ADD: &str = "data/ExtractedCircuitAdd.txt";
+
+/// The extracted circuit with boolean inputs, all of them range checked.
+fn load_circuit<F: Field + FieldSerde>() -> Circuit<F> {
+    let mut circuit = Circuit::<F>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+    let input_num = 1 << circuit.log_input_size();
+    circuit.lookups = vec![
+        // all the inputs are bits
+        LookupColumn {
+            input_ids: (0..input_num).collect(),
+            table: vec![F::BaseField::zero(), F::BaseField::one()],
+        },
+        // a few of them are 4-bit values, looked up twice
+        LookupColumn {
+            input_ids: [0, 5, 7, 5, 100].repeat(2),
+            table: (0..16).map(F::BaseField::from).collect(),
+        },
+    ];
+    circuit
+}
+
+#[test]
+fn test_lookup_raw() {
+    let config = Config::m31_config();
+    let circuit = load_circuit::<M31>();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof, stats) = prover.prove_with_stats(&circuit);
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+
+    let estimate = estimate_proof_cost::<M31, RawCommitmentVerifier<M31>>(&circuit, &config, &());
+    assert_eq!(stats.lookups.bytes, estimate.lookup_bytes);
+    assert_eq!(estimate.proof_bytes(), proof.bytes.len());
+
+    // the proof does not hold for another table
+    let mut other_table = circuit.clone();
+    other_table.lookups[1].table[0] = M31::from(16);
+    assert!(verifier.verify(&other_table, &claimed_v, &proof).is_err());
+
+    // nor for other looked up values
+    let input = &circuit.layers[0].input_vals.evals;
+    let mut other_column = circuit.clone();
+    other_column.lookups[1].input_ids[1] = (0..).find(|&i| input[i] != input[5]).unwrap();
+    assert!(verifier.verify(&other_column, &claimed_v, &proof).is_err());
+}
+
+#[test]
+fn test_lookup_deepfold() {
+    let config = Config::bn254_config();
+    let circuit = load_circuit::<Fr>();
+
+    // the multiplicities are committed along with the input
+    let variable_num = LookupLayout::new(&circuit).var_num;
+    assert_eq!(variable_num, circuit.log_input_size() + 1);
+    let mut mult_subgroups = vec![Radix2Group::<Fr>::new(variable_num as u32 + 3)];
+    for i in 1..variable_num {
+        mult_subgroups.push(mult_subgroups[i - 1].exp(2));
+    }
+    let pp = DeepFoldParam {
+        mult_subgroups,
+        variable_num,
+        query_num: 30,
+    };
+
+    let mut prover = Prover::<_, DeepFoldProver<_>>::new(&config, pp.clone());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+
+    let verifier = Verifier::<_, DeepFoldVerifier<_>>::new(&config, pp);
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+}
+
+#[test]
+fn test_lookup_missing_value() {
+    let mut circuit = load_circuit::<M31>();
+    circuit.layers[0].input_vals.evals[7] = M31::from(2);
+
+    let config = Config::m31_config();
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    assert_eq!(
+        prover.try_prove(&circuit).unwrap_err(),
+        ProverError::NotInLookupTable {
+            column: 0,
+            input_id: 7
+        }
+    );
+
+    // in the second column only, on its second value
+    let input = &mut circuit.layers[0].input_vals.evals;
+    (input[0], input[5], input[7]) = (M31::zero(), M31::one(), M31::one());
+    circuit.lookups[1].table.truncate(1);
+    assert_eq!(
+        prover.try_prove(&circuit).unwrap_err(),
+        ProverError::NotInLookupTable {
+            column: 1,
+            input_id: 5
+        }
+    );
+}
+
+#[test]
+fn test_lookup_zero_knowledge() {
+    let circuit = load_circuit::<Fr>();
+    let mut config = Config::bn254_config();
+    config.zero_knowledge = true;
+    let pp = DeepFoldParam {
+        mult_subgroups: vec![],
+        variable_num: 0,
+        query_num: 0,
+    };
+    let reason = "lookups are not supported in zero-knowledge mode";
+    let mut prover = Prover::<_, HidingDeepFoldProver<_>>::new(&config, pp.clone());
+    assert_eq!(
+        prover.try_prove(&circuit).unwrap_err(),
+        ProverError::InvalidStatement(reason)
+    );
+    let verifier = Verifier::<_, HidingDeepFoldVerifier<_>>::new(&config, pp);
+    assert_eq!(
+        verifier.verify(&circuit, &[Fr::zero()], &Default::default()),
+        Err(VerificationError::InvalidStatement(reason))
+    );
+}
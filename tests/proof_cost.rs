@@ -0,0 +1,130 @@
+use arith::{mul_group::Radix2Group, Field, FieldSerde, TwoAdicField, M31};
+use expander_rs::{
+    deepfold::{
+        hiding_variable_num, DeepFoldParam, DeepFoldProver, DeepFoldVerifier, HidingDeepFoldProver,
+        HidingDeepFoldVerifier,
+    },
+    estimate_proof_cost,
+    mimc_deepfold::{MiMC7DeepFoldProver, MiMC7DeepFoldVerifier},
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    Circuit, Config, PolyCommitProver, PolyCommitVerifier, Prover, ZkLayout,
+};
+use halo2curves::bn256::Fr;
+
+const FILENAME_MUL: &str = "data/ExtractedCircuitMul.txt";
+const FILENAME_ADD: &str = "data/ExtractedCircuitAdd.txt";
+
+fn prove_and_estimate<F, PCP, PCV>(config: &Config, pp: PCP::Param) -> (usize, usize)
+where
+    F: Field + FieldSerde,
+    PCP: PolyCommitProver<F>,
+    PCV: PolyCommitVerifier<F, Param = PCP::Param>,
+{
+    let mut circuit = Circuit::<F>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+
+    let estimate = estimate_proof_cost::<F, PCV>(&circuit, config, &pp);
+    assert_eq!(estimate.sumcheck_bytes.len(), circuit.layers.len());
+    assert!(estimate.field_ops > 0);
+
+    let mut prover = Prover::<F, PCP>::new(config, pp);
+    prover.prepare_mem(&circuit);
+    let (_, proof, stats) = prover.prove_with_stats(&circuit);
+
+    // everything but the opening is measured exactly as estimated
+    assert_eq!(stats.proof_bytes(), proof.bytes.len());
+    assert_eq!(stats.commit.bytes, estimate.commitment_bytes);
+    assert_eq!(stats.grind.bytes, estimate.grinding_bytes);
+    assert_eq!(stats.layers.len(), circuit.layers.len());
+    for (i, layer) in stats.layers.iter().enumerate() {
+        assert_eq!(
+            layer.sumcheck.bytes,
+            estimate.sumcheck_bytes[i] + estimate.claim_bytes[i]
+        );
+        assert_eq!(layer.mul_gates, circuit.layers[i].mul.len());
+        assert_eq!(layer.add_gates, circuit.layers[i].add.len());
+    }
+    assert_eq!(stats.merge.bytes, estimate.merge_bytes);
+    assert_eq!(
+        stats.scratchpad_allocated_bytes,
+        estimate.scratchpad_allocated_bytes
+    );
+    assert!(stats.total_time >= stats.layers.iter().map(|l| l.sumcheck.time).sum());
+
+    (estimate.proof_bytes(), proof.bytes.len())
+}
+
+#[test]
+fn test_proof_cost_raw() {
+    let (estimated, actual) = prove_and_estimate::<
+        M31,
+        RawCommitmentProver<M31>,
+        RawCommitmentVerifier<M31>,
+    >(&Config::m31_config(), ());
+    assert_eq!(estimated, actual);
+
+    let (estimated, actual) = prove_and_estimate::<
+        Fr,
+        RawCommitmentProver<Fr>,
+        RawCommitmentVerifier<Fr>,
+    >(&Config::bn254_config(), ());
+    assert_eq!(estimated, actual);
+}
+
+fn deepfold_param<F: TwoAdicField + FieldSerde>(
+    variable_num: usize,
+    query_num: usize,
+) -> DeepFoldParam<F> {
+    let mut mult_subgroups = vec![Radix2Group::<F>::new(variable_num as u32 + 3)];
+    for i in 1..variable_num {
+        mult_subgroups.push(mult_subgroups[i - 1].exp(2));
+    }
+    DeepFoldParam {
+        mult_subgroups,
+        variable_num,
+        query_num,
+    }
+}
+
+#[test]
+fn test_proof_cost_deepfold() {
+    let variable_num =
+        Circuit::<Fr>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD).log_input_size();
+    let pp = deepfold_param::<Fr>(variable_num, 30);
+    let (estimated, actual) = prove_and_estimate::<Fr, DeepFoldProver<Fr>, DeepFoldVerifier<Fr>>(
+        &Config::bn254_config(),
+        pp,
+    );
+    // queries may collide, so the opening is only bounded from above
+    assert!(actual <= estimated);
+    // everything but the merkle paths and queried values is deterministic
+    assert!(estimated - actual < estimated / 2);
+}
+
+#[test]
+fn test_proof_cost_mimc7_deepfold() {
+    let variable_num =
+        Circuit::<Fr>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD).log_input_size();
+    let pp = deepfold_param::<Fr>(variable_num, 30);
+    // the queries are opened one by one, whether they collide or not
+    let (estimated, actual) = prove_and_estimate::<Fr, MiMC7DeepFoldProver, MiMC7DeepFoldVerifier>(
+        &Config::bn254_config(),
+        pp,
+    );
+    assert_eq!(estimated, actual);
+}
+
+#[test]
+fn test_proof_cost_zero_knowledge() {
+    let mut config = Config::bn254_config();
+    config.zero_knowledge = true;
+    let circuit = Circuit::<Fr>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    let reps = config.for_challenge_field::<Fr>().get_num_repetitions();
+    let variable_num = hiding_variable_num(ZkLayout::new(&circuit, reps).var_num, 30);
+    let pp = deepfold_param::<Fr>(variable_num, 30);
+    let (estimated, actual) =
+        prove_and_estimate::<Fr, HidingDeepFoldProver<Fr>, HidingDeepFoldVerifier<Fr>>(&config, pp);
+    assert!(actual <= estimated);
+    assert!(estimated - actual < estimated / 2);
+}
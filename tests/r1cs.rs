@@ -0,0 +1,214 @@
+use arith::{Field, FieldSerde};
+use expander_rs::{
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    Config, LinearCombination, Prover, R1cs, R1csConstraint, R1csError, Verifier, SENTINEL_BN254,
+};
+use halo2curves::bn256::Fr;
+
+fn write_section(bytes: &mut Vec<u8>, section_type: u32, content: &[u8]) {
+    bytes.extend(section_type.to_le_bytes());
+    bytes.extend((content.len() as u64).to_le_bytes());
+    bytes.extend(content);
+}
+
+fn write_fr(bytes: &mut Vec<u8>, f: &Fr) {
+    let mut buffer = [0u8; Fr::SIZE];
+    f.serialize_into(&mut buffer);
+    bytes.extend(buffer);
+}
+
+fn write_header(bytes: &mut Vec<u8>, magic: &[u8; 4], version: u32) {
+    bytes.extend(magic);
+    bytes.extend(version.to_le_bytes());
+    bytes.extend(2u32.to_le_bytes());
+}
+
+fn write_prime(bytes: &mut Vec<u8>) {
+    bytes.extend((Fr::SIZE as u32).to_le_bytes());
+    bytes.extend(SENTINEL_BN254);
+}
+
+fn encode_r1cs(r1cs: &R1cs) -> Vec<u8> {
+    let mut header = vec![];
+    write_prime(&mut header);
+    for n in [
+        r1cs.num_wires,
+        r1cs.num_pub_out,
+        r1cs.num_pub_in,
+        r1cs.num_prv_in,
+    ] {
+        header.extend((n as u32).to_le_bytes());
+    }
+    header.extend(0u64.to_le_bytes());
+    header.extend((r1cs.constraints.len() as u32).to_le_bytes());
+
+    let mut body = vec![];
+    for constraint in &r1cs.constraints {
+        for lc in [&constraint.a, &constraint.b, &constraint.c] {
+            body.extend((lc.len() as u32).to_le_bytes());
+            for (wire_id, coef) in lc {
+                body.extend((*wire_id as u32).to_le_bytes());
+                write_fr(&mut body, coef);
+            }
+        }
+    }
+
+    let mut bytes = vec![];
+    write_header(&mut bytes, b"r1cs", 1);
+    // sections are not required to be in order
+    write_section(&mut bytes, 2, &body);
+    write_section(&mut bytes, 1, &header);
+    bytes
+}
+
+fn encode_wtns(witness: &[Fr]) -> Vec<u8> {
+    let mut header = vec![];
+    write_prime(&mut header);
+    header.extend((witness.len() as u32).to_le_bytes());
+
+    let mut data = vec![];
+    witness.iter().for_each(|w| write_fr(&mut data, w));
+
+    let mut bytes = vec![];
+    write_header(&mut bytes, b"wtns", 2);
+    write_section(&mut bytes, 1, &header);
+    write_section(&mut bytes, 2, &data);
+    bytes
+}
+
+fn lc(terms: &[(usize, u32)]) -> LinearCombination {
+    terms.iter().map(|(w, c)| (*w, Fr::from(*c))).collect()
+}
+
+/// x^3 + x + 5 = out, with wires [1, out, x, x^2, x^3]
+fn cubic_r1cs() -> R1cs {
+    R1cs {
+        num_wires: 5,
+        num_pub_out: 1,
+        num_pub_in: 0,
+        num_prv_in: 1,
+        constraints: vec![
+            R1csConstraint {
+                a: lc(&[(2, 1)]),
+                b: lc(&[(2, 1)]),
+                c: lc(&[(3, 1)]),
+            },
+            R1csConstraint {
+                a: lc(&[(3, 1)]),
+                b: lc(&[(2, 1)]),
+                c: lc(&[(4, 1)]),
+            },
+            R1csConstraint {
+                a: lc(&[(4, 1), (2, 1), (0, 5)]),
+                b: lc(&[(0, 1)]),
+                c: lc(&[(1, 1)]),
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_r1cs_import() {
+    let r1cs = R1cs::read(&encode_r1cs(&cubic_r1cs())).unwrap();
+    assert_eq!(r1cs.num_wires, 5);
+    assert_eq!(r1cs.constraints.len(), 3);
+    assert_eq!(r1cs.constraints[2].a, cubic_r1cs().constraints[2].a);
+
+    let witness: Vec<Fr> = [1u32, 35, 3, 9, 27].into_iter().map(Fr::from).collect();
+    assert_eq!(R1cs::read_witness(&encode_wtns(&witness)).unwrap(), witness);
+    assert!(r1cs.is_satisfied(&witness));
+
+    let mut circuit = r1cs.to_circuit();
+    circuit.set_r1cs_witness(&witness);
+    circuit.evaluate();
+    let outputs = &circuit.layers.last().unwrap().output_vals.evals;
+    assert!(outputs.iter().all(|o| o.is_zero()));
+
+    let config = Config::bn254_config();
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+
+    // a wrong x^3 breaks the second and third constraints only
+    let mut bad_witness = witness.clone();
+    bad_witness[4] = Fr::from(28u32);
+    assert!(!r1cs.is_satisfied(&bad_witness));
+    circuit.set_r1cs_witness(&bad_witness);
+    circuit.evaluate();
+    let outputs = &circuit.layers.last().unwrap().output_vals.evals;
+    assert!(outputs[0].is_zero());
+    assert!(!outputs[1].is_zero());
+    assert!(!outputs[2].is_zero());
+    assert!(outputs[3].is_zero());
+}
+
+#[test]
+fn test_r1cs_constant_wire() {
+    let r1cs = cubic_r1cs();
+    let mut circuit = r1cs.to_circuit();
+
+    // every linear combination vanishes on the zero witness, only wire 0 catches it
+    let zero_witness = vec![Fr::zero(); r1cs.num_wires];
+    assert!(r1cs.is_satisfied(&zero_witness));
+    circuit.set_r1cs_witness(&zero_witness);
+    circuit.evaluate();
+    let outputs = &circuit.layers.last().unwrap().output_vals.evals;
+    assert!(outputs[..3].iter().all(|o| o.is_zero()));
+    assert!(!outputs[3].is_zero());
+
+    // with x = 0 and w_0 = 2, (x^3 + x + 5 w_0) w_0 = 20 satisfies the constraints
+    let witness: Vec<Fr> = [2u32, 20, 0, 0, 0].into_iter().map(Fr::from).collect();
+    assert!(r1cs.is_satisfied(&witness));
+    circuit.set_r1cs_witness(&witness);
+    circuit.evaluate();
+    let outputs = &circuit.layers.last().unwrap().output_vals.evals;
+    assert!(outputs[..3].iter().all(|o| o.is_zero()));
+    assert!(!outputs[3].is_zero());
+}
+
+#[test]
+fn test_r1cs_malformed_files() {
+    let bytes = encode_r1cs(&cubic_r1cs());
+    assert!(matches!(
+        R1cs::read(&bytes[..bytes.len() - 1]),
+        Err(R1csError::Truncated)
+    ));
+    assert!(matches!(
+        R1cs::read(&[bytes.as_slice(), &[0]].concat()),
+        Err(R1csError::TrailingBytes)
+    ));
+    assert!(matches!(R1cs::read(&[]), Err(R1csError::Truncated)));
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] ^= 1;
+    assert!(matches!(R1cs::read(&bad_magic), Err(R1csError::BadMagic)));
+
+    let mut bad_wire = cubic_r1cs();
+    bad_wire.constraints[0].a = lc(&[(5, 1)]);
+    assert!(matches!(
+        R1cs::read(&encode_r1cs(&bad_wire)),
+        Err(R1csError::WireOutOfRange(5))
+    ));
+
+    let witness: Vec<Fr> = [1u32, 35, 3, 9, 27].into_iter().map(Fr::from).collect();
+    let mut bad_prime = encode_wtns(&witness);
+    // magic, version, section count, section type and size, field size
+    bad_prime[4 + 4 + 4 + 4 + 8 + 4] ^= 1;
+    assert!(matches!(
+        R1cs::read_witness(&bad_prime),
+        Err(R1csError::UnsupportedField)
+    ));
+    let mut bad_element = encode_wtns(&witness);
+    let len = bad_element.len();
+    bad_element[len - Fr::SIZE..].fill(0xff);
+    assert!(matches!(
+        R1cs::read_witness(&bad_element),
+        Err(R1csError::InvalidFieldElement)
+    ));
+    assert!(matches!(
+        R1cs::load_witness("data/missing.wtns"),
+        Err(R1csError::Io(_))
+    ));
+}
@@ -0,0 +1,261 @@
+use arith::{mul_group::Radix2Group, Field, FieldSerde};
+use expander_rs::{
+    deepfold::{DeepFoldParam, DeepFoldProver},
+    mimc_deepfold::{MiMC7DeepFoldProver, MiMC7DeepFoldVerifier},
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    Circuit, CircuitLayer, Config, FiatShamirHashType, GateAdd, GateConst, GateMul, Proof, Prover,
+    RecursiveCommitment, VerificationError, Verifier, VerifierCircuit, GRINDING_NONCE_BYTES,
+};
+use halo2curves::bn256::Fr;
+
+fn gate_layer(
+    input_var_num: usize,
+    output_var_num: usize,
+    mul: &[([usize; 2], usize)],
+    add: &[(usize, usize)],
+) -> CircuitLayer<Fr> {
+    let mut layer = CircuitLayer::<Fr> {
+        input_var_num,
+        output_var_num,
+        ..Default::default()
+    };
+    layer.input_vals.var_num = input_var_num;
+    layer.mul = mul
+        .iter()
+        .map(|&(i_ids, o_id)| GateMul {
+            i_ids,
+            o_id,
+            coef: Fr::from(2u32),
+        })
+        .collect();
+    layer.add = add
+        .iter()
+        .map(|&(i_id, o_id)| GateAdd {
+            i_ids: [i_id],
+            o_id,
+            coef: Fr::from(3u32),
+        })
+        .collect();
+    layer.const_.push(GateConst {
+        i_ids: [],
+        o_id: 1,
+        coef: Fr::from(5u32),
+    });
+    layer
+}
+
+fn gen_target_circuit() -> Circuit<Fr> {
+    let mut circuit = Circuit::default();
+    circuit.layers.push(gate_layer(
+        2,
+        2,
+        &[([0, 1], 0), ([2, 3], 1), ([3, 3], 3)],
+        &[(0, 2), (1, 2), (2, 3)],
+    ));
+    circuit.layers.push(gate_layer(
+        2,
+        2,
+        &[([0, 2], 0), ([1, 3], 2)],
+        &[(3, 1), (0, 3), (1, 3)],
+    ));
+    circuit
+}
+
+fn mimc7_config() -> Config {
+    let mut config = Config::bn254_config();
+    config.fs_hash = FiatShamirHashType::MIMC7;
+    config
+}
+
+fn deepfold_param(variable_num: usize, query_num: usize) -> DeepFoldParam<Fr> {
+    let mut mult_subgroups = vec![Radix2Group::<Fr>::new(variable_num as u32 + 3)];
+    for i in 1..variable_num {
+        mult_subgroups.push(mult_subgroups[i - 1].exp(2));
+    }
+    DeepFoldParam {
+        mult_subgroups,
+        variable_num,
+        query_num,
+    }
+}
+
+/// The outputs of the verifier circuit on the proof.
+fn evaluate(
+    vc: &VerifierCircuit,
+    claimed_v: &[Fr],
+    proof: &Proof,
+) -> Result<Vec<Fr>, VerificationError> {
+    let mut circuit = vc.circuit.clone();
+    circuit.layers[0].input_vals.evals = vc.witness(claimed_v, proof)?;
+    circuit.evaluate();
+    Ok(circuit.layers.last().unwrap().output_vals.evals.clone())
+}
+
+#[test]
+fn test_verifier_circuit() {
+    let config = mimc7_config();
+    let mut circuit = gen_target_circuit();
+    circuit.layers[0].input_vals.evals = (1..=4u32).map(Fr::from).collect();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+
+    let vc = VerifierCircuit::new(&circuit, &config).unwrap();
+    let outputs = evaluate(&vc, &claimed_v, &proof).unwrap();
+    assert!(outputs.iter().all(|v| v.is_zero()));
+
+    // the verification is itself proven, its outputs claimed to be zero
+    let mut verifier_circuit = vc.circuit.clone();
+    verifier_circuit.layers[0].input_vals.evals = vc.witness(&claimed_v, &proof).unwrap();
+    verifier_circuit.evaluate();
+    let outer_config = Config::bn254_config();
+    let mut outer_prover = Prover::<_, RawCommitmentProver<_>>::new(&outer_config, ());
+    outer_prover.prepare_mem(&verifier_circuit);
+    let (outer_claimed_v, outer_proof) = outer_prover.prove(&verifier_circuit);
+    assert!(outer_claimed_v.iter().all(|v| v.is_zero()));
+    let outer_verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&outer_config, ());
+    assert!(outer_verifier
+        .verify(&verifier_circuit, &outer_claimed_v, &outer_proof)
+        .is_ok());
+
+    // another claimed output
+    let mut bad_claimed_v = claimed_v.clone();
+    bad_claimed_v[0] += Fr::one();
+    let outputs = evaluate(&vc, &bad_claimed_v, &proof).unwrap();
+    assert!(outputs.iter().any(|v| !v.is_zero()));
+
+    // another first message of the sumcheck of the output layer
+    let mut bad_proof = proof.clone();
+    let offset = (1 << circuit.log_input_size()) * Fr::SIZE + GRINDING_NONCE_BYTES;
+    let p0 = Fr::deserialize_from(&bad_proof.bytes[offset..]) + Fr::one();
+    p0.serialize_into(&mut bad_proof.bytes[offset..offset + Fr::SIZE]);
+    assert!(verifier.verify(&circuit, &claimed_v, &bad_proof).is_err());
+    let outputs = evaluate(&vc, &claimed_v, &bad_proof).unwrap();
+    assert!(outputs.iter().any(|v| !v.is_zero()));
+
+    let mut short_proof = proof.clone();
+    short_proof.bytes.pop();
+    assert_eq!(
+        vc.witness(&claimed_v, &short_proof),
+        Err(VerificationError::TruncatedProof)
+    );
+}
+
+#[test]
+fn test_verifier_circuit_unsupported_config() {
+    let circuit = gen_target_circuit();
+    assert!(matches!(
+        VerifierCircuit::new(&circuit, &Config::bn254_config()),
+        Err(VerificationError::InvalidStatement(_))
+    ));
+    let mut config = mimc7_config();
+    config.zero_knowledge = true;
+    assert!(matches!(
+        VerifierCircuit::new(&circuit, &config),
+        Err(VerificationError::InvalidStatement(_))
+    ));
+}
+
+#[test]
+fn test_verifier_circuit_grinding() {
+    let mut config = mimc7_config();
+    config.grinding_bits = 10;
+    let mut circuit = gen_target_circuit();
+    circuit.layers[0].input_vals.evals = (1..=4u32).map(Fr::from).collect();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let vc = VerifierCircuit::new(&circuit, &config).unwrap();
+    let outputs = evaluate(&vc, &claimed_v, &proof).unwrap();
+    assert!(outputs.iter().all(|v| v.is_zero()));
+
+    // the proof without grinding, nonce 0, only differs by its proof of work
+    let mut no_grinding_config = config.clone();
+    no_grinding_config.grinding_bits = 0;
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&no_grinding_config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert_eq!(
+        verifier.verify(&circuit, &claimed_v, &proof),
+        Err(VerificationError::ProofOfWork)
+    );
+    let outputs = evaluate(&vc, &claimed_v, &proof).unwrap();
+    assert!(outputs.iter().any(|v| !v.is_zero()));
+    let no_grinding_vc = VerifierCircuit::new(&circuit, &no_grinding_config).unwrap();
+    let outputs = evaluate(&no_grinding_vc, &claimed_v, &proof).unwrap();
+    assert!(outputs.iter().all(|v| v.is_zero()));
+}
+
+#[test]
+fn test_verifier_circuit_rejects_deepfold() {
+    let config = mimc7_config();
+    let mut circuit = gen_target_circuit();
+    circuit.layers[0].input_vals.evals = (1..=4u32).map(Fr::from).collect();
+    circuit.evaluate();
+
+    let pp = deepfold_param(circuit.log_input_size(), 30);
+    let mut prover = Prover::<_, DeepFoldProver<_>>::new(&config, pp);
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+
+    // the proof is read as a raw one, its merkle root as the input
+    let vc = VerifierCircuit::new(&circuit, &config).unwrap();
+    assert!(evaluate(&vc, &claimed_v, &proof)
+        .map_or(true, |outputs| outputs.iter().any(|v| !v.is_zero())));
+}
+
+#[test]
+fn test_verifier_circuit_mimc7_deepfold() {
+    let config = mimc7_config();
+    let mut circuit = gen_target_circuit();
+    circuit.layers[0].input_vals.evals = (1..=4u32).map(Fr::from).collect();
+    circuit.evaluate();
+
+    let pp = deepfold_param(circuit.log_input_size(), 4);
+    let mut prover = Prover::<_, MiMC7DeepFoldProver>::new(&config, pp.clone());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let verifier = Verifier::<_, MiMC7DeepFoldVerifier>::new(&config, pp.clone());
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+
+    let commitment = RecursiveCommitment::MiMC7DeepFold(pp.clone());
+    let vc = VerifierCircuit::with_commitment(&circuit, &config, commitment).unwrap();
+    let outputs = evaluate(&vc, &claimed_v, &proof).unwrap();
+    assert!(outputs.iter().all(|v| v.is_zero()));
+
+    // another last sibling on the path of the last query of the last round
+    let mut bad_proof = proof.clone();
+    let offset = bad_proof.bytes.len() - Fr::SIZE;
+    let sibling = Fr::deserialize_from(&bad_proof.bytes[offset..]) + Fr::one();
+    sibling.serialize_into(&mut bad_proof.bytes[offset..]);
+    assert_eq!(
+        verifier.verify(&circuit, &claimed_v, &bad_proof),
+        Err(VerificationError::MerklePath { round: 1 })
+    );
+    let outputs = evaluate(&vc, &claimed_v, &bad_proof).unwrap();
+    assert!(outputs.iter().any(|v| !v.is_zero()));
+
+    // another claimed output, whose opening fails
+    let mut bad_claimed_v = claimed_v.clone();
+    bad_claimed_v[0] += Fr::one();
+    assert!(verifier.verify(&circuit, &bad_claimed_v, &proof).is_err());
+    let outputs = evaluate(&vc, &bad_claimed_v, &proof).unwrap();
+    assert!(outputs.iter().any(|v| !v.is_zero()));
+
+    let other_pp = deepfold_param(circuit.log_input_size() + 1, 4);
+    assert!(matches!(
+        VerifierCircuit::with_commitment(
+            &circuit,
+            &config,
+            RecursiveCommitment::MiMC7DeepFold(other_pp)
+        ),
+        Err(VerificationError::InvalidStatement(_))
+    ));
+}
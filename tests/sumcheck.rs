@@ -0,0 +1,97 @@
+use arith::{Field, FieldSerde, M31Ext3, MultiLinearPoly};
+use expander_rs::{
+    interpolate_uni_poly, sumcheck_prove, sumcheck_verify, Transcript, VerificationError,
+    VirtualPolynomial,
+};
+use halo2curves::bn256::Fr;
+
+fn test_interpolate_uni_poly_helper<F: Field>() {
+    let mut rng = rand::thread_rng();
+    // binom(d, i) exceeds u32 from d = 34 on
+    for degree in (0..6).chain([34, 40]) {
+        let coefs: Vec<F> = (0..=degree).map(|_| F::random_unsafe(&mut rng)).collect();
+        let eval = |x: F| coefs.iter().rev().fold(F::zero(), |acc, c| acc * x + c);
+        let evals: Vec<F> = (0..=degree).map(|i| eval(F::from(i as u32))).collect();
+        let x = F::random_unsafe(&mut rng);
+        assert_eq!(interpolate_uni_poly(&evals, x), eval(x));
+    }
+}
+
+#[test]
+fn test_interpolate_uni_poly() {
+    test_interpolate_uni_poly_helper::<M31Ext3>();
+    test_interpolate_uni_poly_helper::<Fr>();
+}
+
+fn test_sumcheck_helper<F: Field + FieldSerde>() {
+    let mut rng = rand::thread_rng();
+    let var_num = 6;
+    let mut poly = VirtualPolynomial::<F>::new(var_num);
+    let mles: Vec<usize> = (0..4)
+        .map(|_| {
+            poly.add_mle(
+                (0..1 << var_num)
+                    .map(|_| F::random_unsafe(&mut rng))
+                    .collect(),
+            )
+        })
+        .collect();
+    // a * b * c + 3 * d + 5 * b * d
+    poly.add_product(F::one(), vec![mles[0], mles[1], mles[2]]);
+    poly.add_product(F::from(3), vec![mles[3]]);
+    poly.add_product(F::from(5), vec![mles[1], mles[3]]);
+    assert_eq!(poly.degree(), 3);
+
+    let sum = poly.sum();
+    let mut transcript = Transcript::new();
+    let (point, mle_evals) = sumcheck_prove(poly.clone(), &mut transcript);
+    for (mle, eval) in poly.mles.iter().zip(&mle_evals) {
+        assert_eq!(MultiLinearPoly::eval_multilinear_ext(mle, &point), *eval);
+    }
+    let expected = mle_evals[0] * mle_evals[1] * mle_evals[2]
+        + F::from(3) * mle_evals[3]
+        + F::from(5) * mle_evals[1] * mle_evals[3];
+
+    let mut proof = transcript.proof;
+    let mut transcript = Transcript::new();
+    let (verifier_point, claimed_eval) = sumcheck_verify(
+        sum,
+        var_num,
+        poly.degree(),
+        &mut transcript,
+        &mut proof.clone(),
+    )
+    .unwrap();
+    assert_eq!(verifier_point, point);
+    assert_eq!(claimed_eval, expected);
+
+    let mut transcript = Transcript::new();
+    let res = sumcheck_verify(
+        sum + F::one(),
+        var_num,
+        3,
+        &mut transcript,
+        &mut proof.clone(),
+    );
+    assert_eq!(res, Err(VerificationError::Sumcheck { round: 0 }));
+
+    // a tampered round message changes the claimed evaluation, or the rounds
+    proof.bytes[F::SIZE] ^= 1;
+    let mut transcript = Transcript::new();
+    match sumcheck_verify(sum, var_num, 3, &mut transcript, &mut proof.clone()) {
+        Ok((_, claimed_eval)) => assert_ne!(claimed_eval, expected),
+        Err(e) => assert!(matches!(e, VerificationError::Sumcheck { .. })),
+    }
+
+    // a truncated proof
+    proof.bytes.pop();
+    let mut transcript = Transcript::new();
+    let res = sumcheck_verify(sum, var_num, 3, &mut transcript, &mut proof);
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_sumcheck() {
+    test_sumcheck_helper::<M31Ext3>();
+    test_sumcheck_helper::<Fr>();
+}
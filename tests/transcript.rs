@@ -0,0 +1,204 @@
+use arith::{Field, FieldSerde, M31};
+use expander_rs::{
+    bytes_to_fr_chunks, first_divergence, poseidon_hash, poseidon_params,
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    Circuit, Config, FiatShamirHashType, Keccak256hasher, PoseidonHasher, Prover, TranscriptEvent,
+    TranscriptLabel, TranscriptPhase, VerificationError, Verifier,
+};
+use halo2curves::bn256::Fr;
+
+const FILENAME_MUL: &str = "data/ExtractedCircuitMul.txt";
+const FILENAME_ADD: &str = "data/ExtractedCircuitAdd.txt";
+
+#[test]
+fn test_transcript_recording() {
+    let config = Config::m31_config();
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof, recording) = prover.prove_recorded(&circuit);
+    let (claimed, _) = prover.prove(&circuit);
+    assert_eq!(claimed, claimed_v);
+
+    // the appends cover the proof, in order
+    let mut end = 0;
+    for event in &recording.events {
+        if let TranscriptEvent::Append { range, bytes, .. } = event {
+            assert_eq!(range.start, end);
+            assert_eq!(bytes.as_slice(), &proof.bytes[range.clone()]);
+            end = range.end;
+        }
+    }
+    assert_eq!(end, proof.bytes.len());
+    assert_eq!(
+        recording.events[0].label().phase,
+        TranscriptPhase::Commitment
+    );
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    let (result, verifier_recording) = verifier.verify_recorded(&circuit, &claimed_v, &proof);
+    assert!(result.is_ok());
+    assert_eq!(verifier_recording, recording);
+    assert_eq!(first_divergence(&recording, &verifier_recording), None);
+
+    // a tampered message of the second round of the output layer
+    let label = TranscriptLabel {
+        phase: TranscriptPhase::Layer(circuit.layers.len() - 1),
+        round: 1,
+    };
+    let (index, range) = recording
+        .events
+        .iter()
+        .enumerate()
+        .find_map(|(i, event)| match event {
+            TranscriptEvent::Append {
+                label: l, range, ..
+            } if *l == label => Some((i, range.clone())),
+            _ => None,
+        })
+        .unwrap();
+    let mut bad_proof = proof.clone();
+    bad_proof.bytes[range.start] ^= 1;
+    let (result, verifier_recording) = verifier.verify_recorded(&circuit, &claimed_v, &bad_proof);
+    assert!(result.is_err());
+    let divergence = first_divergence(&recording, &verifier_recording).unwrap();
+    assert_eq!(divergence.index, index);
+    assert_eq!(divergence.label(), label);
+    assert!(divergence.to_string().contains("round 1"));
+
+    // a verifier hashing the transcript with another hash diverges at its first challenge
+    let mut other_config = config.clone();
+    other_config.fs_hash = FiatShamirHashType::MIMC7;
+    let other_verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&other_config, ());
+    let (result, verifier_recording) = other_verifier.verify_recorded(&circuit, &claimed_v, &proof);
+    assert!(result.is_err());
+    let divergence = first_divergence(&recording, &verifier_recording).unwrap();
+    assert_eq!(divergence.index, 1);
+    assert_eq!(divergence.label().phase, TranscriptPhase::ProofOfWork);
+}
+
+#[test]
+fn test_keccak_transcript() {
+    let keccak256 = |input: &[u8]| -> String {
+        let mut output = [0u8; 32];
+        Keccak256hasher.hash(&mut output, input, input.len());
+        output.iter().map(|b| format!("{:02x}", b)).collect()
+    };
+    assert_eq!(
+        keccak256(b""),
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    assert_eq!(
+        keccak256(b"abc"),
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+    );
+    assert_eq!(
+        keccak256(b"The quick brown fox jumps over the lazy dog"),
+        "4d741b6f1eb29cb2a9b9911c82f56fa8d73b04959d3d9d222895df6c0b28aa15"
+    );
+
+    let sha256_config = Config::m31_config();
+    let mut keccak_config = sha256_config.clone();
+    keccak_config.fs_hash = FiatShamirHashType::Keccak256;
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&keccak_config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof, recording) = prover.prove_recorded(&circuit);
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&keccak_config, ());
+    let (result, verifier_recording) = verifier.verify_recorded(&circuit, &claimed_v, &proof);
+    assert!(result.is_ok());
+    assert_eq!(first_divergence(&recording, &verifier_recording), None);
+    let sha256_verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&sha256_config, ());
+    assert!(sha256_verifier
+        .verify(&circuit, &claimed_v, &proof)
+        .is_err());
+
+    // the same commitment, then other challenges
+    let mut sha256_prover = Prover::<_, RawCommitmentProver<_>>::new(&sha256_config, ());
+    sha256_prover.prepare_mem(&circuit);
+    let (_, sha256_proof, sha256_recording) = sha256_prover.prove_recorded(&circuit);
+    assert_ne!(sha256_proof.bytes, proof.bytes);
+    let divergence = first_divergence(&sha256_recording, &recording).unwrap();
+    assert_eq!(divergence.index, 1);
+    assert_eq!(divergence.label().phase, TranscriptPhase::ProofOfWork);
+}
+
+#[test]
+fn test_poseidon_transcript() {
+    // big-endian, as circomlib prints them
+    let hex = |f: Fr| -> String {
+        let mut bytes = [0u8; 32];
+        f.serialize_into(&mut bytes);
+        bytes.iter().rev().map(|b| format!("{:02x}", b)).collect()
+    };
+    assert_eq!(
+        hex(poseidon_params().round_constants[0]),
+        "0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e"
+    );
+    assert_eq!(
+        hex(poseidon_hash([Fr::from(1u32), Fr::from(2u32)])),
+        "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+    );
+    assert_eq!(
+        hex(poseidon_hash([Fr::from(0u32), Fr::from(0u32)])),
+        "2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864"
+    );
+
+    // bytes that are no canonical element are reduced by their 32-byte chunks, not by the
+    // encoding of the byte messages of the Poseidon transcript
+    let unreduced = [0xffu8; 32];
+    let mut reduced = [0u8; 32];
+    Fr::from_uniform_bytes(&unreduced).serialize_into(&mut reduced);
+    assert_eq!(bytes_to_fr_chunks(&unreduced), bytes_to_fr_chunks(&reduced));
+    assert_ne!(
+        PoseidonHasher::bytes_to_elements(&unreduced),
+        PoseidonHasher::bytes_to_elements(&reduced)
+    );
+    assert_ne!(
+        PoseidonHasher::bytes_to_elements(&[0; 8]),
+        PoseidonHasher::bytes_to_elements(&[0; 9])
+    );
+
+    let sha256_config = Config::bn254_config();
+    let mut poseidon_config = sha256_config.clone();
+    poseidon_config.fs_hash = FiatShamirHashType::Poseidon;
+    let mut circuit = Circuit::<Fr>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&poseidon_config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof, recording) = prover.prove_recorded(&circuit);
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&poseidon_config, ());
+    let (result, verifier_recording) = verifier.verify_recorded(&circuit, &claimed_v, &proof);
+    assert!(result.is_ok());
+    assert_eq!(first_divergence(&recording, &verifier_recording), None);
+    let sha256_verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&sha256_config, ());
+    assert!(sha256_verifier
+        .verify(&circuit, &claimed_v, &proof)
+        .is_err());
+}
+
+#[test]
+fn test_poseidon_transcript_bn254_only() {
+    let mut config = Config::m31_config();
+    config.fs_hash = FiatShamirHashType::Poseidon;
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&Config::m31_config(), ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(matches!(
+        verifier.verify(&circuit, &claimed_v, &proof),
+        Err(VerificationError::InvalidStatement(_))
+    ));
+}
@@ -0,0 +1,134 @@
+use arith::{mul_group::Radix2Group, Field, FieldSerde, M31};
+use expander_rs::{
+    deepfold::{DeepFoldParam, DeepFoldProver, DeepFoldVerifier},
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    Circuit, Config, Proof, Prover, VerificationError, Verifier, GRINDING_NONCE_BYTES,
+};
+use halo2curves::bn256::Fr;
+use rand::Rng;
+
+const FILENAME_MUL: &str = "data/ExtractedCircuitMul.txt";
+const FILENAME_ADD: &str = "data/ExtractedCircuitAdd.txt";
+
+fn load_circuit<F: Field + FieldSerde>() -> Circuit<F> {
+    let mut circuit = Circuit::<F>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+    circuit
+}
+
+fn with_bytes(proof: &Proof, bytes: Vec<u8>) -> Proof {
+    let mut proof = proof.clone();
+    proof.bytes = bytes;
+    proof
+}
+
+#[test]
+fn test_verification_errors() {
+    let config = Config::m31_config();
+    let circuit = load_circuit::<M31>();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert_eq!(verifier.verify(&circuit, &claimed_v, &proof), Ok(()));
+
+    // every prefix of the proof is rejected without panicking
+    for len in (0..proof.bytes.len())
+        .step_by(97)
+        .chain([proof.bytes.len() - 1])
+    {
+        let truncated = with_bytes(&proof, proof.bytes[..len].to_vec());
+        assert!(verifier.verify(&circuit, &claimed_v, &truncated).is_err());
+    }
+    let truncated = with_bytes(&proof, proof.bytes[..proof.bytes.len() - 1].to_vec());
+    assert_eq!(
+        verifier.verify(&circuit, &claimed_v, &truncated),
+        Err(VerificationError::TruncatedProof)
+    );
+
+    let mut extended = proof.bytes.clone();
+    extended.push(0);
+    assert_eq!(
+        verifier.verify(&circuit, &claimed_v, &with_bytes(&proof, extended)),
+        Err(VerificationError::TrailingBytes(1))
+    );
+
+    // the sumcheck of the output layer is verified first
+    let commitment_size = (1 << circuit.log_input_size()) * M31::SIZE;
+    let first_message = commitment_size + GRINDING_NONCE_BYTES;
+    let mut tampered = proof.bytes.clone();
+    tampered[first_message] ^= 1;
+    assert_eq!(
+        verifier.verify(&circuit, &claimed_v, &with_bytes(&proof, tampered)),
+        Err(VerificationError::LayerSumcheck {
+            layer: circuit.layers.len() - 1,
+            round: 0
+        })
+    );
+
+    // a nonce found for another commitment has too few leading zero bits, but for the odd
+    // chance of one in 2^grinding_bits
+    let mut other_commitment = proof.bytes.clone();
+    other_commitment[0] ^= 1;
+    let result = verifier.verify(&circuit, &claimed_v, &with_bytes(&proof, other_commitment));
+    assert!(result.is_err());
+    assert!(
+        result == Err(VerificationError::ProofOfWork)
+            || matches!(result, Err(VerificationError::LayerSumcheck { .. }))
+    );
+
+    let mut wrong_claimed_v = claimed_v.clone();
+    wrong_claimed_v.pop();
+    assert!(matches!(
+        verifier.verify(&circuit, &wrong_claimed_v, &proof),
+        Err(VerificationError::InvalidStatement(_))
+    ));
+
+    assert!(matches!(
+        verifier.verify_batch(&[], &[], &proof),
+        Err(VerificationError::InvalidStatement(_))
+    ));
+}
+
+#[test]
+fn test_deepfold_malformed_proofs() {
+    let config = Config::bn254_config();
+    let circuit = load_circuit::<Fr>();
+
+    let variable_num = circuit.log_input_size();
+    let mut mult_subgroups = vec![Radix2Group::<Fr>::new(variable_num as u32 + 3)];
+    for i in 1..variable_num {
+        mult_subgroups.push(mult_subgroups[i - 1].exp(2));
+    }
+    let pp = DeepFoldParam {
+        mult_subgroups,
+        variable_num,
+        query_num: 30,
+    };
+
+    let mut prover = Prover::<_, DeepFoldProver<_>>::new(&config, pp.clone());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let verifier = Verifier::<_, DeepFoldVerifier<_>>::new(&config, pp);
+    assert!(verifier.verify(&circuit, &claimed_v, &proof).is_ok());
+
+    // random flips anywhere in the proof, the merkle paths included, are rejected
+    let rng = &mut rand::thread_rng();
+    for _ in 0..64 {
+        let mut bad_proof = proof.bytes.clone();
+        bad_proof[rng.gen_range(0..proof.bytes.len())] ^= rng.gen_range(1..=255u8);
+        assert!(verifier
+            .verify(&circuit, &claimed_v, &with_bytes(&proof, bad_proof))
+            .is_err());
+    }
+
+    // the last bytes of the proof are the merkle paths of the last queries
+    let mut bad_path = proof.bytes.clone();
+    *bad_path.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+        verifier.verify(&circuit, &claimed_v, &with_bytes(&proof, bad_path)),
+        Err(VerificationError::MerklePath { .. } | VerificationError::PolyCommitOpening)
+    ));
+}
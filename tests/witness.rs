@@ -0,0 +1,150 @@
+use arith::{Field, M31Ext3, M31};
+use expander_rs::{
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    Circuit, Config, LookupColumn, Prover, ProvingKey, Verifier, VerifyingKey, Wiring,
+};
+
+const FILENAME_MUL: &str = "data/ExtractedCircuitMul.txt";
+const FILENAME_ADD: &str = "data/ExtractedCircuitAdd.txt";
+
+#[test]
+fn test_witness_proof() {
+    let config = Config::m31_config();
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    let input = circuit.layers[0].input_vals.evals.clone();
+    circuit.evaluate();
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+
+    // the key holds no values
+    let pk = ProvingKey::new(circuit.clone());
+    assert!(pk
+        .circuit()
+        .layers
+        .iter()
+        .all(|layer| layer.input_vals.evals.is_empty() && layer.output_vals.evals.is_empty()));
+
+    // the same proof as from the evaluated circuit
+    for interval in [1, 3] {
+        let witness = pk.evaluate_checkpointed(input.clone(), interval);
+        assert_eq!(witness.input(), input.as_slice());
+        assert_eq!(
+            witness.output,
+            circuit.layers.last().unwrap().output_vals.evals
+        );
+        let (key_claimed_v, key_proof) = prover.prove_with_key(&pk, &witness);
+        assert_eq!(key_claimed_v, claimed_v);
+        assert_eq!(key_proof.bytes, proof.bytes);
+    }
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier
+        .verify_with_key(&pk.verifying_key(), &claimed_v, &proof)
+        .is_ok());
+}
+
+#[test]
+fn test_concurrent_witness_proofs() {
+    let config = Config::m31_config();
+    let circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    let pk = ProvingKey::new(circuit);
+    let input_num = 1 << pk.circuit().log_input_size();
+
+    // every thread proves its own witness of the shared circuit
+    let proofs: Vec<_> = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..4u32)
+            .map(|k| {
+                let pk = pk.clone();
+                let config = &config;
+                s.spawn(move || {
+                    let input = (0..input_num)
+                        .map(|i| M31::from((i as u32 + k) % 2))
+                        .collect();
+                    let witness = pk.evaluate(input);
+                    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(config, ());
+                    prover.prepare_mem(pk.circuit());
+                    prover.prove_with_key(&pk, &witness)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let vk = pk.verifying_key();
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    for (claimed_v, proof) in &proofs {
+        assert!(verifier.verify_with_key(&vk, claimed_v, proof).is_ok());
+    }
+    assert_ne!(proofs[0].1.bytes, proofs[1].1.bytes);
+
+    let mut bad_claimed_v = proofs[0].0.clone();
+    bad_claimed_v[0] += <M31 as Field>::ChallengeField::one();
+    assert!(verifier
+        .verify_with_key(&vk, &bad_claimed_v, &proofs[0].1)
+        .is_err());
+}
+
+#[test]
+fn test_verifying_key() {
+    let config = Config::m31_config();
+    let mut circuit = Circuit::<M31>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+    circuit.lookups = vec![LookupColumn {
+        input_ids: (0..1 << circuit.log_input_size()).collect(),
+        table: vec![M31::zero(), M31::one()],
+    }];
+
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&config, ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+
+    // the key is read back without the circuit file
+    let vk = VerifyingKey::new(&circuit);
+    let bytes = vk.to_bytes();
+    let vk = VerifyingKey::<M31>::from_bytes(&bytes).unwrap();
+    assert_eq!(vk.to_bytes(), bytes);
+    assert_eq!(vk.lookups(), circuit.lookups.as_slice());
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&config, ());
+    assert!(verifier.verify_with_key(&vk, &claimed_v, &proof).is_ok());
+    let mut bad_claimed_v = claimed_v.clone();
+    bad_claimed_v[0] += M31Ext3::one();
+    assert!(verifier
+        .verify_with_key(&vk, &bad_claimed_v, &proof)
+        .is_err());
+
+    assert!(VerifyingKey::<M31>::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    assert!(VerifyingKey::<M31>::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_none());
+
+    // the sorted gates evaluate the same wiring predicates as the gates of the circuit
+    let mut rng = rand::thread_rng();
+    let mut random_point =
+        |n: usize| -> Vec<M31Ext3> { (0..n).map(|_| M31Ext3::random_unsafe(&mut rng)).collect() };
+    for (layer, sorted) in circuit.layers.iter().zip(vk.layers()) {
+        assert_eq!(
+            sorted.gate_num(),
+            layer.mul.len() + layer.add.len() + layer.const_.len()
+        );
+        let (gates, sorted) = (Wiring::Gates(layer), Wiring::Sorted(sorted));
+        let rz0 = random_point(layer.output_var_num);
+        let rz1 = random_point(layer.output_var_num);
+        let rx = random_point(layer.input_var_num);
+        let ry = random_point(layer.input_var_num);
+        let (alpha, beta) = (M31Ext3::from(3), M31Ext3::from(5));
+        assert_eq!(
+            gates.eval_const(&rz0, &rz1, alpha, beta),
+            sorted.eval_const(&rz0, &rz1, alpha, beta)
+        );
+        assert_eq!(
+            gates.eval_add(&rz0, &rz1, alpha, beta, &rx),
+            sorted.eval_add(&rz0, &rz1, alpha, beta, &rx)
+        );
+        assert_eq!(
+            gates.eval_mul(&rz0, &rz1, alpha, beta, &rx, &ry),
+            sorted.eval_mul(&rz0, &rz1, alpha, beta, &rx, &ry)
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use arith::mul_group::Radix2Group;
+use expander_rs::{
+    deepfold::{hiding_variable_num, DeepFoldParam, HidingDeepFoldProver, HidingDeepFoldVerifier},
+    raw::{RawCommitmentProver, RawCommitmentVerifier},
+    BristolCircuit, Circuit, Config, Prover, VerificationError, Verifier, ZkLayout,
+};
+use halo2curves::bn256::Fr;
+
+const FILENAME_MUL: &str = "data/ExtractedCircuitMul.txt";
+const FILENAME_ADD: &str = "data/ExtractedCircuitAdd.txt";
+
+/// 2-bit adder: inputs a (wires 0..2) and b (wires 2..4), outputs the carry then the sum bits.
+const ADDER_2: &str = "9 13
+2 2 2
+1 3
+
+2 1 0 2 4 XOR
+2 1 0 2 5 AND
+2 1 1 3 6 XOR
+2 1 6 5 7 XOR
+2 1 1 3 8 AND
+2 1 5 6 9 AND
+2 1 8 9 10 XOR
+1 1 4 11 EQW
+1 1 7 12 EQW
+";
+
+fn zk_config() -> Config {
+    let mut config = Config::bn254_config();
+    config.zero_knowledge = true;
+    config
+}
+
+fn hiding_param(circuit: &Circuit<Fr>, config: &Config) -> DeepFoldParam<Fr> {
+    let reps = config.for_challenge_field::<Fr>().get_num_repetitions();
+    let query_num = 30;
+    let variable_num = hiding_variable_num(ZkLayout::new(circuit, reps).var_num, query_num);
+    let mut mult_subgroups = vec![Radix2Group::<Fr>::new(variable_num as u32 + 3)];
+    for i in 1..variable_num {
+        mult_subgroups.push(mult_subgroups[i - 1].exp(2));
+    }
+    DeepFoldParam {
+        mult_subgroups,
+        variable_num,
+        query_num,
+    }
+}
+
+fn adder() -> Circuit<Fr> {
+    let mut adder = BristolCircuit::parse(ADDER_2).unwrap().to_circuit::<Fr>();
+    adder.set_bool_input(&[true, false, true, true]);
+    adder.evaluate();
+    adder
+}
+
+fn check_zero_knowledge_proof(circuit: &Circuit<Fr>) {
+    let config = zk_config();
+    let pp = hiding_param(circuit, &config);
+    let mut prover = Prover::<_, HidingDeepFoldProver<_>>::new(&config, pp.clone());
+    prover.prepare_mem(circuit);
+    let (claimed_v, proof) = prover.prove(circuit);
+
+    let verifier = Verifier::<_, HidingDeepFoldVerifier<_>>::new(&config, pp);
+    assert_eq!(verifier.verify(circuit, &claimed_v, &proof), Ok(()));
+
+    let mut bad_claimed_v = claimed_v.clone();
+    bad_claimed_v[0] += Fr::one();
+    assert!(verifier.verify(circuit, &bad_claimed_v, &proof).is_err());
+
+    // every part of the proof is checked: the commitment, the masked sumchecks, the merge and
+    // the opening
+    for at in [
+        0,
+        proof.bytes.len() / 4,
+        proof.bytes.len() / 2,
+        proof.bytes.len() - 1,
+    ] {
+        let mut bad_proof = proof.clone();
+        bad_proof.bytes[at] ^= 1;
+        assert!(verifier.verify(circuit, &claimed_v, &bad_proof).is_err());
+    }
+}
+
+#[test]
+fn test_zero_knowledge_proof() {
+    check_zero_knowledge_proof(&adder());
+
+    let mut circuit = Circuit::<Fr>::load_extracted_gates(FILENAME_MUL, FILENAME_ADD);
+    circuit.set_random_bool_input_for_test();
+    circuit.evaluate();
+    check_zero_knowledge_proof(&circuit);
+}
+
+#[test]
+fn test_zero_knowledge_proofs_are_randomized() {
+    let circuit = adder();
+    let config = zk_config();
+    let pp = hiding_param(&circuit, &config);
+    let mut prover = Prover::<_, HidingDeepFoldProver<_>>::new(&config, pp.clone());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+    let (other_claimed_v, other_proof) = prover.prove(&circuit);
+    assert_eq!(claimed_v, other_claimed_v);
+    // the commitment differs, and so do the claims on the layer inputs
+    assert_ne!(proof.bytes[..32], other_proof.bytes[..32]);
+    assert_ne!(proof.bytes, other_proof.bytes);
+
+    let verifier = Verifier::<_, HidingDeepFoldVerifier<_>>::new(&config, pp);
+    assert_eq!(verifier.verify(&circuit, &claimed_v, &proof), Ok(()));
+    assert_eq!(verifier.verify(&circuit, &claimed_v, &other_proof), Ok(()));
+}
+
+#[test]
+fn test_zero_knowledge_rejects_raw_commitment() {
+    let circuit = adder();
+    let mut prover = Prover::<_, RawCommitmentProver<_>>::new(&Config::bn254_config(), ());
+    prover.prepare_mem(&circuit);
+    let (claimed_v, proof) = prover.prove(&circuit);
+
+    let verifier = Verifier::<_, RawCommitmentVerifier<_>>::new(&zk_config(), ());
+    assert!(matches!(
+        verifier.verify(&circuit, &claimed_v, &proof),
+        Err(VerificationError::InvalidStatement(_))
+    ));
+}
+
+#[test]
+#[should_panic(expected = "hiding commitment")]
+fn test_zero_knowledge_prover_rejects_raw_commitment() {
+    Prover::<Fr, RawCommitmentProver<_>>::new(&zk_config(), ());
+}